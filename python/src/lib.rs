@@ -0,0 +1,97 @@
+//! # Python bindings
+//!
+//! The python bindings expose the parser and the renderers of the changelog,
+//! so that release tooling written in python uses the same logic as the
+//! binary instead of parsing its output.
+//!
+//! ```python
+//! import git_changelog
+//!
+//! changelog = git_changelog.parse("changelog.toml")
+//! changelog["repositories"][0]["tags"].pop()
+//!
+//! print(git_changelog.render(changelog, "markdown"))
+//! ```
+use std::{convert::TryFrom, error::Error, path::PathBuf, rc::Rc};
+
+use git_changelog::{conf::Configuration, parser::Changelog, renderer::Registry};
+use pyo3::{exceptions::PyRuntimeError, prelude::*};
+
+fn error(err: Box<dyn Error + Send + Sync>) -> PyErr {
+    PyRuntimeError::new_err(err.to_string())
+}
+
+fn changelog(path: PathBuf) -> Result<Changelog, Box<dyn Error + Send + Sync>> {
+    Changelog::try_from(Rc::new(Configuration::try_from(path)?))
+}
+
+fn to_string(content: Vec<u8>) -> PyResult<String> {
+    String::from_utf8(content)
+        .map_err(|err| PyRuntimeError::new_err(format!("could not decode the content, {}", err)))
+}
+
+/// Generate the changelog using the given configuration file and render it
+/// using the given format
+#[pyfunction]
+#[pyo3(signature = (path, format = "markdown"))]
+fn generate(path: PathBuf, format: &str) -> PyResult<String> {
+    let changelog = changelog(path).map_err(error)?;
+    let (_, content) = Registry::default()
+        .render(format, &changelog)
+        .map_err(error)?;
+
+    to_string(content)
+}
+
+/// Generate the changelog using the given configuration file, returns its
+/// model as python objects
+#[pyfunction]
+fn parse(py: Python<'_>, path: PathBuf) -> PyResult<PyObject> {
+    let changelog = changelog(path).map_err(error)?;
+    let json = serde_json::to_string(&changelog).map_err(|err| {
+        PyRuntimeError::new_err(format!("could not serialize the changelog, {}", err))
+    })?;
+
+    Ok(py.import("json")?.call_method1("loads", (json,))?.unbind())
+}
+
+/// Render the model of a changelog, as returned by parse, using the given
+/// format and the settings held by the model (e.g. language or layout)
+#[pyfunction]
+#[pyo3(signature = (changelog, format = "markdown"))]
+fn render(py: Python<'_>, changelog: PyObject, format: &str) -> PyResult<String> {
+    let json: String = py
+        .import("json")?
+        .call_method1("dumps", (changelog,))?
+        .extract()?;
+    let changelog: Changelog = serde_json::from_str(&json).map_err(|err| {
+        PyRuntimeError::new_err(format!("could not deserialize the changelog, {}", err))
+    })?;
+
+    let (_, content) = Registry::default()
+        .render(format, &changelog)
+        .map_err(error)?;
+
+    to_string(content)
+}
+
+/// Returns the names of the available formats
+#[pyfunction]
+fn formats() -> Vec<String> {
+    Registry::default()
+        .names()
+        .into_iter()
+        .map(String::from)
+        .collect()
+}
+
+#[pymodule]
+#[pyo3(name = "git_changelog")]
+fn bindings(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_function(wrap_pyfunction!(generate, module)?)?;
+    module.add_function(wrap_pyfunction!(parse, module)?)?;
+    module.add_function(wrap_pyfunction!(render, module)?)?;
+    module.add_function(wrap_pyfunction!(formats, module)?)?;
+
+    Ok(())
+}
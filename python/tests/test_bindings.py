@@ -0,0 +1,104 @@
+"""Compare the output of the bindings with the one of the binary.
+
+The module must be built first (e.g. `maturin develop`) and the binary must be
+on the path, or given by the `GIT_CHANGELOG` environment variable:
+
+    python -m unittest discover -s python/tests
+"""
+
+import os
+import shutil
+import subprocess
+import tempfile
+import unittest
+
+import git_changelog
+
+BINARY = os.environ.get("GIT_CHANGELOG", "git-changelog")
+
+EXTENSIONS = {"markdown": "md", "html": "html"}
+
+CONFIGURATION = """\
+language = "fr"
+layout = "kind"
+overview = true
+
+[dates.formats]
+markdown = "%d/%m/%Y"
+
+[kinds]
+feat = "Features"
+fix = "Fix changes"
+
+[[repositories]]
+name = "fixture"
+path = "{path}"
+"""
+
+ENVIRONMENT = {
+    "GIT_AUTHOR_NAME": "Jane Doe",
+    "GIT_AUTHOR_EMAIL": "jane@example.com",
+    "GIT_AUTHOR_DATE": "2024-01-02T12:00:00+00:00",
+    "GIT_COMMITTER_NAME": "Jane Doe",
+    "GIT_COMMITTER_EMAIL": "jane@example.com",
+    "GIT_COMMITTER_DATE": "2024-01-02T12:00:00+00:00",
+}
+
+
+class TestBindings(unittest.TestCase):
+    def setUp(self):
+        self.path = tempfile.mkdtemp()
+        self.addCleanup(shutil.rmtree, self.path)
+
+        self.git("init", "-q")
+        self.git("commit", "-q", "--allow-empty", "-m", "feat: add the api")
+        self.git("tag", "-a", "-m", "v1.0.0", "v1.0.0")
+        self.git("commit", "-q", "--allow-empty", "-m", "fix(api): handle timeouts")
+
+        self.config = os.path.join(self.path, "changelog.toml")
+        with open(self.config, "w", encoding="utf-8") as file:
+            file.write(CONFIGURATION.format(path=self.path))
+
+    def git(self, *args):
+        subprocess.run(
+            ["git", *args],
+            cwd=self.path,
+            env={**os.environ, **ENVIRONMENT},
+            check=True,
+        )
+
+    def generate(self, format):
+        output = os.path.join(self.path, "CHANGELOG." + EXTENSIONS[format])
+        subprocess.run(
+            [BINARY, "-q", "-c", self.config, "-f", format, "-o", output],
+            cwd=self.path,
+            check=True,
+        )
+
+        with open(output, encoding="utf-8") as file:
+            return file.read()
+
+    def test_generate_matches_the_binary(self):
+        for format in ["markdown", "html"]:
+            with self.subTest(format=format):
+                self.assertEqual(
+                    git_changelog.generate(self.config, format),
+                    self.generate(format),
+                )
+
+    def test_render_of_the_parsed_model_matches_the_binary(self):
+        changelog = git_changelog.parse(self.config)
+
+        self.assertEqual(changelog["language"], "fr")
+        self.assertEqual(changelog["layout"], "kind")
+
+        for format in ["markdown", "html"]:
+            with self.subTest(format=format):
+                self.assertEqual(
+                    git_changelog.render(changelog, format),
+                    self.generate(format),
+                )
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,67 @@
+//! # Atomic module
+//!
+//! The atomic module replaces files as a whole, so that a reader never sees
+//! a partially written changelog or cache.
+use std::{
+    error::Error,
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::Path,
+    process,
+};
+
+/// Fill a temporary file of the same directory and rename it to the given
+/// path once synced on disk, so that the previous file is kept whole if the
+/// content could not be written. Symbolic links are followed, special files
+/// (e.g. /dev/stdout) are written in place.
+pub fn replace<F>(path: &Path, fill: F) -> Result<(), Box<dyn Error + Send + Sync>>
+where
+    F: FnOnce(&mut BufWriter<File>) -> Result<(), Box<dyn Error + Send + Sync>>,
+{
+    let path = &fs::canonicalize(path).unwrap_or_else(|_| path.to_owned());
+    if fs::metadata(path).is_ok_and(|metadata| !metadata.is_file()) {
+        let file = File::create(path)
+            .map_err(|err| format!("could not create file '{:?}', {}", path, err))?;
+
+        let mut writer = BufWriter::new(file);
+        fill(&mut writer)?;
+        return writer
+            .flush()
+            .map_err(|err| format!("could not flush content, {}", err).into());
+    }
+
+    let name = path
+        .file_name()
+        .ok_or_else(|| format!("could not create file '{:?}', it is not a file", path))?;
+    let temporary =
+        path.with_file_name(format!(".{}.{}.tmp", name.to_string_lossy(), process::id()));
+
+    let result = (|| {
+        let file = File::create(&temporary)
+            .map_err(|err| format!("could not create file '{:?}', {}", temporary, err))?;
+
+        let mut writer = BufWriter::new(file);
+        fill(&mut writer)?;
+
+        let file = writer
+            .into_inner()
+            .map_err(|err| format!("could not flush content on disk, {}", err))?;
+        file.sync_all()
+            .map_err(|err| format!("could not sync content on disk, {}", err))?;
+
+        // Keep the permissions of the previous file, if any
+        if let Ok(metadata) = fs::metadata(path) {
+            fs::set_permissions(&temporary, metadata.permissions())
+                .map_err(|err| format!("could not set permissions of '{:?}', {}", path, err))?;
+        }
+
+        fs::rename(&temporary, path)
+            .map_err(|err| format!("could not replace file '{:?}', {}", path, err).into())
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&temporary);
+    }
+
+    result
+}
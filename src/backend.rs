@@ -0,0 +1,428 @@
+//! # Backend module
+//!
+//! The backend module supplies the commits and the tags of a repository to
+//! the [`History`](crate::parser::History), which classifies them. The
+//! changelog model does not depend on where they come from, so that other
+//! backends (e.g. gix or mercurial) could be plugged using
+//! [`History::from_backend`](crate::parser::History::from_backend).
+use std::{collections::HashMap, error::Error, path::Path, vec::IntoIter};
+
+use git2 as git;
+use slog_scope::{debug, warn};
+
+use crate::{
+    cache::{Cache, Record},
+    conf,
+    log::Entry,
+    parser::{self, Commit, Diffstat, Filter},
+    verify::Verifier,
+};
+
+/// Commit supplied by a backend, before its classification
+#[derive(Clone, Debug)]
+pub struct Revision {
+    pub commit: Commit,
+    /// Full message of the commit
+    pub message: String,
+    /// Name of the author as recorded, before resolving the .mailmap
+    pub name: String,
+    /// Email of the author as recorded, before resolving the .mailmap
+    pub email: String,
+    /// Tag pointing at the commit, if any
+    pub tag: Option<String>,
+    pub diffstat: Option<Diffstat>,
+    pub submodules: Vec<Submodule>,
+}
+
+/// Path of a submodule updated by a commit, along with its commits brought
+/// by the update, from the oldest
+pub type Submodule = (String, Vec<Commit>);
+
+/// Item supplied by a backend
+#[derive(Clone, Debug)]
+pub enum Read {
+    /// Commit outside of paths, along with its tag which should be kept
+    Outside(Option<String>),
+    /// Commit to classify
+    Revision(Box<Revision>),
+}
+
+/// Source of the commits of a repository, from the oldest one
+pub trait Backend {
+    /// Read the next commit, if any
+    fn read(&mut self) -> Option<Result<Read, Box<dyn Error + Send + Sync>>>;
+
+    /// Called once every commit has been read
+    fn finish(&mut self) {}
+}
+
+/// Repository opened using libgit2
+pub struct Git<'r> {
+    repo: &'r git::Repository,
+    conf: &'r conf::Repository,
+    revwalk: parser::Walk<'r>,
+    tags: HashMap<String, String>,
+    mailmap: Option<git::Mailmap>,
+    cache: Option<Cache>,
+    /// Used to compute the diffstat of the kept commits only
+    filter: Filter<'r>,
+    verifier: Option<Verifier>,
+}
+
+impl<'r> Git<'r> {
+    pub fn open(
+        repo: &'r git::Repository,
+        conf: &'r conf::Repository,
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        // We should build a map(commit-id -> tag) before starting walking over the git commit history.
+        //
+        // The full explanation is here:
+        // https://stackoverflow.com/questions/36528576/get-annotated-tags-from-revwalk-commit/36555358#36555358
+        let mut names = vec![];
+        repo.tag_foreach(|oid, name| {
+            names.push((oid, name.to_vec()));
+            true
+        })
+        .map_err(|err| format!("could not retrieve git tags, {}", err))?;
+
+        let mut tags = HashMap::new();
+        for (oid, name) in names {
+            let name = name.strip_prefix(b"refs/tags/").unwrap_or(&name);
+            let name = parser::decode(name, "tag", &oid.to_string());
+            if let Some(ref prefix) = conf.tag_prefix {
+                if !name.starts_with(prefix.as_str()) {
+                    continue;
+                }
+            }
+
+            let object = repo
+                .find_object(oid, None)
+                .map_err(|err| format!("could not retrieve object for tag, {}", err))?;
+
+            let tag = match object.to_owned().into_tag() {
+                Ok(tag) => tag,
+                Err(_) => {
+                    let mut hash = object.id().to_string();
+                    hash.truncate(7);
+                    warn!("could not cast object into tag"; "hash" => hash);
+                    continue;
+                }
+            };
+
+            tags.insert(tag.target_id().to_string(), name);
+        }
+
+        if conf.submodules && repo.is_bare() {
+            warn!("Submodules are not checked out in a bare repository, they are left out"; "repository" => &conf.name);
+        }
+
+        Ok(Self {
+            repo,
+            conf,
+            revwalk: parser::walk(repo, conf.range.as_deref(), conf.branch.as_deref())?,
+            tags,
+            mailmap: repo.mailmap().ok(),
+            cache: if conf.cache {
+                Some(Cache::open(repo))
+            } else {
+                None
+            },
+            filter: Filter::new(conf)?,
+            verifier: if conf.verify {
+                Some(Verifier::from(repo))
+            } else {
+                None
+            },
+        })
+    }
+
+    /// Read the commit from the repository
+    fn commit(
+        &mut self,
+        oid: Result<git::Oid, git::Error>,
+    ) -> Result<Read, Box<dyn Error + Send + Sync>> {
+        let (repo, conf) = (self.repo, self.conf);
+        let oid = oid.map_err(|err| format!("could not retrieve object identifier, {}", err))?;
+
+        let commit = repo
+            .find_commit(oid)
+            .map_err(|err| format!("could not retrieve commit '{}', {}", oid, err))?;
+
+        let tag = self.tags.get(&oid.to_string()).cloned();
+
+        if let Some(ref paths) = conf.paths {
+            if !parser::touches(repo, &commit, paths)? {
+                debug!("Skip commit outside of paths"; "hash" => oid.to_string());
+
+                // The tag should be kept even if the tagged commit is outside of paths
+                return Ok(Read::Outside(tag));
+            }
+        }
+
+        let author = commit.author();
+        let name = String::from_utf8_lossy(author.name_bytes()).to_string();
+        let email = String::from_utf8_lossy(author.email_bytes()).to_string();
+        let message = parser::newlines(&String::from_utf8_lossy(commit.message_bytes()));
+        let signature = self
+            .mailmap
+            .as_ref()
+            .and_then(|mailmap| commit.author_with_mailmap(mailmap).ok());
+
+        let hash = oid.to_string();
+        let cached = self
+            .cache
+            .as_ref()
+            .and_then(|cache| cache.get(&hash))
+            .cloned();
+
+        // Merges are skipped anyway, their diff against the first parent may be huge
+        let stats =
+            if conf.diffstat && self.filter.check(&commit).is_ok() && commit.parent_count() <= 1 {
+                match cached.as_ref().and_then(|record| record.diffstat) {
+                    Some(stats) => Some(stats),
+                    None => Some(parser::diffstat(repo, &commit)?),
+                }
+            } else {
+                None
+            };
+
+        // Merges bring the updates of the submodules made on the merged branch
+        let submodules = if conf.submodules && !repo.is_bare() && commit.parent_count() <= 1 {
+            self.submodules(&commit)?
+        } else {
+            vec![]
+        };
+
+        let verification = self
+            .verifier
+            .as_ref()
+            .map(|verifier| verifier.commit(repo, &commit));
+
+        let mut commit = match cached
+            .as_ref()
+            .and_then(|record| record.commit(conf, &hash))
+        {
+            Some(commit) => commit,
+            None => Commit::try_from((conf, &commit))
+                .map_err(|err| format!("could not parse commit '{}', {}", oid, err))?,
+        };
+
+        // Records are written again only to store a newly computed diffstat
+        match (self.cache.as_mut(), cached) {
+            (Some(cache), None) => {
+                let mut record = Record::from(&commit);
+                record.diffstat = stats;
+                cache.insert(hash, record);
+            }
+            (Some(cache), Some(mut record)) if record.diffstat.is_none() && stats.is_some() => {
+                record.diffstat = stats;
+                cache.insert(hash, record);
+            }
+            _ => {}
+        }
+
+        commit.verification = verification;
+        if conf.sign_offs {
+            commit.sign_offs = parser::sign_offs(&message)
+                .into_iter()
+                .map(|(name, _)| name)
+                .collect();
+        }
+
+        // Resolve the canonical name and email of the author using the .mailmap
+        if let Some(signature) = signature {
+            if let Some(name) = signature.name() {
+                commit.author = String::from(name);
+            }
+
+            if let Some(email) = signature.email() {
+                commit.email = String::from(email);
+            }
+        }
+
+        Ok(Read::Revision(Box::new(Revision {
+            commit,
+            message,
+            name,
+            email,
+            tag,
+            diffstat: stats,
+            submodules,
+        })))
+    }
+
+    /// Read the commits of the submodules updated by the commit, from the
+    /// previously recorded commit of each one
+    fn submodules(
+        &self,
+        commit: &git::Commit<'_>,
+    ) -> Result<Vec<Submodule>, Box<dyn Error + Send + Sync>> {
+        let mut submodules = vec![];
+        for (path, old, new) in parser::submodules(self.repo, commit)? {
+            let name = path.to_string_lossy().to_string();
+            match self.submodule(&path, old, new) {
+                Ok(commits) => submodules.push((name, commits)),
+                // The submodule may not be checked out, e.g. on a fresh clone
+                Err(err) => {
+                    warn!("could not read the history of the submodule"; "submodule" => name, "hash" => commit.id().to_string(), "error" => err.to_string());
+                }
+            }
+        }
+
+        Ok(submodules)
+    }
+
+    /// Read the commits of the submodule reachable from the new commit but
+    /// not from the old one, from the oldest
+    fn submodule(
+        &self,
+        path: &Path,
+        old: git::Oid,
+        new: git::Oid,
+    ) -> Result<Vec<Commit>, Box<dyn Error + Send + Sync>> {
+        let repo = self
+            .repo
+            .find_submodule(&path.to_string_lossy())
+            .and_then(|submodule| submodule.open())
+            .map_err(|err| format!("could not open submodule, {}", err))?;
+
+        let mut revwalk = repo
+            .revwalk()
+            .map_err(|err| format!("could not create revwalk, {}", err))?;
+
+        revwalk
+            .push(new)
+            .and_then(|_| revwalk.hide(old))
+            .map_err(|err| format!("could not walk from '{}' to '{}', {}", old, new, err))?;
+
+        revwalk
+            .set_sorting(git::Sort::TIME | git::Sort::REVERSE)
+            .map_err(|err| format!("could not sort commits, {}", err))?;
+
+        let mut commits = vec![];
+        for oid in revwalk {
+            let oid =
+                oid.map_err(|err| format!("could not retrieve object identifier, {}", err))?;
+            let commit = repo
+                .find_commit(oid)
+                .map_err(|err| format!("could not retrieve commit '{}', {}", oid, err))?;
+
+            let mut commit = Commit::try_from((self.conf, &commit))
+                .map_err(|err| format!("could not parse commit '{}', {}", oid, err))?;
+
+            commit.submodule = Some(path.to_string_lossy().to_string());
+            commits.push(commit);
+        }
+
+        Ok(commits)
+    }
+}
+
+impl Backend for Git<'_> {
+    fn read(&mut self) -> Option<Result<Read, Box<dyn Error + Send + Sync>>> {
+        let oid = self.revwalk.next()?;
+
+        Some(self.commit(oid))
+    }
+
+    fn finish(&mut self) {
+        // The cache only speeds up the next runs, it should not fail this one
+        if let Some(cache) = self.cache.take() {
+            if let Err(err) = cache.save() {
+                warn!("could not save the cache"; "error" => err.to_string());
+            }
+        }
+    }
+}
+
+/// Entries read from the output of git log, from the oldest
+pub struct Log<'r> {
+    conf: &'r conf::Repository,
+    entries: IntoIter<Entry>,
+}
+
+impl<'r> Log<'r> {
+    pub fn new(entries: Vec<Entry>, conf: &'r conf::Repository) -> Self {
+        Self {
+            conf,
+            entries: entries.into_iter(),
+        }
+    }
+}
+
+impl Backend for Log<'_> {
+    fn read(&mut self) -> Option<Result<Read, Box<dyn Error + Send + Sync>>> {
+        let entry = self.entries.next()?;
+
+        Some(Ok(Read::Revision(Box::new(Revision {
+            commit: Commit::from((self.conf, &entry)),
+            tag: entry.tag(self.conf.tag_prefix.as_deref()),
+            name: entry.author,
+            email: entry.email,
+            message: entry.message,
+            diffstat: None,
+            submodules: vec![],
+        }))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, process};
+
+    use super::*;
+    use crate::cache::FILENAME;
+
+    /// Returns the messages of the commits read from the repository
+    fn messages(repo: &git::Repository, conf: &conf::Repository) -> Vec<String> {
+        let mut backend = Git::open(repo, conf).expect("repository to be opened");
+        let mut messages = vec![];
+        while let Some(read) = backend.read() {
+            if let Read::Revision(revision) = read.expect("commit to be read") {
+                messages.push(revision.commit.message);
+            }
+        }
+
+        backend.finish();
+        messages
+    }
+
+    #[test]
+    fn second_run_reuses_the_cached_records() {
+        let path = env::temp_dir().join(format!("git-changelog-cache-{}", process::id()));
+        let repo = git::Repository::init(&path).unwrap();
+        let signature = git::Signature::now("Jane Doe", "jane@example.com").unwrap();
+        let tree = repo
+            .treebuilder(None)
+            .and_then(|tree| tree.write())
+            .unwrap();
+        let tree = repo.find_tree(tree).unwrap();
+        repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            "feat: add the api",
+            &tree,
+            &[],
+        )
+        .unwrap();
+
+        let conf: conf::Repository = serde_json::from_value(serde_json::json!({
+            "name": "api",
+            "path": path,
+            "cache": true,
+        }))
+        .unwrap();
+
+        assert_eq!(messages(&repo, &conf), ["feat: add the api"]);
+
+        // Alter the record so that it could be told apart from the commit
+        let cache = parser::commondir(&repo).join(FILENAME);
+        let content = fs::read_to_string(&cache).unwrap();
+        fs::write(&cache, content.replace("add the api", "add the cached api")).unwrap();
+
+        assert_eq!(messages(&repo, &conf), ["feat: add the cached api"]);
+
+        fs::remove_dir_all(&path).unwrap();
+    }
+}
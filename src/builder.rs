@@ -0,0 +1,173 @@
+//! # Builder module
+//!
+//! The builder module generates a changelog without a configuration file, for
+//! release automation embedding the library.
+//!
+//! ```no_run
+//! use git_changelog::builder::ChangelogBuilder;
+//!
+//! let content = ChangelogBuilder::new()
+//!     .repository(".")
+//!     .range("v1.0.0..v2.0.0")
+//!     .kinds([("feat", "Features"), ("fix", "Bug fixes")])
+//!     .render("markdown")?;
+//!
+//! println!("{}", content);
+//! # Ok::<(), Box<dyn std::error::Error + Send + Sync>>(())
+//! ```
+use std::{error::Error, path::PathBuf, rc::Rc};
+
+use crate::{
+    conf::{self, Configuration, Language, Layout},
+    hooks::Hooks,
+    parser::Changelog,
+    renderer,
+};
+
+/// Build a changelog from the given repositories, the fields of the
+/// repositories apply to the last added one
+#[derive(Default, Clone, Debug)]
+pub struct ChangelogBuilder {
+    conf: Configuration,
+    /// Field set before any repository has been added, if any
+    misplaced: Option<&'static str>,
+}
+
+impl ChangelogBuilder {
+    /// Returns a builder using the default kinds and no repository
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use the given kinds and their titles instead of the default ones
+    pub fn kinds<I, K, V>(mut self, kinds: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.conf.kinds = kinds
+            .into_iter()
+            .map(|(kind, title)| (kind.into(), title.into()))
+            .collect();
+
+        self
+    }
+
+    /// Render the fixed strings and the dates using the given language
+    pub fn language(mut self, language: Language) -> Self {
+        self.conf.language = language;
+        self
+    }
+
+    /// Group the entries by repository or by kind
+    pub fn layout(mut self, layout: Layout) -> Self {
+        self.conf.layout = layout;
+        self
+    }
+
+    /// Add the repository at the given path, named after its directory
+    pub fn repository<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        let path = conf::simplify(&path.into());
+        let name = path
+            .canonicalize()
+            .unwrap_or_else(|_| path.to_owned())
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| conf::slashed(&path));
+
+        self.conf
+            .repositories
+            .push(conf::Repository::new(&name, path));
+        self
+    }
+
+    /// Set the name of the last added repository
+    pub fn name<S: Into<String>>(self, name: S) -> Self {
+        self.with("name", |repository| repository.name = name.into())
+    }
+
+    /// Set the range of commits of the last added repository (e.g. v1..v2)
+    pub fn range<S: Into<String>>(self, range: S) -> Self {
+        self.with("range", |repository| repository.range = Some(range.into()))
+    }
+
+    /// Set the accepted scopes of the last added repository
+    pub fn scopes<I, S>(self, scopes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.with("scopes", |repository| {
+            repository.scopes = Some(scopes.into_iter().map(Into::into).collect())
+        })
+    }
+
+    /// Set the layout of the links to the commits of the last added repository
+    pub fn link<S: Into<String>>(self, link: S) -> Self {
+        self.with("link", |repository| repository.link = Some(link.into()))
+    }
+
+    /// Change any other field of the configuration
+    pub fn configure<F: FnOnce(&mut Configuration)>(mut self, f: F) -> Self {
+        f(&mut self.conf);
+        self
+    }
+
+    /// Returns the configuration built so far
+    pub fn configuration(&self) -> &Configuration {
+        &self.conf
+    }
+
+    /// Parse the history of the repositories
+    pub fn build(self) -> Result<Changelog, Box<dyn Error + Send + Sync>> {
+        self.build_with(&mut ())
+    }
+
+    /// Parse the history of the repositories, calling the given hooks along
+    /// the way
+    pub fn build_with(
+        self,
+        hooks: &mut dyn Hooks,
+    ) -> Result<Changelog, Box<dyn Error + Send + Sync>> {
+        if let Some(field) = self.misplaced {
+            return Err(format!("could not set {}, no repository has been added", field).into());
+        }
+
+        if self.conf.repositories.is_empty() {
+            return Err("could not build the changelog, no repository has been added".into());
+        }
+
+        Changelog::generate(Rc::new(self.conf), hooks)
+    }
+
+    /// Parse the history of the repositories and render the changelog using
+    /// the given format (e.g. markdown or html)
+    pub fn render(self, format: &str) -> Result<String, Box<dyn Error + Send + Sync>> {
+        self.render_with(format, &mut ())
+    }
+
+    /// Parse the history of the repositories and render the changelog using
+    /// the given format, calling the given hooks along the way
+    pub fn render_with(
+        self,
+        format: &str,
+        hooks: &mut dyn Hooks,
+    ) -> Result<String, Box<dyn Error + Send + Sync>> {
+        let (_, content) = renderer::render(format, self.build_with(hooks)?)?;
+
+        Ok(content)
+    }
+
+    /// Apply the change to the last added repository
+    fn with<F: FnOnce(&mut conf::Repository)>(mut self, field: &'static str, f: F) -> Self {
+        match self.conf.repositories.last_mut() {
+            Some(repository) => f(repository),
+            None => {
+                self.misplaced.get_or_insert(field);
+            }
+        }
+
+        self
+    }
+}
@@ -0,0 +1,382 @@
+//! # Bump module
+//!
+//! The bump module computes the next version of a repository from the kinds
+//! of the commits since the last tag.
+use std::{
+    collections::HashMap,
+    fmt::{self, Display, Formatter},
+};
+
+use schemars::JsonSchema;
+use semver::{BuildMetadata, Prerelease, Version};
+use serde::{Deserialize, Serialize};
+
+use crate::parser::{Commit, Repository, Tag, UNRELEASED};
+
+/// Part of the version to increment
+#[derive(
+    Serialize, Deserialize, JsonSchema, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Bump {
+    None,
+    Patch,
+    Minor,
+    Major,
+}
+
+impl Display for Bump {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::None => write!(f, "none"),
+            Self::Patch => write!(f, "patch"),
+            Self::Minor => write!(f, "minor"),
+            Self::Major => write!(f, "major"),
+        }
+    }
+}
+
+impl Bump {
+    /// Returns the bump of a commit, major for breaking changes, then the
+    /// one configured for its kind and scope (e.g. `feat(experimental)`), then
+    /// the one configured for its kind, by default minor for features and
+    /// patch otherwise
+    pub fn of(commit: &Commit, bumps: &HashMap<String, Bump>) -> Self {
+        if commit.breaking {
+            return Self::Major;
+        }
+
+        let scoped = commit
+            .scope
+            .iter()
+            .flat_map(|scope| scope.split(','))
+            .filter_map(|scope| bumps.get(&format!("{}({})", commit.kind, scope.trim())))
+            .max();
+
+        if let Some(bump) = scoped {
+            return *bump;
+        }
+
+        match bumps.get(&commit.kind) {
+            Some(bump) => *bump,
+            None if commit.kind == "feat" => Self::Minor,
+            None => Self::Patch,
+        }
+    }
+}
+
+/// Version of a tag, keeping its prefix (e.g. `v` or `release-`)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Release {
+    pub prefix: String,
+    pub version: Version,
+}
+
+impl Display for Release {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.prefix, self.version)
+    }
+}
+
+impl Default for Release {
+    fn default() -> Self {
+        Self {
+            prefix: String::from("v"),
+            version: Version::new(0, 0, 0),
+        }
+    }
+}
+
+impl Release {
+    /// Parse the tag name as a prefix followed by a semantic version, the
+    /// prefix may contain digits (e.g. `sha2-v1.0.0`)
+    pub fn parse(name: &str) -> Option<Self> {
+        name.char_indices()
+            .filter(|(_, c)| c.is_ascii_digit())
+            .find_map(|(idx, _)| {
+                let version = Version::parse(&name[idx..]).ok()?;
+
+                Some(Self {
+                    prefix: String::from(&name[..idx]),
+                    version,
+                })
+            })
+    }
+
+    /// Returns the release incremented by the given bump
+    pub fn bump(&self, bump: Bump) -> Self {
+        let mut version = self.version.to_owned();
+        match bump {
+            Bump::None => return self.to_owned(),
+            Bump::Patch => version.patch += 1,
+            Bump::Minor => {
+                version.minor += 1;
+                version.patch = 0;
+            }
+            Bump::Major => {
+                version.major += 1;
+                version.minor = 0;
+                version.patch = 0;
+            }
+        }
+
+        version.pre = Prerelease::EMPTY;
+        version.build = BuildMetadata::EMPTY;
+
+        Self {
+            prefix: self.prefix.to_owned(),
+            version,
+        }
+    }
+
+    /// Returns the channel and the number of a pre-release (e.g. `beta.2`)
+    pub fn channel(&self) -> Option<(&str, u64)> {
+        let (channel, number) = self.version.pre.as_str().rsplit_once('.')?;
+
+        Some((channel, number.parse().ok()?))
+    }
+
+    /// Returns the release as the given pre-release, the number is
+    /// incremented if the previous release is a pre-release of the same
+    /// version and channel, otherwise it starts at 1
+    pub fn pre(&self, channel: &str, previous: Option<&Release>) -> Result<Self, String> {
+        let channel = self::channel(channel)?;
+        let number = previous
+            .filter(|previous| {
+                previous.version.major == self.version.major
+                    && previous.version.minor == self.version.minor
+                    && previous.version.patch == self.version.patch
+            })
+            .and_then(Release::channel)
+            .filter(|(c, _)| *c == channel.as_str())
+            .map_or(1, |(_, number)| number + 1);
+
+        let mut release = self.to_owned();
+        release.version.pre = Prerelease::new(&format!("{}.{}", channel, number))
+            .map_err(|err| format!("could not use channel '{}', {}", channel, err))?;
+
+        Ok(release)
+    }
+}
+
+/// Returns the channel of a pre-release if it is a valid identifier (e.g.
+/// beta or rc), made of ASCII alphanumerics and hyphens only
+pub fn channel(value: &str) -> Result<String, String> {
+    Prerelease::new(value)
+        .ok()
+        .filter(|pre| !pre.is_empty() && !pre.as_str().contains('.'))
+        .map(|pre| pre.to_string())
+        .ok_or_else(|| {
+            format!(
+                "could not use channel '{}', expected a non-empty identifier made of ASCII alphanumerics and hyphens (e.g. beta)",
+                value
+            )
+        })
+}
+
+/// Returns the most recent release of the repository
+pub fn latest(repository: &Repository) -> Option<Release> {
+    repository
+        .tags
+        .iter()
+        .filter(|tag| tag.name != UNRELEASED)
+        .find_map(|tag| Release::parse(&tag.name))
+}
+
+/// Returns the commits which are not yet released
+pub fn unreleased(repository: &Repository) -> Vec<&Commit> {
+    repository
+        .tags
+        .iter()
+        .filter(|tag| tag.name == UNRELEASED)
+        .flat_map(|tag| tag.commits.values().flatten())
+        .collect()
+}
+
+/// Returns the previous release, if any, and the next one along with the
+/// bump of the commits since the previous stable release.
+///
+/// The next version is computed from the previous stable release, so that
+/// pre-releases of the same version share the same number, when a channel is
+/// given the next release is a pre-release of this channel, which fails if the
+/// channel is not a valid pre-release identifier.
+pub fn next(
+    repository: &Repository,
+    bumps: &HashMap<String, Bump>,
+    channel: Option<&str>,
+) -> Result<(Option<Release>, Release, Bump), String> {
+    let previous = latest(repository);
+    let pending = unreleased(repository)
+        .into_iter()
+        .map(|commit| Bump::of(commit, bumps))
+        .max()
+        .unwrap_or(Bump::None);
+
+    if pending == Bump::None {
+        return Ok((
+            previous.to_owned(),
+            previous.unwrap_or_default(),
+            Bump::None,
+        ));
+    }
+
+    // Walk the sections from the newest one until the previous stable release
+    let mut stable = None;
+    let mut bump = Bump::None;
+    for tag in &repository.tags {
+        if let Some(release) = Release::parse(&tag.name) {
+            if release.version.pre.is_empty() {
+                stable = Some(release);
+                break;
+            }
+        }
+
+        for commit in tag.commits.values().flatten() {
+            bump = bump.max(Bump::of(commit, bumps));
+        }
+    }
+
+    let mut base = stable.unwrap_or_default();
+    if let Some(ref previous) = previous {
+        base.prefix = previous.prefix.to_owned();
+    }
+
+    let next = match (base.bump(bump), channel) {
+        (next, Some(channel)) => next.pre(channel, previous.as_ref())?,
+        (next, None) => next,
+    };
+
+    Ok((previous, next, bump))
+}
+
+/// Returns the release notes of a tag as plain text
+pub fn notes(name: &str, tag: &Tag) -> String {
+    let mut notes = format!("{}\n", name);
+    let mut kinds: Vec<_> = tag.commits.iter().collect();
+    kinds.sort_by(|a, b| a.0.cmp(b.0));
+
+    for (kind, commits) in kinds {
+        notes += &format!("\n{}\n", kind);
+        for commit in commits {
+            notes += &format!("- {} ({})\n", commit.message, commit.short_hash);
+        }
+    }
+
+    notes
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::parser::commit;
+
+    /// Returns a repository holding the tags, from the most recent one
+    fn repository(tags: &[(&str, &[&str])]) -> Repository {
+        let mut repository = Repository::from(String::from("api"));
+        for (name, kinds) in tags {
+            let mut commits: BTreeMap<String, Vec<Commit>> = BTreeMap::new();
+            for (idx, kind) in kinds.iter().enumerate() {
+                let hash = format!("{}{:07}", kind, idx);
+                let message = format!("{}: change", kind);
+                commits.entry(kind.to_string()).or_default().push(commit(
+                    &hash,
+                    kind,
+                    &message,
+                    "2024-01-01",
+                ));
+            }
+
+            repository.tags.push(Tag::from((name.to_string(), commits)));
+        }
+
+        repository
+    }
+
+    fn release(name: &str) -> Release {
+        Release::parse(name).expect("release to be parsed")
+    }
+
+    #[test]
+    fn pre_numbers_the_pre_releases_of_the_same_version_and_channel() {
+        let next = release("v1.3.0");
+
+        let first = next.pre("beta", None).unwrap();
+        assert_eq!(first.to_string(), "v1.3.0-beta.1");
+
+        let second = next.pre("beta", Some(&first)).unwrap();
+        assert_eq!(second.to_string(), "v1.3.0-beta.2");
+
+        let rc = next.pre("rc", Some(&second)).unwrap();
+        assert_eq!(rc.to_string(), "v1.3.0-rc.1");
+
+        let other = release("v1.4.0").pre("beta", Some(&second)).unwrap();
+        assert_eq!(other.to_string(), "v1.4.0-beta.1");
+    }
+
+    #[test]
+    fn pre_rejects_invalid_channels() {
+        for channel in ["", "beta_1", "beta.1", "bêta"] {
+            assert!(release("v1.3.0").pre(channel, None).is_err(), "{}", channel);
+        }
+    }
+
+    #[test]
+    fn next_bumps_the_latest_release_by_the_pending_commits() {
+        let repository = repository(&[(UNRELEASED, &["fix", "feat"]), ("v1.2.3", &["fix"])]);
+
+        let (previous, next, bump) = next(&repository, &HashMap::new(), None).unwrap();
+
+        assert_eq!(previous, Some(release("v1.2.3")));
+        assert_eq!(next, release("v1.3.0"));
+        assert_eq!(bump, Bump::Minor);
+    }
+
+    #[test]
+    fn next_follows_the_configured_bumps() {
+        let repository = repository(&[(UNRELEASED, &["perf"]), ("v1.2.3", &["fix"])]);
+        let bumps = HashMap::from([(String::from("perf"), Bump::Major)]);
+
+        let (_, next, bump) = next(&repository, &bumps, None).unwrap();
+
+        assert_eq!(next, release("v2.0.0"));
+        assert_eq!(bump, Bump::Major);
+    }
+
+    #[test]
+    fn next_keeps_the_version_of_the_pre_releases_since_the_stable_one() {
+        let repository = repository(&[
+            (UNRELEASED, &["fix"]),
+            ("v1.3.0-beta.1", &["feat"]),
+            ("v1.2.3", &["fix"]),
+        ]);
+
+        let (previous, next, bump) = next(&repository, &HashMap::new(), Some("beta")).unwrap();
+
+        assert_eq!(previous, Some(release("v1.3.0-beta.1")));
+        assert_eq!(next, release("v1.3.0-beta.2"));
+        assert_eq!(bump, Bump::Minor);
+    }
+
+    #[test]
+    fn next_without_pending_commits_is_the_latest_release() {
+        let repository = repository(&[("v1.2.3", &["fix"])]);
+
+        let (previous, next, bump) = next(&repository, &HashMap::new(), None).unwrap();
+
+        assert_eq!(previous, Some(release("v1.2.3")));
+        assert_eq!(next, release("v1.2.3"));
+        assert_eq!(bump, Bump::None);
+    }
+
+    #[test]
+    fn next_starts_from_zero_without_release() {
+        let repository = repository(&[(UNRELEASED, &["fix"])]);
+
+        let (previous, next, _) = next(&repository, &HashMap::new(), None).unwrap();
+
+        assert_eq!(previous, None);
+        assert_eq!(next, release("v0.0.1"));
+    }
+}
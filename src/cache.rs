@@ -0,0 +1,140 @@
+//! # Cache module
+//!
+//! The cache module stores the parsed commits of a repository on disk, keyed
+//! by their object identifier, so that repeated runs on large histories only
+//! parse the new commits. Commits are immutable, the entries never expire.
+//!
+//! Only the parsing of the commits and their diffstat are cached, the commits
+//! are still walked and read from the repository to check the paths, the
+//! filters, the submodules and the signatures, which depend on the
+//! configuration.
+use std::{collections::HashMap, error::Error, fs, path::PathBuf};
+
+use chrono::DateTime;
+use git2 as git;
+use serde::{Deserialize, Serialize};
+use slog_scope::{debug, warn};
+
+use crate::{
+    atomic, conf,
+    parser::{self, Commit, Diffstat},
+};
+
+/// Name of the cache file, written in the git directory of the repository
+pub const FILENAME: &str = "changelog-cache";
+
+/// Parsed fields of a commit, the ones depending on the configuration are
+/// computed again from them
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Record {
+    pub message: String,
+    pub author: String,
+    pub email: String,
+    pub authored: String,
+    pub committed: String,
+    pub breaking: bool,
+    pub origin: Option<String>,
+    pub diffstat: Option<Diffstat>,
+}
+
+impl From<&Commit> for Record {
+    fn from(commit: &Commit) -> Self {
+        Self {
+            message: commit.message.to_owned(),
+            author: commit.author.to_owned(),
+            email: commit.email.to_owned(),
+            authored: commit.authored.to_rfc3339(),
+            committed: commit.committed.to_rfc3339(),
+            breaking: commit.breaking,
+            origin: commit.origin.to_owned(),
+            diffstat: commit.diffstat,
+        }
+    }
+}
+
+impl Record {
+    /// Returns the commit having the given hash, or none if the dates of the
+    /// record could not be parsed
+    pub fn commit(&self, conf: &conf::Repository, hash: &str) -> Option<Commit> {
+        let authored = DateTime::parse_from_rfc3339(&self.authored).ok()?;
+        let committed = DateTime::parse_from_rfc3339(&self.committed).ok()?;
+
+        Some(Commit {
+            hash: hash.to_string(),
+            short_hash: hash.chars().take(conf.hash_length).collect(),
+            message: self.message.to_owned(),
+            author: self.author.to_owned(),
+            email: self.email.to_owned(),
+            date: committed.naive_utc().format("%F").to_string(),
+            authored,
+            committed,
+            link: None,
+            kind: String::new(),
+            scope: None,
+            breaking: self.breaking,
+            origin: self.origin.to_owned(),
+            duplicates: vec![],
+            first_contribution: false,
+            diffstat: None,
+            submodule: None,
+            verification: None,
+            sign_offs: vec![],
+        })
+    }
+}
+
+/// Records of the commits of a repository, keyed by their object identifier
+pub struct Cache {
+    path: PathBuf,
+    records: HashMap<String, Record>,
+    dirty: bool,
+}
+
+impl Cache {
+    /// Open the cache of the repository, the cache is empty if it does not
+    /// exist yet or could not be read
+    pub fn open(repo: &git::Repository) -> Self {
+        let path = parser::commondir(repo).join(FILENAME);
+        let records = match fs::read(&path) {
+            Ok(content) => serde_json::from_slice(&content).unwrap_or_else(|err| {
+                warn!("could not parse the cache, it is rebuilt"; "path" => path.display().to_string(), "error" => err.to_string());
+                HashMap::new()
+            }),
+            Err(_) => HashMap::new(),
+        };
+
+        Self {
+            path,
+            records,
+            dirty: false,
+        }
+    }
+
+    /// Returns the record of the commit, if any
+    pub fn get(&self, oid: &str) -> Option<&Record> {
+        self.records.get(oid)
+    }
+
+    /// Store the record of the commit
+    pub fn insert(&mut self, oid: String, record: Record) {
+        self.records.insert(oid, record);
+        self.dirty = true;
+    }
+
+    /// Write the cache on disk if new records have been inserted
+    pub fn save(&self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        if !self.dirty {
+            return Ok(());
+        }
+
+        // The cache is replaced as a whole, so that an interrupted run does
+        // not leave a truncated one
+        atomic::replace(&self.path, |writer| {
+            serde_json::to_writer(writer, &self.records)
+                .map_err(|err| format!("could not serialize the cache, {}", err).into())
+        })?;
+
+        debug!("Write cache"; "path" => self.path.display().to_string(), "records" => self.records.len());
+        Ok(())
+    }
+}
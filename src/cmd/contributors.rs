@@ -0,0 +1,153 @@
+//! # Contributors module
+//!
+//! The contributors module ranks the authors by number of entries over the
+//! parsed history, for each repository and for all of them combined, which
+//! is handy for community reports.
+use std::{collections::HashMap, convert::TryFrom, error::Error};
+
+use serde::Serialize;
+use structopt::StructOpt;
+
+use crate::{
+    cmd,
+    parser::{Changelog, Repository},
+    Args,
+};
+
+/// Name of the ranking combining all repositories
+const COMBINED: &str = "All repositories";
+
+#[derive(StructOpt, Clone, Debug)]
+pub struct Contributors {
+    /// Rank the contributors of the repository with the given name only
+    #[structopt(short = "n", long = "repository")]
+    pub repository: Option<String>,
+
+    /// Print only the given number of contributors of each ranking
+    #[structopt(short = "l", long = "limit")]
+    pub limit: Option<usize>,
+
+    /// Print the rankings as json
+    #[structopt(long = "json")]
+    pub json: bool,
+}
+
+/// Contributor along with the number of entries authored
+#[derive(Serialize, Clone, Debug)]
+pub struct Contributor {
+    pub author: String,
+    pub commits: usize,
+    pub first_contribution: bool,
+}
+
+/// Contributors of a repository, sorted by decreasing number of entries
+#[derive(Serialize, Clone, Debug)]
+pub struct Ranking {
+    pub repository: String,
+    pub contributors: Vec<Contributor>,
+}
+
+/// Returns the contributors of the repositories by decreasing number of
+/// entries, then by name
+fn rank<'r>(name: &str, repositories: impl Iterator<Item = &'r Repository>) -> Ranking {
+    let mut counters: HashMap<&str, (usize, bool)> = HashMap::new();
+    for commit in repositories
+        .flat_map(|repository| &repository.tags)
+        .flat_map(|tag| tag.commits.values().flatten())
+        .filter(|commit| !commit.author.is_empty())
+    {
+        let counter = counters.entry(commit.author.as_str()).or_default();
+        counter.0 += 1;
+        counter.1 |= commit.first_contribution;
+    }
+
+    let mut contributors: Vec<_> = counters
+        .into_iter()
+        .map(|(author, (commits, first_contribution))| Contributor {
+            author: author.to_string(),
+            commits,
+            first_contribution,
+        })
+        .collect();
+
+    contributors.sort_unstable_by(|a, b| b.commits.cmp(&a.commits).then(a.author.cmp(&b.author)));
+
+    Ranking {
+        repository: name.to_string(),
+        contributors,
+    }
+}
+
+impl Contributors {
+    pub fn execute(&self, args: &Args) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let conf = cmd::configuration(args)?;
+        let changelog = Changelog::try_from(conf)
+            .map_err(|err| format!("could not generate the changelog, {}", err))?;
+
+        let repositories: Vec<_> = changelog
+            .repositories
+            .iter()
+            .filter(|repository| {
+                self.repository
+                    .as_ref()
+                    .map_or(true, |name| name == &repository.name)
+            })
+            .collect();
+
+        if repositories.is_empty() {
+            return Err(format!(
+                "could not find repository '{}'",
+                self.repository.as_deref().unwrap_or_default()
+            )
+            .into());
+        }
+
+        let mut rankings: Vec<_> = repositories
+            .iter()
+            .map(|repository| rank(&repository.name, std::iter::once(*repository)))
+            .collect();
+
+        if repositories.len() > 1 {
+            rankings.push(rank(COMBINED, repositories.iter().copied()));
+        }
+
+        if let Some(limit) = self.limit {
+            for ranking in &mut rankings {
+                ranking.contributors.truncate(limit);
+            }
+        }
+
+        if self.json {
+            let content = serde_json::to_string_pretty(&rankings)
+                .map_err(|err| format!("could not serialize the rankings, {}", err))?;
+
+            println!("{}", content);
+            return Ok(());
+        }
+
+        for ranking in &rankings {
+            println!("{}", ranking.repository);
+            if ranking.contributors.is_empty() {
+                println!("  none");
+            }
+
+            for (idx, contributor) in ranking.contributors.iter().enumerate() {
+                println!(
+                    "  {:>3}. {:<32} {:>6}{}",
+                    idx + 1,
+                    contributor.author,
+                    contributor.commits,
+                    if contributor.first_contribution {
+                        " (first contribution)"
+                    } else {
+                        ""
+                    }
+                );
+            }
+
+            println!();
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,334 @@
+//! # Curate module
+//!
+//! The curate module presents the parsed entries in a terminal user interface
+//! where they could be toggled, edited and moved to another kind before the
+//! changelog is rendered, for teams reviewing their release notes by hand.
+use std::{
+    collections::{BTreeMap, HashMap},
+    convert::TryFrom,
+    error::Error,
+    io::{stdout, Stdout, Write},
+};
+
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEvent, KeyModifiers},
+    execute, queue,
+    style::{Attribute, Print, SetAttribute},
+    terminal::{self, ClearType},
+};
+use slog_scope::info;
+use structopt::StructOpt;
+
+use crate::{
+    cmd,
+    parser::{Changelog, Commit, Tag},
+    renderer, Args,
+};
+
+/// Keys understood by the interface
+const HELP: &str = "↑/↓ move  space toggle  e edit  t change kind  w write  q quit without writing";
+
+#[derive(StructOpt, Clone, Debug)]
+pub struct Curate {}
+
+/// Entry of the changelog as presented to the user
+#[derive(Clone, Debug)]
+struct Entry {
+    repository: usize,
+    tag: usize,
+    section: String,
+    commit: Commit,
+    included: bool,
+}
+
+/// State of the interface
+struct Screen {
+    entries: Vec<Entry>,
+    kinds: Vec<(String, String)>,
+    tags: Vec<Vec<String>>,
+    cursor: usize,
+    editing: Option<String>,
+}
+
+/// Outcome of the curation
+enum Outcome {
+    Write,
+    Quit,
+}
+
+impl Curate {
+    pub fn execute(&self, args: &Args) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let conf = cmd::configuration(args)?;
+        let changelog = Changelog::try_from(conf.to_owned())
+            .map_err(|err| format!("could not generate the changelog, {}", err))?;
+
+        let mut kinds: Vec<_> = conf
+            .kinds
+            .iter()
+            .map(|(kind, title)| (kind.to_owned(), title.to_owned()))
+            .collect();
+        kinds.sort();
+
+        let mut screen = Screen::new(&changelog, kinds);
+        if screen.entries.is_empty() {
+            return Err("could not curate the changelog, there is no entry".into());
+        }
+
+        let mut out = stdout();
+        terminal::enable_raw_mode()
+            .map_err(|err| format!("could not enable raw mode of the terminal, {}", err))?;
+        execute!(out, terminal::EnterAlternateScreen, cursor::Hide)
+            .map_err(|err| format!("could not prepare the terminal, {}", err))?;
+
+        let outcome = screen.run(&mut out);
+
+        // The terminal should be restored even if the interface failed
+        execute!(out, cursor::Show, terminal::LeaveAlternateScreen)
+            .map_err(|err| format!("could not restore the terminal, {}", err))?;
+        terminal::disable_raw_mode()
+            .map_err(|err| format!("could not disable raw mode of the terminal, {}", err))?;
+
+        match outcome? {
+            Outcome::Quit => {
+                println!("Curation aborted, nothing has been written");
+                Ok(())
+            }
+            Outcome::Write => {
+                let included = screen.entries.iter().filter(|e| e.included).count();
+                let changelog = screen.changelog(changelog);
+                let (extension, content) = renderer::render(&args.format, changelog)?;
+
+                let mut output = args.output.to_owned();
+                output.set_extension(extension);
+                if args.dry_run {
+                    println!("Would write {} ({} bytes)", output.display(), content.len());
+                    return Ok(());
+                }
+
+                cmd::write(&output, &content)?;
+
+                info!("Write curated changelog"; "output" => output.to_string_lossy().to_string(), "entries" => included);
+                println!("Changelog {:?} written with {} entries", output, included);
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Screen {
+    fn new(changelog: &Changelog, kinds: Vec<(String, String)>) -> Self {
+        let mut entries = vec![];
+        let mut tags = vec![];
+
+        for (r, repository) in changelog.repositories.iter().enumerate() {
+            tags.push(
+                repository
+                    .tags
+                    .iter()
+                    .map(|tag| tag.name.to_owned())
+                    .collect(),
+            );
+
+            for (t, tag) in repository.tags.iter().enumerate() {
+                let mut sections: Vec<_> = tag.commits.iter().collect();
+                sections.sort_by(|a, b| a.0.cmp(b.0));
+
+                for (section, commits) in sections {
+                    for commit in commits {
+                        entries.push(Entry {
+                            repository: r,
+                            tag: t,
+                            section: section.to_owned(),
+                            commit: commit.to_owned(),
+                            included: true,
+                        });
+                    }
+                }
+            }
+        }
+
+        Self {
+            entries,
+            kinds,
+            tags,
+            cursor: 0,
+            editing: None,
+        }
+    }
+
+    fn run(&mut self, out: &mut Stdout) -> Result<Outcome, Box<dyn Error + Send + Sync>> {
+        loop {
+            self.draw(out)
+                .map_err(|err| format!("could not draw the interface, {}", err))?;
+
+            let event = event::read().map_err(|err| format!("could not read event, {}", err))?;
+            if let Event::Key(key) = event {
+                if let Some(outcome) = self.handle(key) {
+                    return Ok(outcome);
+                }
+            }
+        }
+    }
+
+    /// Apply the key, returns the outcome once the curation is over
+    fn handle(&mut self, key: KeyEvent) -> Option<Outcome> {
+        if let Some(ref mut text) = self.editing {
+            match key.code {
+                KeyCode::Enter => {
+                    self.entries[self.cursor].commit.message = text.to_owned();
+                    self.editing = None;
+                }
+                KeyCode::Esc => self.editing = None,
+                KeyCode::Backspace => {
+                    text.pop();
+                }
+                KeyCode::Char(c) => text.push(c),
+                _ => {}
+            }
+
+            return None;
+        }
+
+        match key.code {
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                return Some(Outcome::Quit)
+            }
+            KeyCode::Char('q') | KeyCode::Esc => return Some(Outcome::Quit),
+            KeyCode::Char('w') => return Some(Outcome::Write),
+            KeyCode::Up | KeyCode::Char('k') => self.cursor = self.cursor.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.cursor = (self.cursor + 1).min(self.entries.len() - 1)
+            }
+            KeyCode::PageUp => self.cursor = self.cursor.saturating_sub(10),
+            KeyCode::PageDown => self.cursor = (self.cursor + 10).min(self.entries.len() - 1),
+            KeyCode::Char(' ') => {
+                let entry = &mut self.entries[self.cursor];
+                entry.included = !entry.included;
+            }
+            KeyCode::Char('e') => {
+                self.editing = Some(self.entries[self.cursor].commit.message.to_owned());
+            }
+            KeyCode::Char('t') => self.cycle(),
+            _ => {}
+        }
+
+        None
+    }
+
+    /// Move the entry under the cursor to the next kind
+    fn cycle(&mut self) {
+        let entry = &mut self.entries[self.cursor];
+        let next = match self
+            .kinds
+            .iter()
+            .position(|(kind, _)| kind == &entry.commit.kind)
+        {
+            Some(idx) => (idx + 1) % self.kinds.len(),
+            None => 0,
+        };
+
+        let (kind, title) = &self.kinds[next];
+        entry.commit.kind = kind.to_owned();
+        entry.section = title.to_owned();
+    }
+
+    fn draw(&self, out: &mut Stdout) -> crossterm::Result<()> {
+        let (width, height) = terminal::size()?;
+        let (width, rows) = (width as usize, (height as usize).saturating_sub(3).max(1));
+        let first = self
+            .cursor
+            .saturating_sub(rows / 2)
+            .min(self.entries.len().saturating_sub(rows));
+
+        queue!(out, terminal::Clear(ClearType::All), cursor::MoveTo(0, 0))?;
+        queue!(
+            out,
+            SetAttribute(Attribute::Bold),
+            Print(truncate(HELP, width)),
+            SetAttribute(Attribute::Reset)
+        )?;
+
+        for (row, (idx, entry)) in self
+            .entries
+            .iter()
+            .enumerate()
+            .skip(first)
+            .take(rows)
+            .enumerate()
+        {
+            let line = format!(
+                "{} {} | {} | {} {}",
+                if entry.included { "[x]" } else { "[ ]" },
+                self.tags[entry.repository][entry.tag],
+                entry.section,
+                entry.commit.short_hash,
+                entry.commit.message
+            );
+
+            queue!(out, cursor::MoveTo(0, row as u16 + 2))?;
+            if idx == self.cursor {
+                queue!(out, SetAttribute(Attribute::Reverse))?;
+            }
+
+            queue!(
+                out,
+                Print(truncate(&line, width)),
+                SetAttribute(Attribute::Reset)
+            )?;
+        }
+
+        let status = match self.editing {
+            Some(ref text) => format!("edit (enter to confirm, esc to cancel): {}", text),
+            None => format!(
+                "{}/{} entries included",
+                self.entries.iter().filter(|e| e.included).count(),
+                self.entries.len()
+            ),
+        };
+
+        queue!(
+            out,
+            cursor::MoveTo(0, height.saturating_sub(1)),
+            Print(truncate(&status, width))
+        )?;
+
+        out.flush()?;
+        Ok(())
+    }
+
+    /// Returns the changelog holding the curated entries
+    fn changelog(&self, mut changelog: Changelog) -> Changelog {
+        let mut commits: HashMap<(usize, usize), BTreeMap<String, Vec<Commit>>> = HashMap::new();
+        for entry in self.entries.iter().filter(|entry| entry.included) {
+            commits
+                .entry((entry.repository, entry.tag))
+                .or_default()
+                .entry(entry.section.to_owned())
+                .or_default()
+                .push(entry.commit.to_owned());
+        }
+
+        for (r, repository) in changelog.repositories.iter_mut().enumerate() {
+            for (t, tag) in repository.tags.iter_mut().enumerate() {
+                let (collapsed, verification) = (tag.collapsed.take(), tag.verification);
+                *tag = Tag::from((
+                    tag.name.to_owned(),
+                    commits.remove(&(r, t)).unwrap_or_default(),
+                ));
+
+                tag.verification = verification;
+                tag.collapsed = collapsed.filter(|section| tag.commits.contains_key(section));
+            }
+
+            repository.tags.retain(|tag| !tag.commits.is_empty());
+        }
+
+        changelog
+    }
+}
+
+/// Returns the first characters of the text fitting in the given width
+fn truncate(text: &str, width: usize) -> String {
+    text.chars().take(width).collect()
+}
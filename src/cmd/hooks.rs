@@ -0,0 +1,200 @@
+//! # Hooks module
+//!
+//! The hooks module installs git hooks invoking the changelog, the
+//! commit-msg hook lints the message and the prepare-commit-msg one reminds
+//! the accepted kinds and scopes in the message template.
+use std::{
+    error::Error,
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use git2 as git;
+use slog_scope::info;
+use structopt::StructOpt;
+
+use crate::{cmd, parser, Args};
+
+/// Marker written in hooks installed by the changelog
+const MARKER: &str = "# Installed by git-changelog";
+
+#[derive(StructOpt, Clone, Debug)]
+pub enum Hooks {
+    /// Install the commit-msg and prepare-commit-msg hooks
+    #[structopt(name = "install")]
+    Install {
+        /// Overwrite existing hooks which were not installed by the changelog
+        #[structopt(long = "force")]
+        force: bool,
+    },
+
+    /// Remove the hooks installed by the changelog
+    #[structopt(name = "uninstall")]
+    Uninstall,
+
+    /// Append the accepted kinds and scopes to the message, called by the
+    /// prepare-commit-msg hook
+    #[structopt(name = "prepare", setting = structopt::clap::AppSettings::Hidden)]
+    Prepare {
+        /// File containing the commit message
+        file: PathBuf,
+
+        /// Source of the commit message
+        source: Option<String>,
+
+        /// Hash of the commit, when amending
+        hash: Option<String>,
+    },
+}
+
+impl Hooks {
+    pub fn execute(&self, args: &Args) -> Result<(), Box<dyn Error + Send + Sync>> {
+        match self {
+            Self::Install { force } => install(args, *force),
+            Self::Uninstall => uninstall(),
+            Self::Prepare { file, source, .. } => prepare(args, file, source.as_deref()),
+        }
+    }
+}
+
+/// Returns the hooks directory of the repository containing the current
+/// directory, honoring the `core.hooksPath` setting
+fn directory() -> Result<PathBuf, Box<dyn Error + Send + Sync>> {
+    let repo = git::Repository::discover(".")
+        .map_err(|err| format!("could not retrieve git repository, {}", err))?;
+
+    let hooks = repo
+        .config()
+        .and_then(|config| config.get_path("core.hooksPath"))
+        .ok();
+
+    Ok(match hooks {
+        Some(path) if path.is_relative() => {
+            repo.workdir().unwrap_or_else(|| repo.path()).join(path)
+        }
+        Some(path) => path,
+        None => parser::commondir(&repo).join("hooks"),
+    })
+}
+
+fn installed(path: &Path) -> bool {
+    fs::read_to_string(path)
+        .map(|content| content.contains(MARKER))
+        .unwrap_or(false)
+}
+
+fn install(args: &Args, force: bool) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let directory = directory()?;
+    let config = args.config.to_string_lossy().replace('\'', "'\\''");
+
+    fs::create_dir_all(&directory)
+        .map_err(|err| format!("could not create directory '{:?}', {}", directory, err))?;
+
+    let hooks = [
+        (
+            "commit-msg",
+            format!(
+                "exec git-changelog --config '{}' lint --message-file \"$1\"",
+                config
+            ),
+        ),
+        (
+            "prepare-commit-msg",
+            format!(
+                "exec git-changelog --config '{}' hooks prepare \"$@\"",
+                config
+            ),
+        ),
+    ];
+
+    for (name, command) in &hooks {
+        let path = directory.join(name);
+        if path.exists() && !installed(&path) && !force {
+            return Err(format!(
+                "could not install hook, '{:?}' already exists, use --force to overwrite it",
+                path
+            )
+            .into());
+        }
+
+        fs::write(&path, format!("#!/bin/sh\n{}\n{}\n", MARKER, command))
+            .map_err(|err| format!("could not write hook '{:?}', {}", path, err))?;
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o755))
+                .map_err(|err| format!("could not make hook '{:?}' executable, {}", path, err))?;
+        }
+
+        info!("Install hook"; "path" => path.to_string_lossy().to_string());
+        println!("Hook installed in {:?}", path);
+    }
+
+    Ok(())
+}
+
+fn uninstall() -> Result<(), Box<dyn Error + Send + Sync>> {
+    let directory = directory()?;
+
+    for name in &["commit-msg", "prepare-commit-msg"] {
+        let path = directory.join(name);
+        if !installed(&path) {
+            continue;
+        }
+
+        fs::remove_file(&path)
+            .map_err(|err| format!("could not remove hook '{:?}', {}", path, err))?;
+
+        info!("Uninstall hook"; "path" => path.to_string_lossy().to_string());
+        println!("Hook removed from {:?}", path);
+    }
+
+    Ok(())
+}
+
+fn prepare(
+    args: &Args,
+    file: &Path,
+    source: Option<&str>,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    // Only help the user when the message is written from scratch, not when
+    // it is given on the command line, or comes from a merge, squash or amend
+    if !matches!(source, None | Some("template")) {
+        return Ok(());
+    }
+
+    let conf = cmd::configuration(args)?;
+    let mut kinds: Vec<_> = conf.kinds.keys().map(String::as_str).collect();
+    kinds.sort_unstable();
+
+    let mut content = String::from("#\n# Accepted kinds: ");
+    content += &kinds.join(", ");
+
+    let mut scopes: Vec<_> = conf
+        .repositories
+        .iter()
+        .filter_map(|repository| repository.scopes.as_ref())
+        .flatten()
+        .map(String::as_str)
+        .collect();
+
+    if !scopes.is_empty() {
+        scopes.sort_unstable();
+        scopes.dedup();
+        content += "\n# Accepted scopes: ";
+        content += &scopes.join(", ");
+    }
+
+    let mut file = OpenOptions::new()
+        .append(true)
+        .open(file)
+        .map_err(|err| format!("could not open file '{:?}', {}", file, err))?;
+
+    file.write_all(format!("{}\n", content).as_bytes())
+        .map_err(|err| format!("could not write content, {}", err))?;
+
+    Ok(())
+}
@@ -0,0 +1,225 @@
+//! # Init module
+//!
+//! The init module inspects the current git repository to write a ready to
+//! edit configuration file.
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    error::Error,
+    fs,
+};
+
+use git2 as git;
+use regex::Regex;
+use slog_scope::info;
+use structopt::StructOpt;
+
+use crate::{conf::DEFAULT_KINDS, parser::PATTERN, Args};
+
+/// Kinds and scopes found in the recent history
+#[derive(Default, Clone, Debug)]
+struct Usage {
+    kinds: BTreeMap<String, usize>,
+    scopes: BTreeSet<String>,
+}
+
+#[derive(StructOpt, Clone, Debug)]
+pub struct Init {
+    /// Inspect the git repository at the given path
+    #[structopt(short = "p", long = "path", default_value = ".")]
+    pub path: String,
+
+    /// Number of recent commits inspected to find kinds and scopes
+    #[structopt(short = "n", long = "depth", default_value = "500")]
+    pub depth: usize,
+
+    /// Overwrite the configuration file if it already exists
+    #[structopt(long = "force")]
+    pub force: bool,
+}
+
+impl Init {
+    pub fn execute(&self, args: &Args) -> Result<(), Box<dyn Error + Send + Sync>> {
+        if args.config.exists() && !self.force {
+            return Err(format!(
+                "could not write configuration, '{:?}' already exists, use --force to overwrite it",
+                args.config
+            )
+            .into());
+        }
+
+        let repo = git::Repository::discover(&self.path).map_err(|err| {
+            format!(
+                "could not retrieve git repository at '{}', {}",
+                self.path, err
+            )
+        })?;
+
+        let name = repo
+            .workdir()
+            .unwrap_or_else(|| repo.path())
+            .canonicalize()
+            .ok()
+            .and_then(|path| {
+                path.file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+            })
+            .unwrap_or_else(|| String::from("Changelog"));
+
+        let link = repo
+            .find_remote("origin")
+            .ok()
+            .and_then(|remote| remote.url().map(link))
+            .flatten();
+
+        let tags = repo
+            .tag_names(None)
+            .map_err(|err| format!("could not retrieve git tags, {}", err))?
+            .len();
+
+        let usage = self.inspect(&repo)?;
+
+        let content = render(&name, &self.path, &usage, link.as_deref(), tags);
+
+        fs::write(&args.config, content)
+            .map_err(|err| format!("could not write file '{:?}', {}", args.config, err))?;
+
+        info!("Write configuration"; "path" => args.config.to_string_lossy().to_string(), "kinds" => usage.kinds.len(), "scopes" => usage.scopes.len());
+        println!("Configuration written in {:?}", args.config);
+        Ok(())
+    }
+
+    /// Walk the recent history to retrieve the kinds and scopes in use
+    fn inspect(&self, repo: &git::Repository) -> Result<Usage, Box<dyn Error + Send + Sync>> {
+        let mut usage = Usage::default();
+
+        let mut revwalk = repo
+            .revwalk()
+            .map_err(|err| format!("could create a walker on git history, {}", err))?;
+
+        if revwalk.push_head().is_err() {
+            // Freshly initialized repository, there is nothing to inspect
+            return Ok(usage);
+        }
+
+        let re = Regex::new(PATTERN).expect("pattern to be a valid regular expression");
+        for oid in revwalk.take(self.depth) {
+            let oid =
+                oid.map_err(|err| format!("could not retrieve object identifier, {}", err))?;
+
+            let commit = repo
+                .find_commit(oid)
+                .map_err(|err| format!("could not retrieve commit '{}', {}", oid, err))?;
+
+            let captures = match commit.summary().and_then(|summary| re.captures(summary)) {
+                Some(captures) => captures,
+                None => continue,
+            };
+
+            if let Some(kind) = captures.name("kind") {
+                *usage.kinds.entry(kind.as_str().to_owned()).or_insert(0) += 1;
+            }
+
+            if let Some(scope) = captures.name("scope") {
+                usage
+                    .scopes
+                    .extend(scope.as_str().split(',').map(|s| s.trim().to_owned()));
+            }
+        }
+
+        Ok(usage)
+    }
+}
+
+/// Derive a commit link from the remote url of well known forges, the
+/// credentials of the url and the port of the ssh ones are left out
+fn link(url: &str) -> Option<String> {
+    let url = url.trim_end_matches('/').trim_end_matches(".git");
+    let (scheme, rest, ssh) = match url.strip_prefix("git@") {
+        Some(rest) => ("https", rest.replacen(':', "/", 1), true),
+        None => match url.split_once("://")? {
+            ("ssh", rest) => ("https", rest.to_owned(), true),
+            (scheme @ ("https" | "http"), rest) => (scheme, rest.to_owned(), false),
+            _ => return None,
+        },
+    };
+
+    let (authority, path) = rest.split_once('/').unwrap_or((&rest, ""));
+    let host = authority
+        .rsplit_once('@')
+        .map_or(authority, |(_, host)| host);
+    let host = if ssh {
+        host.split(':').next().unwrap_or_default()
+    } else {
+        host
+    };
+
+    if host.is_empty() || path.is_empty() {
+        return None;
+    }
+
+    let url = format!("{}://{}/{}", scheme, host, path);
+    if url.contains("gitlab") {
+        Some(format!("{}/-/commit/{{hash}}", url))
+    } else {
+        Some(format!("{}/commit/{{hash}}", url))
+    }
+}
+
+/// Quote the value as a toml basic string
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn render(name: &str, path: &str, usage: &Usage, link: Option<&str>, tags: usize) -> String {
+    let defaults: BTreeMap<&str, &str> = DEFAULT_KINDS.iter().cloned().collect();
+    let mut content = String::from(
+        "# Kinds are based on https://github.com/angular/angular/blob/master/CONTRIBUTING.md#type\n\
+         #\n\
+         # When a commit match the kind, it will be pushed in the related rubrics.\n\
+         # If it not match, the commit will not be render on the CHANGELOG.md.\n\
+         [kinds]\n",
+    );
+
+    // Keep the default kinds and add the ones found in the history which are
+    // used at least twice, to avoid typos.
+    for (kind, description) in DEFAULT_KINDS {
+        content += &format!("{} = {}\n", kind, quote(description));
+    }
+
+    for (kind, count) in &usage.kinds {
+        if !defaults.contains_key(kind.as_str()) && *count > 1 {
+            content += &format!("{} = {}\n", quote(kind), quote(kind));
+        }
+    }
+
+    content +=
+        "\n# Repositories is an array of git repository that will be used in order to render\n\
+                # the CHANGELOG.md.\n\
+                [[repositories]]\n";
+    content += &format!("name = {}\n", quote(name));
+    content += &format!("path = {}\n", quote(path));
+
+    if !usage.scopes.is_empty() {
+        content += "\n# Scopes found in the recent history, if scopes are omitted all scopes are accepted.\n";
+        content += "scopes = [\n";
+        for scope in &usage.scopes {
+            content += &format!("    {},\n", quote(scope));
+        }
+        content += "]\n";
+    }
+
+    content +=
+        "\n# The range parameter allow you to select which commits to render the CHANGELOG.md.\n";
+    if tags == 0 {
+        content += "# There is no tag yet, all commits will be rendered in a single section.\n";
+    }
+    content += "#\n# range = \"v1.0.0..HEAD\"\n";
+
+    content += "\n# Link allow to directly retrieve commit details by providing a link pointing to them.\n";
+    match link {
+        Some(link) => content += &format!("link = {}\n", quote(link)),
+        None => content += "#\n# link = \"https://github.com/owner/repository/commit/{hash}\"\n",
+    }
+
+    content
+}
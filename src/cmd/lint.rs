@@ -0,0 +1,564 @@
+//! # Lint module
+//!
+//! The lint module walks the history of the configured repositories and
+//! reports every commit which does not follow the convention.
+use std::{
+    error::Error,
+    fmt::Write,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use git2 as git;
+use serde_json::{json, Value};
+use slog_scope::info;
+use structopt::StructOpt;
+
+use crate::{
+    cmd,
+    conf::{self, Configuration, Severity},
+    parser::{self, Classifier, Filter, Skip},
+    rules::{Broken, Candidate, Rules},
+    Args,
+};
+
+#[derive(StructOpt, Clone, Debug)]
+pub struct Lint {
+    /// Lint the given range instead of the configured one (e.g. origin/main..HEAD)
+    #[structopt(short = "r", long = "range")]
+    pub range: Option<String>,
+
+    /// Lint only the repository with the given name
+    #[structopt(short = "n", long = "repository")]
+    pub repository: Option<String>,
+
+    /// Lint the message of the given file instead of the history, as done by
+    /// the commit-msg git hook (e.g. .git/COMMIT_EDITMSG)
+    #[structopt(short = "m", long = "message-file")]
+    pub message_file: Option<PathBuf>,
+
+    /// Require each commit to be signed off by its author, as done to enforce
+    /// the Developer Certificate of Origin (merge commits are accepted), the
+    /// author of a message file is the one configured in git
+    #[structopt(long = "dco")]
+    pub dco: bool,
+
+    /// Print the results using the given format, sarif being read by code
+    /// scanning platforms (e.g. GitHub)
+    #[structopt(short = "f", long = "format", possible_values = FORMATS, default_value = "text")]
+    pub format: String,
+}
+
+/// Formats of the output of the lint subcommand
+const FORMATS: &[&str] = &["text", "sarif"];
+
+/// Version of the SARIF specification followed by the output
+const SARIF_VERSION: &str = "2.1.0";
+
+/// Commit which does not follow the convention or breaks a rule
+#[derive(Clone, Debug)]
+pub struct Violation {
+    pub repository: String,
+    /// Hash of the commit, empty if a message file is linted
+    pub hash: String,
+    pub summary: String,
+    /// Line of the summary in the message file, if a message file is linted
+    pub line: Option<usize>,
+    /// Broken rules, the errors come before the warnings
+    pub broken: Vec<Broken>,
+}
+
+impl Violation {
+    /// Returns true if one of the broken rules is an error
+    pub fn failed(&self) -> bool {
+        self.broken
+            .iter()
+            .any(|broken| broken.severity == Severity::Error)
+    }
+
+    /// Returns the line introducing the commit
+    pub fn title(&self) -> String {
+        if self.hash.is_empty() {
+            return self.summary.to_owned();
+        }
+
+        let hash = self.hash.get(..7).unwrap_or(&self.hash);
+        format!("{} {} {}", self.repository, hash, self.summary)
+    }
+}
+
+impl Lint {
+    pub fn execute(&self, args: &Args) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let conf = cmd::configuration(args)?;
+        let violations = match self.message_file {
+            Some(ref path) => self.lint_file(&conf, path)?.into_iter().collect(),
+            None => self.lint(&conf)?,
+        };
+
+        if self.format == "sarif" {
+            let log = sarif(&violations, self.message_file.as_deref());
+            let content = serde_json::to_string_pretty(&log)
+                .map_err(|err| format!("could not serialize the results, {}", err))?;
+            println!("{}", content);
+        } else {
+            for violation in &violations {
+                let mut line = violation.title();
+                for broken in &violation.broken {
+                    match broken.severity {
+                        Severity::Error => write!(line, "\n  - {}", broken.reason)?,
+                        Severity::Warning => write!(line, "\n  - warning: {}", broken.reason)?,
+                    }
+                }
+
+                // The commit-msg hook should not write to the standard output
+                if self.message_file.is_some() {
+                    eprintln!("{}", line);
+                } else {
+                    println!("{}", line);
+                }
+            }
+        }
+
+        let failed = violations
+            .iter()
+            .filter(|violation| violation.failed())
+            .count();
+
+        if failed == 0 {
+            return Ok(());
+        }
+
+        if self.message_file.is_some() {
+            return Err(String::from("the commit message does not follow the convention").into());
+        }
+
+        Err(format!("{} commit(s) do not follow the convention", failed).into())
+    }
+
+    /// Lint the message written by git in the given file
+    pub fn lint_file(
+        &self,
+        conf: &Configuration,
+        path: &Path,
+    ) -> Result<Option<Violation>, Box<dyn Error + Send + Sync>> {
+        let content =
+            fs::read(path).map_err(|err| format!("could not read file '{:?}', {}", path, err))?;
+        let content = parser::newlines(&String::from_utf8_lossy(&content));
+        let message = message(&content);
+        let summary = match summary(&message) {
+            Some(summary) => summary,
+            None => {
+                // git aborts the commit on empty message by itself
+                return Ok(None);
+            }
+        };
+        let line = content
+            .lines()
+            .position(|line| line.trim_end() == summary)
+            .map(|idx| idx + 1);
+
+        let repository = cmd::select(conf, self.repository.as_deref())?;
+        let classifier = Classifier::new(
+            &conf.kinds,
+            repository.and_then(|r| r.scopes.as_ref()),
+            repository.map(|r| r.grammar.as_slice()).unwrap_or_default(),
+        )?;
+        let mut broken: Vec<_> = check(&classifier, &summary)
+            .into_iter()
+            .map(|reason| error(CONVENTION, reason))
+            .collect();
+        if self.dco && classifier.classify(&summary) != Err(Skip::Merge) {
+            let path = repository.map_or(Path::new("."), |r| r.path.as_path());
+            let email = author(path)?;
+            broken.extend(dco(&message, &email).map(|reason| error(DCO, reason)));
+        }
+
+        broken.extend(rules(&conf.lint, &classifier, &message));
+
+        let mut custom = Rules::load(&conf.lint, conf.plugin_fuel)?;
+        let name = repository.map(|r| r.name.as_str()).unwrap_or_default();
+        if let Some(candidate) = candidate(&classifier, name, &message) {
+            broken.extend(custom.check(&candidate));
+        }
+
+        if broken.is_empty() {
+            return Ok(None);
+        }
+
+        broken.sort_by_key(|broken| broken.severity == Severity::Warning);
+        Ok(Some(Violation {
+            repository: name.to_owned(),
+            hash: String::new(),
+            summary,
+            line,
+            broken,
+        }))
+    }
+
+    pub fn lint(
+        &self,
+        conf: &Configuration,
+    ) -> Result<Vec<Violation>, Box<dyn Error + Send + Sync>> {
+        let mut violations = vec![];
+        let mut custom = Rules::load(&conf.lint, conf.plugin_fuel)?;
+
+        for repository in &conf.repositories {
+            if let Some(ref name) = self.repository {
+                if name != &repository.name {
+                    continue;
+                }
+            }
+
+            let repo = git::Repository::discover(&repository.path).map_err(|err| {
+                format!(
+                    "could not retrieve git repository at '{:?}', {}",
+                    repository.path, err
+                )
+            })?;
+
+            let range = self.range.as_deref().or(repository.range.as_deref());
+            let classifier =
+                Classifier::new(&conf.kinds, repository.scopes.as_ref(), &repository.grammar)?;
+            let filter = Filter::new(repository)?;
+
+            let mut walked = 0;
+            for oid in parser::walk(&repo, range, repository.branch.as_deref())? {
+                let oid =
+                    oid.map_err(|err| format!("could not retrieve object identifier, {}", err))?;
+
+                let commit = repo
+                    .find_commit(oid)
+                    .map_err(|err| format!("could not retrieve commit '{}', {}", oid, err))?;
+
+                // Excluded commits are not part of the changelog
+                if filter.check(&commit).is_err() {
+                    continue;
+                }
+
+                let summary =
+                    String::from_utf8_lossy(commit.summary_bytes().unwrap_or_default()).to_string();
+
+                walked += 1;
+                let mut broken: Vec<_> = check(&classifier, &summary)
+                    .into_iter()
+                    .map(|reason| error(CONVENTION, reason))
+                    .collect();
+                if self.dco && commit.parent_count() <= 1 {
+                    let message = String::from_utf8_lossy(commit.message_bytes());
+                    let email = String::from_utf8_lossy(commit.author().email_bytes()).to_string();
+                    broken.extend(dco(&message, &email).map(|reason| error(DCO, reason)));
+                }
+
+                let message = parser::newlines(&String::from_utf8_lossy(commit.message_bytes()));
+                broken.extend(rules(&conf.lint, &classifier, &message));
+
+                if let Some(mut candidate) = candidate(&classifier, &repository.name, &message) {
+                    let author = commit.author();
+                    candidate.hash = oid.to_string();
+                    candidate.author = String::from_utf8_lossy(author.name_bytes()).to_string();
+                    candidate.email = String::from_utf8_lossy(author.email_bytes()).to_string();
+                    broken.extend(custom.check(&candidate));
+                }
+
+                if broken.is_empty() {
+                    continue;
+                }
+
+                broken.sort_by_key(|broken| broken.severity == Severity::Warning);
+                violations.push(Violation {
+                    repository: repository.name.to_owned(),
+                    hash: oid.to_string(),
+                    summary,
+                    line: None,
+                    broken,
+                });
+            }
+
+            info!("Lint repository"; "repository" => &repository.name, "commits" => walked);
+        }
+
+        Ok(violations)
+    }
+}
+
+/// Rule of the convention, checked by default
+const CONVENTION: &str = "convention";
+
+/// Rule of the Developer Certificate of Origin, checked using --dco
+const DCO: &str = "dco";
+
+/// Returns the rule broken with the error severity
+fn error(rule: &str, reason: String) -> Broken {
+    Broken {
+        rule: rule.to_owned(),
+        severity: Severity::Error,
+        reason,
+    }
+}
+
+/// Returns the SARIF log of the violations, commits are not part of a file so
+/// only the results of a message file are located, at the line of its summary
+fn sarif(violations: &[Violation], message_file: Option<&Path>) -> Value {
+    let mut rules: Vec<&str> = vec![];
+    let mut results = vec![];
+
+    for violation in violations {
+        for broken in &violation.broken {
+            if !rules.contains(&broken.rule.as_str()) {
+                rules.push(&broken.rule);
+            }
+
+            let level = match broken.severity {
+                Severity::Error => "error",
+                Severity::Warning => "warning",
+            };
+
+            let mut result = json!({
+                "ruleId": broken.rule,
+                "level": level,
+                "message": { "text": format!("{} ({})", broken.reason, violation.title()) },
+                "properties": {
+                    "repository": violation.repository,
+                    "commit": violation.hash,
+                    "summary": violation.summary,
+                },
+            });
+
+            if !violation.hash.is_empty() {
+                result["partialFingerprints"] = json!({ "commitSha/v1": violation.hash });
+            }
+
+            if let Some(path) = message_file {
+                result["locations"] = json!([{
+                    "physicalLocation": {
+                        "artifactLocation": { "uri": conf::slashed(path) },
+                        "region": { "startLine": violation.line.unwrap_or(1) },
+                    },
+                }]);
+            }
+
+            results.push(result);
+        }
+    }
+
+    let rules: Vec<_> = rules.iter().map(|rule| json!({ "id": rule })).collect();
+    json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": SARIF_VERSION,
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": env!("CARGO_PKG_NAME"),
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules,
+                },
+            },
+            "results": results,
+        }],
+    })
+}
+
+/// Returns the reasons why the message does not follow the convention, merge
+/// commits are accepted
+pub fn check(classifier: &Classifier<'_>, message: &str) -> Vec<String> {
+    match classifier.classify(message) {
+        Ok(classification) => match classification.scope {
+            Some(ref scope) => classifier
+                .unknown_scopes(scope)
+                .into_iter()
+                .map(|sub_scope| {
+                    format!("scope '{}' is not contained in provided scopes", sub_scope)
+                })
+                .collect(),
+            None => vec![],
+        },
+        Err(Skip::Merge) => vec![],
+        Err(skip) => vec![skip.to_string()],
+    }
+}
+
+/// Returns why the message does not comply with the Developer Certificate of
+/// Origin, if any, it should be signed off by the author with the given email
+fn dco(message: &str, email: &str) -> Option<String> {
+    let sign_offs = parser::sign_offs(message);
+    if sign_offs.is_empty() {
+        return Some(String::from("Signed-off-by trailer is missing"));
+    }
+
+    if sign_offs
+        .iter()
+        .any(|(_, signer)| signer.eq_ignore_ascii_case(email))
+    {
+        return None;
+    }
+
+    Some(format!(
+        "Signed-off-by trailer does not match the author <{}>",
+        email
+    ))
+}
+
+/// Returns the email of the author of the commit being written, the one of
+/// GIT_AUTHOR_EMAIL as git does, otherwise the one configured in the
+/// repository at the given path
+fn author(path: &Path) -> Result<String, Box<dyn Error + Send + Sync>> {
+    if let Ok(email) = std::env::var("GIT_AUTHOR_EMAIL") {
+        return Ok(email);
+    }
+
+    git::Repository::discover(path)
+        .and_then(|repo| repo.config())
+        .and_then(|config| config.get_string("user.email"))
+        .map_err(|err| {
+            format!(
+                "could not retrieve the author of the commit to sign off, {}",
+                err
+            )
+            .into()
+        })
+}
+
+/// Returns the configured rules broken by the full message, merge commits are
+/// accepted
+pub fn rules(rules: &conf::Lint, classifier: &Classifier<'_>, message: &str) -> Vec<Broken> {
+    let mut lines = message.trim_start_matches('\n').lines();
+    let subject = lines.next().unwrap_or_default().trim_end();
+    if classifier.classify(subject) == Err(Skip::Merge) {
+        return vec![];
+    }
+
+    let prefix = subject
+        .split_once(':')
+        .map(|(prefix, _)| prefix)
+        .unwrap_or_default();
+    let kind = prefix.split(['(', '!']).next().unwrap_or_default();
+
+    let mut broken = vec![];
+    if let Some(ref rule) = rules.subject_length {
+        let length = subject.chars().count();
+        if length > rule.max {
+            let reason = format!(
+                "subject is {} characters long, the maximum is {}",
+                length, rule.max
+            );
+            broken.push(("subject_length", rule.severity, reason));
+        }
+    }
+
+    if let Some(ref rule) = rules.trailing_period {
+        if subject.ends_with('.') {
+            broken.push((
+                "trailing_period",
+                rule.severity,
+                String::from("subject ends with a period"),
+            ));
+        }
+    }
+
+    if let Some(ref rule) = rules.lowercase_kind {
+        if kind != kind.to_lowercase() {
+            broken.push((
+                "lowercase_kind",
+                rule.severity,
+                format!("kind '{}' is not lowercase", kind),
+            ));
+        }
+    }
+
+    if let Some(ref rule) = rules.scope_required {
+        if !prefix.contains('(') {
+            broken.push((
+                "scope_required",
+                rule.severity,
+                String::from("scope is missing"),
+            ));
+        }
+    }
+
+    if let Some(ref rule) = rules.blank_line {
+        if matches!(lines.clone().next(), Some(line) if !line.trim().is_empty()) {
+            let reason = String::from("subject is not followed by a blank line");
+            broken.push(("blank_line", rule.severity, reason));
+        }
+    }
+
+    if let Some(ref rule) = rules.body_required {
+        // Trailers (e.g. Signed-off-by) are not part of the body
+        let body = lines.any(|line| !line.trim().is_empty() && !is_trailer(line));
+        if !body && rule.kinds.iter().any(|k| k.eq_ignore_ascii_case(kind)) {
+            broken.push((
+                "body_required",
+                rule.severity,
+                format!("body is required for kind '{}'", kind),
+            ));
+        }
+    }
+
+    broken
+        .into_iter()
+        .map(|(rule, severity, reason)| Broken {
+            rule: String::from(rule),
+            severity,
+            reason,
+        })
+        .collect()
+}
+
+/// Returns the commit checked by the rules of the plugins and the scripts,
+/// merge commits are accepted
+fn candidate(classifier: &Classifier<'_>, repository: &str, message: &str) -> Option<Candidate> {
+    let subject = message
+        .trim_start_matches('\n')
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .trim_end();
+
+    let (kind, scope) = match classifier.classify(subject) {
+        Ok(classification) => (classification.kind, classification.scope),
+        Err(Skip::Merge) => return None,
+        Err(_) => (String::new(), None),
+    };
+
+    Some(Candidate {
+        repository: repository.to_owned(),
+        kind,
+        scope,
+        subject: subject.to_owned(),
+        message: message.to_owned(),
+        ..Default::default()
+    })
+}
+
+/// Returns true if the line is a git trailer, e.g. `Refs: #123`
+fn is_trailer(line: &str) -> bool {
+    match line.split_once(": ") {
+        Some((token, _)) => {
+            !token.is_empty() && token.chars().all(|c| c.is_alphanumeric() || c == '-')
+        }
+        None => false,
+    }
+}
+
+/// Returns the message written by git, comments are removed as well as
+/// everything below the scissors line
+pub fn message(content: &str) -> String {
+    content
+        .lines()
+        .take_while(|line| {
+            !line.starts_with("# ------------------------ >8 ------------------------")
+        })
+        .filter(|line| !line.starts_with('#'))
+        .map(str::trim_end)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Returns the summary of a message written by git, comments are removed as
+/// well as everything below the scissors line
+pub fn summary(content: &str) -> Option<String> {
+    message(content)
+        .lines()
+        .find(|line| !line.trim().is_empty())
+        .map(String::from)
+}
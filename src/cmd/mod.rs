@@ -0,0 +1,224 @@
+//! # Command module
+//!
+//! The command module provides the subcommands of the changelog
+use std::{error::Error, io::Write, path::Path, rc::Rc};
+
+use structopt::StructOpt;
+
+use git2 as git;
+
+use crate::{
+    atomic,
+    builder::ChangelogBuilder,
+    conf::{self, Configuration},
+    exit::{self, Code},
+    parser::Changelog,
+    renderer, Args,
+};
+
+pub mod contributors;
+pub mod curate;
+pub mod hooks;
+pub mod init;
+pub mod lint;
+pub mod next;
+pub mod release;
+pub mod schema;
+pub mod search;
+pub mod serve;
+pub mod show;
+pub mod stats;
+pub mod validate;
+pub mod workspace;
+
+#[derive(StructOpt, Clone, Debug)]
+pub enum Command {
+    /// Serve the changelog as html and json over http
+    #[structopt(name = "serve")]
+    Serve(serve::Serve),
+
+    /// Write a configuration file based on the current git repository
+    #[structopt(name = "init")]
+    Init(init::Init),
+
+    /// Check the configuration and report all problems found
+    #[structopt(name = "validate")]
+    Validate(validate::Validate),
+
+    /// Print the JSON schema of the configuration file
+    #[structopt(name = "schema")]
+    Schema(schema::Schema),
+
+    /// Report every commit which does not follow the convention
+    #[structopt(name = "lint")]
+    Lint(lint::Lint),
+
+    /// Manage the git hooks invoking the changelog
+    #[structopt(name = "hooks")]
+    Hooks(hooks::Hooks),
+
+    /// Show the release notes of a single tag
+    #[structopt(name = "show")]
+    Show(show::Show),
+
+    /// Search the entries of the parsed history
+    #[structopt(name = "search")]
+    Search(search::Search),
+
+    /// Print statistics of the parsed history
+    #[structopt(name = "stats")]
+    Stats(stats::Stats),
+
+    /// Rank the contributors by number of entries
+    #[structopt(name = "contributors")]
+    Contributors(contributors::Contributors),
+
+    /// Write the changelog, commit it and tag the next version
+    #[structopt(name = "release")]
+    Release(release::Release),
+
+    /// Print the next version computed from the commits since the last tag
+    #[structopt(name = "next-version")]
+    NextVersion(next::Next),
+
+    /// Review the parsed entries in a terminal interface before writing the changelog
+    #[structopt(name = "curate")]
+    Curate(curate::Curate),
+
+    /// Write one changelog per crate of the cargo workspace and a combined one
+    #[structopt(name = "workspace")]
+    Workspace(workspace::Workspace),
+}
+
+impl Command {
+    pub fn execute(&self, args: &Args) -> Result<(), Box<dyn Error + Send + Sync>> {
+        // Release, workspace and curate honor --dry-run, the other ones do not
+        // write anything but init and hooks
+        if args.dry_run {
+            if let Self::Init(_) | Self::Hooks(_) = self {
+                return Err("could not rehearse the subcommand, --dry-run is not supported by init and hooks".into());
+            }
+        }
+
+        match self {
+            Self::Serve(cmd) => cmd.execute(args),
+            Self::Init(cmd) => cmd.execute(args),
+            Self::Validate(cmd) => cmd.execute(args),
+            Self::Schema(cmd) => cmd.execute(args),
+            Self::Lint(cmd) => cmd.execute(args),
+            Self::Hooks(cmd) => cmd.execute(args),
+            Self::Show(cmd) => cmd.execute(args),
+            Self::Search(cmd) => cmd.execute(args),
+            Self::Stats(cmd) => cmd.execute(args),
+            Self::Contributors(cmd) => cmd.execute(args),
+            Self::Release(cmd) => cmd.execute(args),
+            Self::NextVersion(cmd) => cmd.execute(args),
+            Self::Curate(cmd) => cmd.execute(args),
+            Self::Workspace(cmd) => cmd.execute(args),
+        }
+    }
+}
+
+/// Load the configuration file using the given profile, if any, or build the
+/// configuration of the repository given by --path without it
+pub fn configuration(args: &Args) -> Result<Rc<Configuration>, Box<dyn Error + Send + Sync>> {
+    if let Some(ref path) = args.path {
+        let mut builder = ChangelogBuilder::new().repository(path);
+        if let Some(ref range) = args.range {
+            builder = builder.range(range);
+        }
+
+        if !args.kinds.is_empty() {
+            builder = builder.kinds(args.kinds.to_owned());
+        }
+
+        return Ok(Rc::new(builder.configuration().to_owned()));
+    }
+
+    match Configuration::load(&args.config, args.profile.as_deref()) {
+        Ok(conf) => Ok(Rc::new(conf)),
+        Err(err) => Err(exit::with(
+            Code::Configuration,
+            format!("could not load configuration, {}", err).into(),
+        )),
+    }
+}
+
+/// Load the configuration again and validate it, in order to swap it with
+/// the current one in the long running modes (serve and watch)
+pub fn reload(args: &Args) -> Result<Rc<Configuration>, Box<dyn Error + Send + Sync>> {
+    let conf = configuration(args)?;
+    let problems = validate::validate(&conf);
+    if !problems.is_empty() {
+        let problems: Vec<String> = problems.iter().map(ToString::to_string).collect();
+        return Err(format!(
+            "configuration has {} problem(s), {}",
+            problems.len(),
+            problems.join(", ")
+        )
+        .into());
+    }
+
+    Ok(conf)
+}
+
+/// Write the content in the file at the given path and sync it on disk
+pub fn write(path: &Path, content: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
+    atomic::replace(path, |writer| {
+        writer
+            .write_all(content.as_bytes())
+            .map_err(|err| format!("could not write content, {}", err).into())
+    })
+}
+
+/// Stream the markdown changelog into the file at the given path
+pub fn stream(path: &Path, changelog: Changelog) -> Result<(), Box<dyn Error + Send + Sync>> {
+    atomic::replace(path, |writer| renderer::stream(changelog, writer))
+}
+
+/// Select a repository of the configuration, the one given by its name or
+/// the one containing the current directory
+pub fn select<'c>(
+    conf: &'c Configuration,
+    name: Option<&str>,
+) -> Result<Option<&'c conf::Repository>, Box<dyn Error + Send + Sync>> {
+    if let Some(name) = name {
+        return match conf.repositories.iter().find(|r| r.name == name) {
+            Some(repository) => Ok(Some(repository)),
+            None => Err(format!("could not find repository '{}'", name).into()),
+        };
+    }
+
+    // Outside of a repository, none of the configured ones is selected
+    let current = match git::Repository::discover(".")
+        .ok()
+        .and_then(|repo| repo.path().canonicalize().ok())
+    {
+        Some(current) => current,
+        None => return Ok(None),
+    };
+
+    Ok(conf.repositories.iter().find(|repository| {
+        git::Repository::discover(&repository.path)
+            .ok()
+            .and_then(|repo| repo.path().canonicalize().ok())
+            .as_ref()
+            == Some(&current)
+    }))
+}
+
+/// Select the repository targeted by a command, the one given by its name,
+/// the one containing the current directory or the only one configured
+pub fn target<'c>(
+    conf: &'c Configuration,
+    name: Option<&str>,
+) -> Result<&'c conf::Repository, Box<dyn Error + Send + Sync>> {
+    match select(conf, name)? {
+        Some(repository) => Ok(repository),
+        None if conf.repositories.len() == 1 => Ok(&conf.repositories[0]),
+        None => Err(
+            "could not find the repository containing the current directory, use --repository"
+                .into(),
+        ),
+    }
+}
@@ -0,0 +1,53 @@
+//! # Next module
+//!
+//! The next module prints the next version of a repository computed from the
+//! commits since the last tag.
+use std::{convert::TryFrom, error::Error};
+
+use structopt::StructOpt;
+
+use crate::{bump, cmd, parser::Changelog, Args};
+
+#[derive(StructOpt, Clone, Debug)]
+pub struct Next {
+    /// Compute the version of the repository with the given name instead of
+    /// the one containing the current directory
+    #[structopt(short = "n", long = "repository")]
+    pub repository: Option<String>,
+
+    /// Compute a pre-release of the given channel (e.g. beta)
+    #[structopt(long = "pre", parse(try_from_str = bump::channel))]
+    pub pre: Option<String>,
+
+    /// Print the suggested bump (none, patch, minor or major) instead of the version
+    #[structopt(short = "b", long = "bump")]
+    pub bump: bool,
+}
+
+impl Next {
+    pub fn execute(&self, args: &Args) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let conf = cmd::configuration(args)?;
+        let name = cmd::target(&conf, self.repository.as_deref())?
+            .name
+            .to_owned();
+
+        let changelog = Changelog::try_from(conf.to_owned())
+            .map_err(|err| format!("could not generate the changelog, {}", err))?;
+
+        let repository = changelog
+            .repositories
+            .iter()
+            .find(|r| r.name == name)
+            .ok_or_else(|| format!("could not find repository '{}'", name))?;
+
+        let channel = self.pre.as_deref().or(conf.prerelease.as_deref());
+        let (_, next, bump) = bump::next(repository, &conf.bumps, channel)?;
+        if self.bump {
+            println!("{}", bump);
+        } else {
+            println!("{}", next);
+        }
+
+        Ok(())
+    }
+}
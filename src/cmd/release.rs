@@ -0,0 +1,322 @@
+//! # Release module
+//!
+//! The release module computes the next version from the commits since the
+//! last tag, writes the changelog, commits it and creates an annotated tag
+//! holding the release notes.
+use std::{
+    convert::TryFrom,
+    error::Error,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use git2 as git;
+use slog_scope::info;
+use structopt::StructOpt;
+
+use crate::{
+    bump, cmd,
+    exit::{self, Code},
+    manifest,
+    parser::{Changelog, UNRELEASED},
+    renderer, Args,
+};
+
+#[derive(StructOpt, Clone, Debug)]
+pub struct Release {
+    /// Release the repository with the given name instead of the one
+    /// containing the current directory
+    #[structopt(short = "n", long = "repository")]
+    pub repository: Option<String>,
+
+    /// Message of the release commit, use {version} to inject the version
+    #[structopt(
+        short = "m",
+        long = "message",
+        default_value = "chore(release): {version}"
+    )]
+    pub message: String,
+
+    /// Release a pre-release of the given channel (e.g. beta)
+    #[structopt(long = "pre", parse(try_from_str = bump::channel))]
+    pub pre: Option<String>,
+
+    /// Rewrite the version of the given manifest (Cargo.toml, package.json or
+    /// pyproject.toml) and include it in the release commit, could be repeated
+    #[structopt(long = "manifest")]
+    pub manifests: Vec<PathBuf>,
+
+    /// Push the release commit and tag to the remote
+    #[structopt(short = "p", long = "push")]
+    pub push: bool,
+
+    /// Remote to push the release to
+    #[structopt(short = "r", long = "remote", default_value = "origin")]
+    pub remote: String,
+
+    /// Report what the release would do, without writing, committing nor
+    /// pushing anything
+    #[structopt(long = "dry-run")]
+    pub dry_run: bool,
+}
+
+impl Release {
+    pub fn execute(&self, args: &Args) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let conf = cmd::configuration(args)?;
+        let repository = cmd::target(&conf, self.repository.as_deref())?.to_owned();
+
+        let mut changelog = Changelog::try_from(conf.to_owned())
+            .map_err(|err| format!("could not generate the changelog, {}", err))?;
+
+        let parsed = changelog
+            .repositories
+            .iter_mut()
+            .find(|r| r.name == repository.name)
+            .ok_or_else(|| format!("could not find repository '{}'", repository.name))?;
+
+        let channel = self.pre.as_deref().or(conf.prerelease.as_deref());
+        let (previous, next, bump) = bump::next(parsed, &conf.bumps, channel)?;
+        if bump == bump::Bump::None {
+            return Err(format!("there is nothing to release in '{}'", repository.name).into());
+        }
+
+        let name = next.to_string();
+        info!("Compute next version"; "previous" => previous.map(|p| p.to_string()), "next" => &name, "bump" => bump.to_string());
+
+        let tag = parsed
+            .tags
+            .iter_mut()
+            .find(|tag| tag.name == UNRELEASED)
+            .expect("unreleased commits to be in the unreleased section");
+
+        tag.name = name.to_owned();
+        let notes = bump::notes(&name, tag);
+        let entries = tag.count();
+
+        let repo = git::Repository::discover(&repository.path).map_err(|err| {
+            format!(
+                "could not retrieve git repository at '{:?}', {}",
+                repository.path, err
+            )
+        })?;
+
+        if repo.is_bare() {
+            return Err(format!(
+                "could not release '{}', the repository is bare",
+                repository.name
+            )
+            .into());
+        }
+
+        // Everything is checked before the first file is written, so that a
+        // failed release does not leave a half-done one on disk
+        clean(&repo, &repository.name)?;
+
+        let (extension, content) = renderer::render(&args.format, changelog)?;
+        let mut output = args.output.to_owned();
+        output.set_extension(extension);
+
+        let mut files = vec![(output, content)];
+        for path in &self.manifests {
+            files.push((path.to_owned(), manifest::bumped(path, &next.version)?));
+        }
+
+        let workdir = repo
+            .workdir()
+            .ok_or("could not commit the changelog in a bare repository")?;
+        let relatives = files
+            .iter()
+            .map(|(path, _)| relative(workdir, path))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if self.dry_run || args.dry_run {
+            let (output, content) = &files[0];
+            println!("Would write {} ({} bytes)", output.display(), content.len());
+            for path in &self.manifests {
+                println!("Would bump {} to {}", path.display(), next.version);
+            }
+
+            println!(
+                "Would commit '{}'",
+                self.message.replace("{version}", &name)
+            );
+            println!("Would tag {} with {} entries", name, entries);
+            if self.push {
+                println!("Would push {} to '{}'", name, self.remote);
+            }
+
+            return Ok(());
+        }
+
+        for (path, content) in &files {
+            cmd::write(path, content)?;
+        }
+
+        for path in &self.manifests {
+            info!("Bump manifest"; "manifest" => path.to_string_lossy().to_string(), "version" => next.version.to_string());
+        }
+
+        let blobs: Vec<_> = relatives
+            .into_iter()
+            .zip(files.iter().map(|(_, content)| content.as_bytes()))
+            .collect();
+
+        let message = self.message.replace("{version}", &name);
+        let oid = commit(&repo, &blobs, &message)?;
+        let object = repo
+            .find_object(oid, None)
+            .map_err(|err| format!("could not retrieve commit '{}', {}", oid, err))?;
+
+        let signature = repo
+            .signature()
+            .map_err(|err| format!("could not retrieve signature, {}", err))?;
+
+        repo.tag(&name, &object, &signature, &notes, false)
+            .map_err(|err| format!("could not create tag '{}', {}", name, err))?;
+
+        info!("Create release"; "tag" => &name, "commit" => oid.to_string());
+        println!("Released {}", name);
+
+        if self.push {
+            push(&repo, &self.remote, &name)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Fail if the index or the working tree holds changes, they would be mixed
+/// with the release otherwise
+fn clean(repo: &git::Repository, name: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let mut options = git::StatusOptions::new();
+    options.include_untracked(false).include_ignored(false);
+
+    let statuses = repo
+        .statuses(Some(&mut options))
+        .map_err(|err| format!("could not retrieve the status of the repository, {}", err))?;
+
+    if statuses
+        .iter()
+        .any(|entry| entry.status() != git::Status::CURRENT)
+    {
+        return Err(format!(
+            "could not release '{}', the index or the working tree holds uncommitted changes",
+            name
+        )
+        .into());
+    }
+
+    Ok(())
+}
+
+/// Returns the path relative to the working directory, the file may not
+/// exist yet
+fn relative(workdir: &Path, path: &Path) -> Result<PathBuf, Box<dyn Error + Send + Sync>> {
+    let workdir = workdir
+        .canonicalize()
+        .map_err(|err| format!("could not resolve working directory, {}", err))?;
+
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let file = path
+        .file_name()
+        .ok_or_else(|| format!("could not commit '{:?}', it is not a file", path))?;
+    let path = parent
+        .canonicalize()
+        .map_err(|err| format!("could not resolve path '{:?}', {}", path, err))?
+        .join(file);
+
+    path.strip_prefix(&workdir)
+        .map(Path::to_path_buf)
+        .map_err(|_| {
+            format!(
+                "could not commit '{:?}', it is outside of the repository",
+                path
+            )
+            .into()
+        })
+}
+
+/// Commit the given files on top of HEAD, the tree of HEAD is used so that
+/// nothing else is committed, the index is then reset to the new tree
+fn commit(
+    repo: &git::Repository,
+    files: &[(PathBuf, &[u8])],
+    message: &str,
+) -> Result<git::Oid, Box<dyn Error + Send + Sync>> {
+    let parent = repo
+        .head()
+        .and_then(|head| head.peel_to_commit())
+        .map_err(|err| format!("could not retrieve HEAD commit, {}", err))?;
+
+    let base = parent
+        .tree()
+        .map_err(|err| format!("could not retrieve HEAD tree, {}", err))?;
+
+    let mut builder = git::build::TreeUpdateBuilder::new();
+    for (path, content) in files {
+        let blob = repo
+            .blob(content)
+            .map_err(|err| format!("could not write blob of '{:?}', {}", path, err))?;
+        builder.upsert(path, blob, git::FileMode::Blob);
+    }
+
+    let tree = builder
+        .create_updated(repo, &base)
+        .and_then(|oid| repo.find_tree(oid))
+        .map_err(|err| format!("could not write tree, {}", err))?;
+
+    let signature = repo
+        .signature()
+        .map_err(|err| format!("could not retrieve signature, {}", err))?;
+
+    let oid = repo
+        .commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            message,
+            &tree,
+            &[&parent],
+        )
+        .map_err(|err| format!("could not commit the changelog, {}", err))?;
+
+    // The index matched HEAD, it now matches the release commit
+    let mut index = repo
+        .index()
+        .map_err(|err| format!("could not retrieve index, {}", err))?;
+    index
+        .read_tree(&tree)
+        .and_then(|_| index.write())
+        .map_err(|err| format!("could not write index, {}", err))?;
+
+    Ok(oid)
+}
+
+/// Push HEAD and the tag to the remote, using git in order to benefit from
+/// the credentials configured by the user
+fn push(
+    repo: &git::Repository,
+    remote: &str,
+    tag: &str,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let workdir = repo.workdir().unwrap_or_else(|| repo.path());
+    let status = Command::new("git")
+        .arg("-C")
+        .arg(workdir)
+        .args(["push", "--atomic", remote, "HEAD"])
+        .arg(format!("refs/tags/{}", tag))
+        .status()
+        .map_err(|err| format!("could not execute git, {}", err))?;
+
+    if !status.success() {
+        return Err(exit::with(
+            Code::Network,
+            format!("could not push release to '{}', git {}", remote, status).into(),
+        ));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,22 @@
+//! # Schema module
+//!
+//! The schema module prints the JSON schema of the configuration file, so
+//! that editors could complete and check it.
+use std::error::Error;
+
+use structopt::StructOpt;
+
+use crate::{conf, Args};
+
+#[derive(StructOpt, Clone, Debug)]
+pub struct Schema {}
+
+impl Schema {
+    pub fn execute(&self, _args: &Args) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let schema = serde_json::to_string_pretty(&conf::schema())
+            .map_err(|err| format!("could not serialize the schema, {}", err))?;
+
+        println!("{}", schema);
+        Ok(())
+    }
+}
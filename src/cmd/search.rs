@@ -0,0 +1,112 @@
+//! # Search module
+//!
+//! The search module queries the parsed history and prints the matching
+//! entries along with their tag.
+use std::{convert::TryFrom, error::Error};
+
+use regex::RegexBuilder;
+use structopt::StructOpt;
+
+use crate::{
+    cmd,
+    parser::{Changelog, Commit},
+    Args,
+};
+
+#[derive(StructOpt, Clone, Debug)]
+pub struct Search {
+    /// Keep entries of the given kind (e.g. fix)
+    #[structopt(short = "k", long = "kind")]
+    pub kind: Option<String>,
+
+    /// Keep entries having the given scope
+    #[structopt(short = "s", long = "scope")]
+    pub scope: Option<String>,
+
+    /// Keep entries whose author contains the given value, ignoring case
+    #[structopt(short = "a", long = "author")]
+    pub author: Option<String>,
+
+    /// Keep entries whose message matches the given regular expression, ignoring case
+    #[structopt(short = "g", long = "grep")]
+    pub grep: Option<String>,
+
+    /// Keep entries of the given tag
+    #[structopt(short = "t", long = "tag")]
+    pub tag: Option<String>,
+
+    /// Search only the repository with the given name
+    #[structopt(short = "n", long = "repository")]
+    pub repository: Option<String>,
+}
+
+impl Search {
+    pub fn execute(&self, args: &Args) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let grep = match self.grep {
+            Some(ref pattern) => Some(
+                RegexBuilder::new(pattern)
+                    .case_insensitive(true)
+                    .build()
+                    .map_err(|err| format!("could not parse pattern '{}', {}", pattern, err))?,
+            ),
+            None => None,
+        };
+
+        let author = self.author.as_ref().map(|author| author.to_lowercase());
+        let matches = |commit: &Commit| {
+            self.kind.as_ref().map_or(true, |kind| &commit.kind == kind)
+                && self.scope.as_ref().map_or(true, |scope| {
+                    commit
+                        .scope
+                        .as_ref()
+                        .is_some_and(|s| s.split(',').any(|s| s.trim() == scope))
+                })
+                && author
+                    .as_ref()
+                    .map_or(true, |author| commit.author.to_lowercase().contains(author))
+                && grep
+                    .as_ref()
+                    .map_or(true, |re| re.is_match(&commit.message))
+        };
+
+        let conf = cmd::configuration(args)?;
+        let changelog = Changelog::try_from(conf)
+            .map_err(|err| format!("could not generate the changelog, {}", err))?;
+
+        let mut found = 0;
+        for repository in &changelog.repositories {
+            if self
+                .repository
+                .as_ref()
+                .is_some_and(|name| name != &repository.name)
+            {
+                continue;
+            }
+
+            for tag in &repository.tags {
+                if self.tag.as_ref().is_some_and(|name| name != &tag.name) {
+                    continue;
+                }
+
+                for commit in tag.commits.values().flatten().filter(|c| matches(c)) {
+                    found += 1;
+                    println!(
+                        "{} {} {} {} ({}, {})",
+                        repository.name,
+                        tag.name,
+                        commit.short_hash,
+                        commit.message,
+                        commit.author,
+                        commit.date
+                    );
+                }
+            }
+        }
+
+        if found == 0 {
+            return Err(String::from("could not find any matching entry").into());
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,193 @@
+//! # Serve module
+//!
+//! The serve module exposes the changelog over http, the changelog is
+//! regenerated once it is older than the given interval or on demand, and
+//! once the configuration file or one of the files it includes changes.
+use std::{
+    convert::TryFrom,
+    error::Error,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use percent_encoding::percent_decode_str;
+use slog_scope::{error, info};
+use structopt::StructOpt;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::{
+    cmd,
+    conf::Configuration,
+    parser::{Changelog, HTMLChangelog, Repository},
+    watch, Args,
+};
+
+#[derive(StructOpt, Clone, Debug)]
+pub struct Serve {
+    /// Listen on the given address
+    #[structopt(short = "l", long = "listen", default_value = "127.0.0.1:8080")]
+    pub listen: String,
+
+    /// Regenerate the changelog when it is older than the given number of seconds
+    #[structopt(short = "i", long = "interval", default_value = "300")]
+    pub interval: u64,
+}
+
+/// Content negotiated by the extension of the requested path
+enum Content {
+    Html,
+    Json,
+}
+
+impl Serve {
+    pub fn execute(&self, args: &Args) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let server = Server::http(&self.listen)
+            .map_err(|err| format!("could not listen on '{}', {}", self.listen, err))?;
+
+        info!("Serve changelog"; "listen" => &self.listen);
+
+        let interval = Duration::from_secs(self.interval);
+        let mut conf = cmd::configuration(args)?;
+        let mut changelog = generate(&conf)?;
+        let mut generated = Instant::now();
+        let mut modified = watch::stamps(&args.config);
+
+        loop {
+            // Requests are awaited for a while so that the configuration is
+            // reloaded even if nobody asks for the changelog
+            let request = server
+                .recv_timeout(Duration::from_millis(500))
+                .map_err(|err| format!("could not receive request, {}", err))?;
+
+            // The configuration is swapped only once the changelog has been
+            // generated using it, the previous one is served otherwise
+            let current = watch::stamps(&args.config);
+            if current != modified {
+                modified = current;
+                match reload(args) {
+                    Ok((c, ch)) => {
+                        info!("Configuration changed, reload it");
+                        conf = c;
+                        changelog = ch;
+                        generated = Instant::now();
+                    }
+                    Err(err) => {
+                        error!("could not reload the configuration, keep the previous one"; "error" => err.to_string());
+                    }
+                }
+            }
+
+            let request = match request {
+                Some(request) => request,
+                None => continue,
+            };
+
+            let refresh = request.method() == &Method::Post && request.url() == "/refresh";
+            if refresh || generated.elapsed() >= interval {
+                match generate(&conf) {
+                    Ok(c) => {
+                        changelog = c;
+                        generated = Instant::now();
+                    }
+                    Err(err) => {
+                        error!("could not regenerate the changelog"; "error" => err.to_string());
+                    }
+                }
+            }
+
+            let url = request.url().to_owned();
+            let result = if refresh {
+                request.respond(Response::empty(204))
+            } else {
+                respond(request, &changelog, &url)
+            };
+
+            if let Err(err) = result {
+                error!("could not respond to request"; "url" => url, "error" => err.to_string());
+            }
+        }
+    }
+}
+
+fn generate(conf: &Rc<Configuration>) -> Result<Changelog, Box<dyn Error + Send + Sync>> {
+    Changelog::try_from(conf.to_owned())
+        .map_err(|err| format!("could not generate the changelog, {}", err).into())
+}
+
+/// Load and validate the configuration, then generate the changelog using it
+fn reload(args: &Args) -> Result<(Rc<Configuration>, Changelog), Box<dyn Error + Send + Sync>> {
+    let conf = cmd::reload(args)?;
+    let changelog = generate(&conf)?;
+    Ok((conf, changelog))
+}
+
+/// Route the request on the changelog, the available routes are:
+///
+/// - `/` or `/index.{html,json}` for the whole changelog
+/// - `/<repository>.{html,json}` for a repository
+/// - `/<repository>/<tag>.{html,json}` for a tag of a repository, the tag
+///   being the percent-decoded rest of the path
+fn respond(request: Request, changelog: &Changelog, url: &str) -> std::io::Result<()> {
+    if request.method() != &Method::Get {
+        return request.respond(Response::empty(405));
+    }
+
+    let path = url.split('?').next().unwrap_or_default().trim_matches('/');
+    let (path, content) = match path.rsplit_once('.') {
+        Some((path, "html")) => (path, Content::Html),
+        Some((path, "json")) => (path, Content::Json),
+        _ => (path, Content::Html),
+    };
+
+    // The tag is the rest of the path, as it could hold slashes (e.g. api/v1.0.0)
+    let (name, tag) = match path.split_once('/') {
+        Some((name, tag)) => (name, Some(percent_decode_str(tag).decode_utf8_lossy())),
+        None => (path, None),
+    };
+
+    let name = percent_decode_str(name).decode_utf8_lossy();
+    let repositories = match (name.as_ref(), tag) {
+        ("" | "index", None) => changelog.repositories.to_owned(),
+        (name, None) => match find(changelog, name) {
+            Some(repository) => vec![repository.to_owned()],
+            None => return request.respond(Response::empty(404)),
+        },
+        (name, Some(tag)) => match find(changelog, name)
+            .filter(|repository| repository.tags.iter().any(|t| t.name == tag))
+        {
+            Some(repository) => {
+                let mut repository = repository.to_owned();
+                repository.tags.retain(|t| t.name == tag);
+                vec![repository]
+            }
+            None => return request.respond(Response::empty(404)),
+        },
+    };
+
+    let changelog = changelog.with(repositories);
+    let (body, content_type) = match content {
+        Content::Html => (
+            format!("{}", HTMLChangelog::from(changelog)),
+            "text/html; charset=utf-8",
+        ),
+        Content::Json => match serde_json::to_string_pretty(&changelog) {
+            Ok(body) => (body, "application/json"),
+            Err(err) => {
+                error!("could not serialize the changelog"; "error" => err.to_string());
+                return request.respond(Response::empty(500));
+            }
+        },
+    };
+
+    let header =
+        Header::from_bytes("Content-Type", content_type).expect("content type header to be valid");
+
+    request.respond(Response::from_string(body).with_header(header))
+}
+
+fn find<'a>(changelog: &'a Changelog, name: &str) -> Option<&'a Repository> {
+    changelog
+        .repositories
+        .iter()
+        .find(|repository| repository.name == name)
+}
@@ -0,0 +1,177 @@
+//! # Show module
+//!
+//! The show module renders a single release to the terminal without
+//! touching any file.
+use std::{
+    collections::BTreeMap,
+    convert::TryFrom,
+    error::Error,
+    io::{stdout, IsTerminal},
+};
+
+use git2 as git;
+use structopt::StructOpt;
+
+use crate::{
+    bump::Release,
+    cmd,
+    conf::{self, Configuration},
+    parser::{Changelog, MarkdownChangelog, Repository, Tag},
+    renderer::TermChangelog,
+    Args,
+};
+
+#[derive(StructOpt, Clone, Debug)]
+pub struct Show {
+    /// Name of the tag to show
+    pub tag: String,
+
+    /// Show only the release of the repository with the given name
+    #[structopt(short = "n", long = "repository")]
+    pub repository: Option<String>,
+
+    /// Render the release as markdown, even when the output is a terminal
+    #[structopt(short = "m", long = "markdown")]
+    pub markdown: bool,
+
+    /// Show only the entries added since the previous pre-release of the same
+    /// version (e.g. v1.0.0-rc.1 for v1.0.0-rc.2)
+    #[structopt(short = "d", long = "delta")]
+    pub delta: bool,
+}
+
+impl Show {
+    pub fn execute(&self, args: &Args) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let conf = cmd::configuration(args)?;
+        if self.delta {
+            let repositories = self.deltas(&conf)?;
+            if repositories.is_empty() {
+                return Err(format!("could not find tag '{}'", self.tag).into());
+            }
+
+            return self.print(Changelog::from(conf.as_ref()).with(repositories));
+        }
+
+        let mut changelog = Changelog::try_from(conf)
+            .map_err(|err| format!("could not generate the changelog, {}", err))?;
+
+        let repositories: Vec<_> = std::mem::take(&mut changelog.repositories)
+            .into_iter()
+            .filter(|repository| match self.repository {
+                Some(ref name) => &repository.name == name,
+                None => true,
+            })
+            .filter_map(|repository| {
+                let tag = repository
+                    .tags
+                    .into_iter()
+                    .find(|tag| tag.name == self.tag)?;
+
+                let mut repository = Repository::from(repository.name);
+                repository.tags.push(tag);
+                Some(repository)
+            })
+            .collect();
+
+        if repositories.is_empty() {
+            return Err(format!("could not find tag '{}'", self.tag).into());
+        }
+
+        self.print(changelog.with(repositories))
+    }
+
+    fn print(&self, changelog: Changelog) -> Result<(), Box<dyn Error + Send + Sync>> {
+        if self.markdown || !stdout().is_terminal() {
+            print!("{}", MarkdownChangelog::from(changelog));
+        } else {
+            print!("{}", TermChangelog::from(changelog));
+        }
+
+        Ok(())
+    }
+
+    /// Returns the entries added since the previous pre-release of the same
+    /// version, in the repositories holding the tag
+    fn deltas(
+        &self,
+        conf: &Configuration,
+    ) -> Result<Vec<Repository>, Box<dyn Error + Send + Sync>> {
+        let release = Release::parse(&self.tag)
+            .filter(|release| !release.version.pre.is_empty())
+            .ok_or_else(|| format!("could not use tag '{}', it is not a pre-release", self.tag))?;
+
+        let mut repositories = vec![];
+        for repository in &conf.repositories {
+            if let Some(ref name) = self.repository {
+                if name != &repository.name {
+                    continue;
+                }
+            }
+
+            let repo = git::Repository::discover(&repository.path).map_err(|err| {
+                format!(
+                    "could not retrieve git repository at '{:?}', {}",
+                    repository.path, err
+                )
+            })?;
+
+            let names = repo
+                .tag_names(None)
+                .map_err(|err| format!("could not retrieve git tags, {}", err))?;
+
+            if !names.iter().flatten().any(|name| name == self.tag) {
+                continue;
+            }
+
+            let previous = names
+                .iter()
+                .flatten()
+                .filter_map(|name| Some((name, Release::parse(name)?)))
+                .filter(|(_, other)| {
+                    other.prefix == release.prefix
+                        && other.version.major == release.version.major
+                        && other.version.minor == release.version.minor
+                        && other.version.patch == release.version.patch
+                        && !other.version.pre.is_empty()
+                        && other.version.pre < release.version.pre
+                })
+                .max_by(|a, b| a.1.version.cmp(&b.1.version))
+                .map(|(name, _)| name.to_string())
+                .ok_or_else(|| {
+                    format!(
+                        "could not find a pre-release of the same version before '{}' in '{}'",
+                        self.tag, repository.name
+                    )
+                })?;
+
+            let ranged = conf::Repository {
+                range: Some(format!("{}..{}", previous, self.tag)),
+                ..repository.to_owned()
+            };
+
+            let parsed = Repository::try_from((&conf.kinds, &ranged)).map_err(|err| {
+                format!(
+                    "could not process repository '{}', {}",
+                    repository.name, err
+                )
+            })?;
+
+            let mut commits: BTreeMap<String, Vec<_>> = BTreeMap::new();
+            for tag in parsed.tags {
+                for (kind, entries) in tag.commits {
+                    commits.entry(kind).or_default().extend(entries);
+                }
+            }
+
+            let mut delta = Repository::from(repository.name.to_owned());
+            delta.tags.push(Tag::from((
+                format!("{} (since {})", self.tag, previous),
+                commits,
+            )));
+
+            repositories.push(delta);
+        }
+
+        Ok(repositories)
+    }
+}
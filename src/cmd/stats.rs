@@ -0,0 +1,129 @@
+//! # Stats module
+//!
+//! The stats module prints statistics of the parsed history of each
+//! repository, useful to tune the kinds and scopes of the configuration.
+use std::{collections::HashMap, convert::TryFrom, error::Error};
+
+use structopt::StructOpt;
+
+use crate::{
+    cmd,
+    parser::{Changelog, Skip},
+    Args,
+};
+
+#[derive(StructOpt, Clone, Debug)]
+pub struct Stats {
+    /// Print statistics of the repository with the given name only
+    #[structopt(short = "n", long = "repository")]
+    pub repository: Option<String>,
+}
+
+/// Print the counters sorted by decreasing count, then by name
+fn print(title: &str, counters: HashMap<&str, usize>) {
+    let mut counters: Vec<_> = counters.into_iter().collect();
+    counters.sort_unstable_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+
+    println!("  {}:", title);
+    if counters.is_empty() {
+        println!("    none");
+    }
+
+    for (name, count) in counters {
+        println!("    {:<32} {:>6}", name, count);
+    }
+}
+
+impl Stats {
+    pub fn execute(&self, args: &Args) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let conf = cmd::configuration(args)?;
+        let changelog = Changelog::try_from(conf)
+            .map_err(|err| format!("could not generate the changelog, {}", err))?;
+
+        for repository in &changelog.repositories {
+            if self
+                .repository
+                .as_ref()
+                .is_some_and(|name| name != &repository.name)
+            {
+                continue;
+            }
+
+            let mut kinds = HashMap::new();
+            let mut scopes = HashMap::new();
+            let mut authors = HashMap::new();
+            let mut entries = 0;
+
+            for commit in repository
+                .tags
+                .iter()
+                .flat_map(|tag| tag.commits.values().flatten())
+            {
+                entries += 1;
+                *kinds.entry(commit.kind.as_str()).or_insert(0) += 1;
+                *authors.entry(commit.author.as_str()).or_insert(0) += 1;
+
+                if let Some(ref scope) = commit.scope {
+                    for scope in scope.split(',') {
+                        *scopes.entry(scope.trim()).or_insert(0) += 1;
+                    }
+                }
+            }
+
+            let mut unknown = HashMap::new();
+            let mut failures = vec![];
+            let mut merges = 0;
+            let mut filtered = 0;
+            for commit in &repository.skipped {
+                match commit.reason {
+                    Skip::Merge => merges += 1,
+                    Skip::Unparseable => failures.push(commit),
+                    Skip::UnknownKind(ref kind) => *unknown.entry(kind.as_str()).or_insert(0) += 1,
+                    Skip::Filtered(_) => filtered += 1,
+                }
+            }
+
+            let walked = entries + repository.skipped.len();
+            let candidates = walked - merges - filtered;
+            let rate = if candidates == 0 {
+                0.0
+            } else {
+                failures.len() as f64 * 100.0 / candidates as f64
+            };
+
+            println!("{}", repository.name);
+            println!("  commits walked: {}", walked);
+            println!("  entries: {}", entries);
+            println!("  merge commits: {}", merges);
+            println!("  filtered commits: {}", filtered);
+            println!(
+                "  parse failures: {} ({:.1}% of non-merge commits)",
+                failures.len(),
+                rate
+            );
+
+            for commit in failures.iter().take(10) {
+                println!("    {} {}", commit.hash, commit.message);
+            }
+
+            if failures.len() > 10 {
+                println!("    and {} more", failures.len() - 10);
+            }
+
+            print("unknown kinds", unknown);
+            print("kinds", kinds);
+            print("scopes", scopes);
+            print("authors", authors);
+
+            println!("  releases:");
+            for tag in &repository.tags {
+                let size: usize = tag.commits.values().map(Vec::len).sum();
+                println!("    {:<32} {:>6}", tag.name, size);
+            }
+
+            println!();
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,293 @@
+//! # Validate module
+//!
+//! The validate module checks the configuration before any generation is
+//! attempted and reports all problems found.
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+    path::Path,
+};
+
+use chrono::Utc;
+use git2 as git;
+use regex::Regex;
+use structopt::StructOpt;
+
+use crate::{
+    bump, cmd,
+    conf::{self, Configuration},
+    dates,
+    exit::{self, Code},
+    parser::{self, LINK_VARIABLES},
+    Args,
+};
+
+/// Problem found in the configuration, located by its field
+#[derive(Clone, Debug)]
+pub struct Problem {
+    pub field: String,
+    pub message: String,
+}
+
+impl Display for Problem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.field, self.message)
+    }
+}
+
+#[derive(StructOpt, Clone, Debug)]
+pub struct Validate {}
+
+impl Validate {
+    pub fn execute(&self, args: &Args) -> Result<(), Box<dyn Error + Send + Sync>> {
+        // Unknown entries are ignored when loading, they are likely typos which
+        // could explain why the configuration does not load
+        let unknown = match args.path {
+            Some(_) => vec![],
+            None => conf::unknown(
+                &Configuration::entries(&args.config, args.profile.as_deref())
+                    .map_err(|err| exit::with(Code::Configuration, err))?,
+            ),
+        };
+
+        let mut problems = vec![];
+        for (field, suggestion) in unknown {
+            let message = match suggestion {
+                Some(suggestion) => format!("unknown entry, did you mean '{}'?", suggestion),
+                None => String::from("unknown entry"),
+            };
+
+            let problem = Problem { field, message };
+            eprintln!("{}: {}", args.config.display(), problem);
+            problems.push(problem);
+        }
+
+        let conf = cmd::configuration(args)?;
+        let found = validate(&conf);
+        for problem in &found {
+            eprintln!("{}: {}", args.config.display(), problem);
+        }
+
+        problems.extend(found);
+
+        if !problems.is_empty() {
+            return Err(exit::with(
+                Code::Configuration,
+                format!(
+                    "configuration '{}' has {} problem(s)",
+                    args.config.display(),
+                    problems.len()
+                )
+                .into(),
+            ));
+        }
+
+        println!("Configuration is healthy");
+        Ok(())
+    }
+}
+
+/// Returns the variables referenced by a link layout, `{{` and `}}` are
+/// escaped braces and a format specification could follow the name after a
+/// colon.
+pub fn placeholders(layout: &str) -> Result<Vec<String>, String> {
+    let mut names = vec![];
+    let mut chars = layout.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+            }
+            '{' => {
+                let mut name = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => name.push(c),
+                        None => return Err(String::from("unclosed placeholder")),
+                    }
+                }
+
+                let name = name.split(':').next().unwrap_or_default().trim();
+                names.push(name.to_owned());
+            }
+            '}' => return Err(String::from("unmatched closing brace")),
+            _ => {}
+        }
+    }
+
+    Ok(names)
+}
+
+/// Check the configuration and returns every problem found
+pub fn validate(conf: &Configuration) -> Vec<Problem> {
+    let mut problems = vec![];
+    let mut problem = |field: String, message: String| problems.push(Problem { field, message });
+
+    if conf.kinds.is_empty() {
+        problem(
+            String::from("kinds"),
+            String::from("at least one kind should be defined"),
+        );
+    }
+
+    if conf.repositories.is_empty() {
+        problem(
+            String::from("repositories"),
+            String::from("at least one repository should be defined"),
+        );
+    }
+
+    if let Some(ref badge) = conf.badge {
+        if !conf.repositories.iter().any(|r| &r.name == badge) {
+            problem(
+                String::from("badge"),
+                format!("repository '{}' is not configured", badge),
+            );
+        }
+    }
+
+    if let Some(ref channel) = conf.prerelease {
+        if let Err(err) = bump::channel(channel) {
+            problem(String::from("prerelease"), err);
+        }
+    }
+
+    if let Err(err) = dates::offset(&conf.dates.timezone, &Utc::now().into()) {
+        problem(String::from("dates.timezone"), err.to_string());
+    }
+
+    for (output, format) in &conf.dates.formats {
+        if let Err(err) = dates::check(format) {
+            problem(format!("dates.formats.{}", output), err.to_string());
+        }
+    }
+
+    for (idx, rewrite) in conf.rewrites.iter().enumerate() {
+        if let Err(err) = Regex::new(&rewrite.pattern) {
+            problem(
+                format!("rewrites[{}].pattern", idx),
+                format!("could not compile pattern '{}', {}", rewrite.pattern, err),
+            );
+        }
+    }
+
+    for (idx, repository) in conf.repositories.iter().enumerate() {
+        let field = |name: &str| format!("repositories[{}].{}", idx, name);
+
+        if !Path::new(&repository.path).exists() {
+            problem(
+                field("path"),
+                format!("'{}' does not exist", repository.path.display()),
+            );
+            continue;
+        }
+
+        let repo = match git::Repository::discover(&repository.path) {
+            Ok(repo) => Some(repo),
+            Err(err) => {
+                problem(
+                    field("path"),
+                    format!(
+                        "'{}' is not a git repository, {}",
+                        repository.path.display(),
+                        err.message()
+                    ),
+                );
+                None
+            }
+        };
+
+        if let (Some(repo), Some(range)) = (&repo, &repository.range) {
+            if let Err(err) = repo.revparse(range) {
+                problem(
+                    field("range"),
+                    format!("could not parse range '{}', {}", range, err.message()),
+                );
+            }
+        }
+
+        if let Some(ref link) = repository.link {
+            match placeholders(link) {
+                Ok(names) => {
+                    for name in names {
+                        if !LINK_VARIABLES.contains(&name.as_str()) {
+                            problem(
+                                field("link"),
+                                format!(
+                                    "unknown variable '{}', available variables are: {}",
+                                    name,
+                                    LINK_VARIABLES.join(", ")
+                                ),
+                            );
+                        }
+                    }
+                }
+                Err(err) => problem(field("link"), format!("could not parse layout, {}", err)),
+            }
+        }
+
+        let lists = [
+            ("authors_include", &repository.authors_include),
+            ("authors_exclude", &repository.authors_exclude),
+            ("bots.patterns", &repository.bots.patterns),
+            ("dependencies.patterns", &repository.dependencies.patterns),
+        ];
+
+        for (name, patterns) in lists {
+            for (i, pattern) in patterns.iter().enumerate() {
+                if let Err(err) = parser::patterns(std::slice::from_ref(pattern)) {
+                    problem(
+                        field(&format!("{}[{}]", name, i)),
+                        format!("could not compile pattern '{}', {}", pattern, err),
+                    );
+                }
+            }
+        }
+
+        let patterns = [
+            ("include_pattern", &repository.include_pattern),
+            ("exclude_pattern", &repository.exclude_pattern),
+        ];
+
+        for (name, pattern) in patterns {
+            if let Some(pattern) = pattern {
+                if let Err(err) = Regex::new(pattern) {
+                    problem(
+                        field(name),
+                        format!("could not compile pattern '{}', {}", pattern, err),
+                    );
+                }
+            }
+        }
+
+        for (i, variant) in repository.grammar.iter().enumerate() {
+            match Regex::new(&variant.pattern) {
+                Ok(re) if !re.capture_names().any(|name| name == Some("kind")) => problem(
+                    field(&format!("grammar[{}].pattern", i)),
+                    format!("pattern of '{}' has no kind group", variant.name),
+                ),
+                Ok(_) => {}
+                Err(err) => problem(
+                    field(&format!("grammar[{}].pattern", i)),
+                    format!("could not compile pattern '{}', {}", variant.pattern, err),
+                ),
+            }
+        }
+
+        if let Some(ref scopes) = repository.scopes {
+            if scopes.is_empty() {
+                problem(
+                    field("scopes"),
+                    String::from("no scope is accepted, omit the field to accept all scopes"),
+                );
+            }
+        }
+    }
+
+    problems
+}
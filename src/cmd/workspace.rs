@@ -0,0 +1,213 @@
+//! # Workspace module
+//!
+//! The workspace module reads the members of a cargo workspace and writes one
+//! changelog per crate, using the commits touching the crate and the tags
+//! named after it (e.g. `cratename-v1.2.3`), along with a combined one.
+use std::{
+    convert::TryFrom,
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use git2 as git;
+use slog_scope::info;
+use structopt::StructOpt;
+
+use crate::{
+    cmd,
+    conf::{self, Configuration},
+    parser::Changelog,
+    renderer, Args,
+};
+
+#[derive(StructOpt, Clone, Debug)]
+pub struct Workspace {
+    /// Use the workspace of the repository with the given name instead of
+    /// the one containing the current directory
+    #[structopt(short = "n", long = "repository")]
+    pub repository: Option<String>,
+
+    /// Name of the changelog written in the directory of each crate
+    #[structopt(long = "crate-output", default_value = "CHANGELOG")]
+    pub crate_output: PathBuf,
+}
+
+/// Crate of the workspace
+#[derive(Clone, Debug)]
+pub struct Member {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+impl Workspace {
+    pub fn execute(&self, args: &Args) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let conf = cmd::configuration(args)?;
+        let repository = cmd::target(&conf, self.repository.as_deref())?;
+
+        let repo = git::Repository::discover(&repository.path).map_err(|err| {
+            format!(
+                "could not retrieve git repository at '{:?}', {}",
+                repository.path, err
+            )
+        })?;
+
+        let workdir = repo
+            .workdir()
+            .ok_or("could not read the workspace of a bare repository")?;
+
+        let members = members(workdir)?;
+        if members.is_empty() {
+            return Err(format!(
+                "could not find any crate in the workspace at '{:?}'",
+                workdir
+            )
+            .into());
+        }
+
+        let repositories = members
+            .iter()
+            .map(|member| conf::Repository {
+                name: member.name.to_owned(),
+                paths: Some(vec![member.path.to_owned()]),
+                tag_prefix: Some(format!("{}-v", member.name)),
+                // A crate is a package already, it should not be split again
+                monorepo: false,
+                ..repository.to_owned()
+            })
+            .collect();
+
+        let workspace = Rc::new(Configuration {
+            repositories,
+            ..(*conf).to_owned()
+        });
+
+        let changelog = Changelog::try_from(workspace)
+            .map_err(|err| format!("could not generate the changelog, {}", err))?;
+
+        // Repositories could be reordered or skipped, they are matched by name
+        for member in &members {
+            let repository = match changelog
+                .repositories
+                .iter()
+                .find(|repository| repository.name == member.name)
+            {
+                Some(repository) => repository,
+                None => {
+                    info!("Skip crate without changelog"; "crate" => &member.name);
+                    continue;
+                }
+            };
+
+            let (extension, content) =
+                renderer::render(&args.format, changelog.with(vec![repository.to_owned()]))?;
+
+            let mut output = workdir.join(&member.path).join(&self.crate_output);
+            output.set_extension(extension);
+            if args.dry_run {
+                println!("Would write {} ({} bytes)", output.display(), content.len());
+                continue;
+            }
+
+            cmd::write(&output, &content)?;
+
+            info!("Write crate changelog"; "crate" => &member.name, "output" => output.to_string_lossy().to_string());
+        }
+
+        let (extension, content) = renderer::render(&args.format, changelog)?;
+        let mut output = args.output.to_owned();
+        output.set_extension(extension);
+        if args.dry_run {
+            println!("Would write {} ({} bytes)", output.display(), content.len());
+            return Ok(());
+        }
+
+        cmd::write(&output, &content)?;
+
+        Ok(())
+    }
+}
+
+/// Returns the crates of the workspace at the given path, glob patterns are
+/// expanded to the directories holding a manifest
+pub fn members(root: &Path) -> Result<Vec<Member>, Box<dyn Error + Send + Sync>> {
+    let manifest = read(&root.join("Cargo.toml"))?;
+    let workspace = manifest.get("workspace").ok_or_else(|| {
+        format!(
+            "could not find a workspace in '{:?}'",
+            root.join("Cargo.toml")
+        )
+    })?;
+
+    let patterns = |key: &str| -> Vec<String> {
+        workspace
+            .get(key)
+            .and_then(toml::Value::as_array)
+            .map(|values| {
+                values
+                    .iter()
+                    .filter_map(toml::Value::as_str)
+                    .map(String::from)
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    let excluded: Vec<PathBuf> = patterns("exclude").iter().map(PathBuf::from).collect();
+    let mut paths = vec![];
+    for pattern in patterns("members") {
+        if glob::Pattern::escape(&pattern) == pattern {
+            paths.push(PathBuf::from(pattern));
+            continue;
+        }
+
+        let entries = glob::glob(&root.join(&pattern).to_string_lossy())
+            .map_err(|err| format!("could not parse member '{}', {}", pattern, err))?;
+
+        let mut expanded = vec![];
+        for entry in entries {
+            let entry = entry.map_err(|err| format!("could not read directory entry, {}", err))?;
+
+            if entry.join("Cargo.toml").is_file() {
+                if let Ok(path) = entry.strip_prefix(root) {
+                    expanded.push(path.to_path_buf());
+                }
+            }
+        }
+
+        expanded.sort();
+        paths.extend(expanded);
+    }
+
+    let mut members = vec![];
+    for path in paths {
+        if excluded.contains(&path) {
+            continue;
+        }
+
+        let manifest = read(&root.join(&path).join("Cargo.toml"))?;
+        let name = manifest
+            .get("package")
+            .and_then(|package| package.get("name"))
+            .and_then(toml::Value::as_str)
+            .ok_or_else(|| format!("could not find the name of the crate at '{:?}'", path))?;
+
+        members.push(Member {
+            name: name.to_string(),
+            path,
+        });
+    }
+
+    Ok(members)
+}
+
+/// Read and parse the manifest at the given path
+fn read(path: &Path) -> Result<toml::Value, Box<dyn Error + Send + Sync>> {
+    let content = fs::read_to_string(path)
+        .map_err(|err| format!("could not read manifest '{:?}', {}", path, err))?;
+
+    content
+        .parse()
+        .map_err(|err| format!("could not parse manifest '{:?}', {}", path, err).into())
+}
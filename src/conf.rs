@@ -1,34 +1,1255 @@
 //! # Config module
 //!
-//! The configuration module handle the changelog.toml file
+//! The configuration module handle the changelog.toml file, which could also
+//! be written in yaml (`.yaml` or `.yml`) or json (`.json`). Its JSON schema,
+//! returned by [`schema`], is shipped as `changelog.schema.json`.
 
-use std::{collections::HashMap, convert::TryFrom, error::Error, path::PathBuf};
+use std::{
+    collections::HashMap,
+    convert::TryFrom,
+    env,
+    error::Error,
+    path::{Path, PathBuf},
+};
 
-use config::{Config, File};
+use config::{Config, File, FileFormat};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use slog_scope::{debug, warn};
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+use crate::bump::Bump;
+
+/// Prefix of the environment variables overriding the entries of the
+/// configuration file
+pub const ENVIRONMENT_PREFIX: &str = "CHANGELOG__";
+
+/// Kinds based on https://github.com/angular/angular/blob/master/CONTRIBUTING.md#type
+pub const DEFAULT_KINDS: &[(&str, &str)] = &[
+    ("build", "Build improvements"),
+    ("ci", "Continuous integration improvements"),
+    ("docs", "Documentation enhancements"),
+    ("feat", "Features"),
+    ("fix", "Fix changes"),
+    ("perf", "Performance improvements"),
+    ("refactor", "Refactor enhancements"),
+    ("style", "Style changes"),
+    ("test", "Unit test changes"),
+    ("infra", "Infrastructure changes"),
+    ("chore", "Chore tasks"),
+];
+
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug)]
 pub struct Repository {
     pub name: String,
     pub path: PathBuf,
     pub scopes: Option<Vec<String>>,
     pub range: Option<String>,
+    /// Branch walked instead of HEAD (e.g. the default branch of a bare
+    /// repository whose HEAD does not point to it)
+    pub branch: Option<String>,
+    /// Heading grouping the repository with others in the combined changelog
+    /// (e.g. Backend)
+    pub group: Option<String>,
     pub link: Option<String>,
+    /// Number of characters of the displayed hashes
+    #[serde(default = "default_hash_length")]
+    pub hash_length: usize,
+    /// Keep only the commits touching these paths, relative to the root of
+    /// the git repository
+    pub paths: Option<Vec<PathBuf>>,
+    /// Use only the tags starting with this prefix as releases (e.g. `cratename-v`)
+    pub tag_prefix: Option<String>,
+    /// Split the repository into one entry per package, using tags of the
+    /// form `pkg@1.2.3` or `component/v1.2.3`
+    #[serde(default)]
+    pub monorepo: bool,
+    /// Paths of the packages of a monorepo, commits of a package are the ones
+    /// touching its path if given
+    #[serde(default)]
+    pub packages: HashMap<String, PathBuf>,
+    /// Hashes (or prefixes of hashes) of the commits to exclude
+    #[serde(default)]
+    pub skip_commits: Vec<String>,
+    /// Tokens which exclude a commit when found anywhere in its message
+    #[serde(default = "default_skip_markers")]
+    pub skip_markers: Vec<String>,
+    /// Keep only the commits whose author name or email matches one of these
+    /// patterns, all authors are kept if empty
+    #[serde(default)]
+    pub authors_include: Vec<String>,
+    /// Exclude the commits whose author name or email matches one of these patterns
+    #[serde(default)]
+    pub authors_exclude: Vec<String>,
+    /// Handling of the commits authored by bots
+    #[serde(default)]
+    pub bots: Bots,
+    /// Collapsing of the dependency updates into a single entry
+    #[serde(default)]
+    pub dependencies: Dependencies,
+    /// Keep only the parsed commits whose full message matches this pattern
+    pub include_pattern: Option<String>,
+    /// Exclude the parsed commits whose full message matches this pattern
+    pub exclude_pattern: Option<String>,
+    /// Keep only one entry for the cherry-picks of a commit and for the
+    /// commits sharing the same summary
+    #[serde(default)]
+    pub deduplicate: bool,
+    /// Highlight the entries of the authors contributing for the first time
+    /// in the walked history
+    #[serde(default)]
+    pub first_contributions: bool,
+    /// Compute the files changed, insertions and deletions of each commit
+    #[serde(default)]
+    pub diffstat: bool,
+    /// Split the history into a section per week or month if the repository
+    /// has no tags
+    pub periods: Option<Period>,
+    /// Store the parsed commits in the git directory of the repository, in
+    /// order to only parse the new ones on the next runs
+    #[serde(default)]
+    pub cache: bool,
+    /// Read the commits from the output of git log stored in the given file
+    /// (- for the standard input) instead of opening the repository
+    pub log: Option<PathBuf>,
+    /// Additional variants of the grammar of the messages, tried in order
+    /// before the conventional one
+    #[serde(default)]
+    pub grammar: Vec<Variant>,
+    /// Fail on the commits which do not follow the convention (unparseable
+    /// message, unknown kind or scope) instead of skipping them, overrides
+    /// the strict mode of the configuration
+    pub strict: Option<bool>,
+    /// Handling of the repository when its branch has no commits yet
+    #[serde(default)]
+    pub empty: EmptyPolicy,
+    /// Fetch the whole history when the repository is a shallow clone (e.g.
+    /// on continuous integration runners)
+    #[serde(default)]
+    pub unshallow: bool,
+    /// Expand the updates of the submodules into their commits, gathered in
+    /// a section named after the path of each submodule
+    #[serde(default)]
+    pub submodules: bool,
+    /// Check the signatures of the commits and the annotated tags, and render
+    /// whether they are verified
+    #[serde(default)]
+    pub verify: bool,
+    /// Display the names of the people who signed off each entry, using a
+    /// Signed-off-by trailer
+    #[serde(default)]
+    pub sign_offs: bool,
+}
+
+/// What to do with a repository whose branch has no commits yet (e.g. a
+/// freshly initialized repository)
+#[derive(Serialize, Deserialize, JsonSchema, Default, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum EmptyPolicy {
+    /// Fail the generation
+    #[default]
+    Fail,
+    /// Leave the repository out of the changelog
+    Skip,
+    /// Render the repository without any release
+    Render,
+}
+
+/// Named variant of the grammar of the messages
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug)]
+pub struct Variant {
+    pub name: String,
+    /// Regular expression holding a kind group and optionally the scope and
+    /// breaking groups
+    pub pattern: String,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug)]
+pub struct Dependencies {
+    /// Collapse the dependency updates of each release into a single entry
+    #[serde(default)]
+    pub collapse: bool,
+    /// Title of the collapsed entry
+    #[serde(default = "default_dependencies_title")]
+    pub title: String,
+    /// Patterns matching the message of dependency updates
+    #[serde(default = "default_dependencies_patterns")]
+    pub patterns: Vec<String>,
+    /// Consider the commits authored by bots as dependency updates
+    #[serde(default = "default_true")]
+    pub bots: bool,
+    /// Minimum number of dependency updates in a release to collapse them
+    #[serde(default = "default_dependencies_minimum")]
+    pub minimum: usize,
+}
+
+impl Default for Dependencies {
+    fn default() -> Self {
+        Self {
+            collapse: false,
+            title: default_dependencies_title(),
+            patterns: default_dependencies_patterns(),
+            bots: default_true(),
+            minimum: default_dependencies_minimum(),
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_dependencies_title() -> String {
+    String::from("Dependency updates")
+}
+
+fn default_dependencies_patterns() -> Vec<String> {
+    vec![
+        String::from(r"^(build|chore|fix)\(deps(-dev)?\)"),
+        String::from("^bump "),
+        String::from("^update dependency "),
+    ]
+}
+
+fn default_dependencies_minimum() -> usize {
+    2
+}
+
+/// What to do with the commits authored by bots
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum BotPolicy {
+    /// Keep them along with the other commits
+    Keep,
+    /// Exclude them from the changelog
+    Skip,
+    /// Divert them into a dedicated section of each release
+    Section,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug)]
+pub struct Bots {
+    #[serde(default = "default_bot_policy")]
+    pub policy: BotPolicy,
+    /// Title of the section holding the commits of bots
+    #[serde(default = "default_bot_section")]
+    pub section: String,
+    /// Patterns matching the name or the email of bots
+    #[serde(default = "default_bot_patterns")]
+    pub patterns: Vec<String>,
+}
+
+impl Default for Bots {
+    fn default() -> Self {
+        Self {
+            policy: default_bot_policy(),
+            section: default_bot_section(),
+            patterns: default_bot_patterns(),
+        }
+    }
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+fn default_bot_policy() -> BotPolicy {
+    BotPolicy::Keep
+}
+
+fn default_bot_section() -> String {
+    String::from("Automated updates")
+}
+
+/// Returns the patterns matching common bots, e.g. dependabot[bot] or renovate[bot]
+fn default_bot_patterns() -> Vec<String> {
+    vec![
+        String::from(r"\[bot\]"),
+        String::from("^dependabot"),
+        String::from("^renovate"),
+        String::from("^github-actions"),
+    ]
+}
+
+fn default_hash_length() -> usize {
+    7
+}
+
+fn default_plugin_fuel() -> u64 {
+    10_000_000
+}
+
+/// Returns the tokens excluding a commit, mirroring the `[skip ci]` convention
+fn default_skip_markers() -> Vec<String> {
+    vec![
+        String::from("[skip changelog]"),
+        String::from("[changelog skip]"),
+    ]
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug)]
 pub struct Configuration {
     pub kinds: HashMap<String, String>,
+    /// Part of the version incremented by each kind (none, patch, minor or major)
+    #[serde(default)]
+    pub bumps: HashMap<String, Bump>,
+    /// Channel of the next releases (e.g. beta), releases are stable if omitted
+    pub prerelease: Option<String>,
+    /// Merging of the small sections of each release
+    #[serde(default)]
+    pub other_changes: OtherChanges,
+    /// Display names of the authors by email, applied after the .mailmap
+    #[serde(default)]
+    pub authors: HashMap<String, String>,
+    /// Rendering of the authors, for changelogs which should not disclose them
+    #[serde(default = "default_anonymization")]
+    pub anonymize: Anonymization,
+    /// Rules rewriting the rendered subjects in order, before their normalization
+    #[serde(default)]
+    pub rewrites: Vec<Rewrite>,
+    /// Normalizations applied to the rendered subjects
+    #[serde(default)]
+    pub normalize: Normalization,
+    /// Language of the fixed strings of the generated changelog
+    #[serde(default)]
+    pub language: Language,
+    /// Selection, timezone and formats of the dates of the commits
+    #[serde(default)]
+    pub dates: Dates,
+    /// Order of the entries within each section
+    #[serde(default)]
+    pub sort: Sort,
+    /// Grouping of the entries of the rendered changelog
+    #[serde(default)]
+    pub layout: Layout,
+    /// Names of the repositories in the order they are rendered, the others
+    /// follow in the order of their declaration
+    #[serde(default)]
+    pub order: Vec<String>,
+    /// Paths to the WebAssembly plugins, called in order on the entries
+    #[serde(default)]
+    pub plugins: Vec<PathBuf>,
+    /// Fuel given to each call of a plugin, roughly one unit per executed
+    /// instruction, the call traps once it is consumed
+    #[serde(default = "default_plugin_fuel")]
+    pub plugin_fuel: u64,
+    /// Paths to the Rhai scripts, run in order on the parsed commits
+    #[serde(default)]
+    pub scripts: Vec<PathBuf>,
+    /// Render a table summarizing the latest release of each repository at
+    /// the top of the html changelog
+    #[serde(default)]
+    pub overview: bool,
+    /// Name of the repository described by the badge format, required when
+    /// the changelog holds several repositories
+    pub badge: Option<String>,
+    /// Fail on the commits which do not follow the convention (unparseable
+    /// message, unknown kind or scope) instead of skipping them
+    #[serde(default)]
+    pub strict: bool,
+    /// Rules checked by the lint subcommand in addition to the convention
+    #[serde(default)]
+    pub lint: Lint,
     pub repositories: Vec<Repository>,
 }
 
+/// Rules of the lint subcommand, each one is disabled unless configured
+#[derive(Serialize, Deserialize, JsonSchema, Default, Clone, Debug)]
+pub struct Lint {
+    /// The subject should not be longer than the maximum
+    pub subject_length: Option<SubjectLength>,
+    /// The subject should not end with a period
+    pub trailing_period: Option<Rule>,
+    /// The kind should be lowercase
+    pub lowercase_kind: Option<Rule>,
+    /// The subject should have a scope
+    pub scope_required: Option<Rule>,
+    /// The commits of the given kinds should have a body
+    pub body_required: Option<BodyRequired>,
+    /// The subject should be followed by a blank line
+    pub blank_line: Option<Rule>,
+    /// Rules supplied by Rhai scripts, see the rules module of the library
+    #[serde(default)]
+    pub scripts: Vec<Custom>,
+    /// Rules supplied by WebAssembly plugins exporting `lint`
+    #[serde(default)]
+    pub plugins: Vec<Custom>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Default, Clone, Debug)]
+pub struct Rule {
+    #[serde(default)]
+    pub severity: Severity,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug)]
+pub struct SubjectLength {
+    /// Maximum number of characters of the subject
+    pub max: usize,
+    #[serde(default)]
+    pub severity: Severity,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug)]
+pub struct BodyRequired {
+    pub kinds: Vec<String>,
+    #[serde(default)]
+    pub severity: Severity,
+}
+
+/// Lint rule supplied by a plugin or a script
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug)]
+pub struct Custom {
+    pub path: PathBuf,
+    #[serde(default)]
+    pub severity: Severity,
+}
+
+/// Outcome of a broken lint rule
+#[derive(Serialize, Deserialize, JsonSchema, Default, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// The commit is reported and the lint fails
+    #[default]
+    Error,
+    /// The commit is reported only
+    Warning,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug)]
+pub struct Rewrite {
+    /// Regular expression matching the part of the subject to rewrite
+    pub pattern: String,
+    /// Replacement of the matches, which may reference the groups of the
+    /// pattern (e.g. $1 or ${name})
+    pub replacement: String,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Default, Clone, Debug)]
+pub struct Normalization {
+    /// Capitalize the first letter of the description
+    #[serde(default)]
+    pub capitalize: bool,
+    /// Strip the trailing period of the description
+    #[serde(default)]
+    pub strip_period: bool,
+    /// Replacements of the first word of the description, in order to use
+    /// the imperative mood (e.g. Added by Add)
+    #[serde(default)]
+    pub imperative: HashMap<String, String>,
+}
+
+/// Grouping of the entries of the rendered changelog
+#[derive(Serialize, Deserialize, JsonSchema, Default, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Layout {
+    /// Group by repository, then by release and by kind
+    #[default]
+    Repository,
+    /// Group by kind, then by repository, which reads better for the release
+    /// notes of a product made of several repositories
+    Kind,
+}
+
+/// Length of the sections of a repository without tags
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Period {
+    /// ISO week, e.g. 2024-W05
+    Week,
+    /// Calendar month, e.g. 2024-02
+    Month,
+}
+
+/// Key sorting the entries of a section
+#[derive(Serialize, Deserialize, JsonSchema, Default, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SortKey {
+    /// Keep the order of the history
+    #[default]
+    None,
+    /// Sort by the date of the commits
+    Date,
+    /// Sort by the scope of the commits, entries without scope come first
+    Scope,
+    /// Sort alphabetically by the rendered subject, ignoring case
+    Message,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Default, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    #[default]
+    Ascending,
+    Descending,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Default, Clone, Copy, Debug)]
+pub struct Sort {
+    #[serde(default)]
+    pub by: SortKey,
+    #[serde(default)]
+    pub order: SortOrder,
+}
+
+/// Date of the commits to use
+#[derive(Serialize, Deserialize, JsonSchema, Default, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DateSource {
+    /// Date at which the change has been authored
+    Author,
+    /// Date at which the change has been committed
+    #[default]
+    Committer,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug)]
+pub struct Dates {
+    #[serde(default)]
+    pub source: DateSource,
+    /// Timezone of the dates, either utc, local, commit (the offset recorded
+    /// in the commit) or a fixed offset (e.g. +02:00)
+    #[serde(default = "default_timezone")]
+    pub timezone: String,
+    /// Strftime formats of the rendered dates by output format (markdown or html)
+    #[serde(default)]
+    pub formats: HashMap<String, String>,
+    /// Render the dates of the first and last commits next to each release
+    #[serde(default)]
+    pub ranges: bool,
+}
+
+impl Default for Dates {
+    fn default() -> Self {
+        Self {
+            source: DateSource::default(),
+            timezone: default_timezone(),
+            formats: HashMap::new(),
+            ranges: false,
+        }
+    }
+}
+
+fn default_timezone() -> String {
+    String::from("utc")
+}
+
+/// Languages of the generated changelog
+#[derive(Serialize, Deserialize, JsonSchema, Default, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Language {
+    #[default]
+    En,
+    Fr,
+}
+
+/// How to render the authors of the commits
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Anonymization {
+    /// Render the name of the authors
+    None,
+    /// Render the initials of the authors (e.g. J.D. for John Doe)
+    Initials,
+    /// Render a short hash of the email of the authors
+    Hash,
+    /// Do not render the authors at all
+    Omit,
+}
+
+fn default_anonymization() -> Anonymization {
+    Anonymization::None
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug)]
+pub struct OtherChanges {
+    /// Sections with fewer entries are merged, nothing is merged if zero
+    #[serde(default)]
+    pub threshold: usize,
+    /// Title of the section holding the merged entries
+    #[serde(default = "default_other_changes_title")]
+    pub title: String,
+}
+
+impl Default for OtherChanges {
+    fn default() -> Self {
+        Self {
+            threshold: 0,
+            title: default_other_changes_title(),
+        }
+    }
+}
+
+fn default_other_changes_title() -> String {
+    String::from("Other changes")
+}
+
+impl Default for Configuration {
+    /// Configuration holding the default kinds and no repository, the other
+    /// fields take the same default value as in the configuration file
+    fn default() -> Self {
+        let kinds: HashMap<_, _> = DEFAULT_KINDS.iter().copied().collect();
+
+        serde_json::from_value(serde_json::json!({ "kinds": kinds, "repositories": [] }))
+            .expect("default configuration to be valid")
+    }
+}
+
+impl Repository {
+    /// Returns the repository with the given name and path, the other fields
+    /// take the same default value as in the configuration file
+    pub fn new(name: &str, path: PathBuf) -> Self {
+        let mut repository: Self =
+            serde_json::from_value(serde_json::json!({ "name": name, "path": "" }))
+                .expect("default repository to be valid");
+
+        repository.path = path;
+        repository
+    }
+}
+
 impl TryFrom<PathBuf> for Configuration {
     type Error = Box<dyn Error + Send + Sync>;
 
     fn try_from(path: PathBuf) -> Result<Self, Self::Error> {
-        Ok(Config::builder()
-            .add_source(File::from(path).required(true))
-            .build()?
-            .try_deserialize()?)
+        Self::load(&path, None)
+    }
+}
+
+impl Configuration {
+    /// Load the configuration file, the entries of the given profile replace
+    /// the ones of the file
+    pub fn load(path: &Path, profile: Option<&str>) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let mut value = Self::entries(path, profile)?;
+        for (key, suggestion) in unknown(&value) {
+            warn!("Unknown entry in configuration, it is ignored"; "key" => key, "suggestion" => suggestion);
+        }
+
+        discover(&mut value)?;
+        inherit(&mut value);
+
+        let content = serde_json::to_string(&value)
+            .map_err(|err| format!("could not serialize configuration, {}", err))?;
+        let mut builder = Config::builder().add_source(File::from_str(&content, FileFormat::Json));
+
+        // Variables which are not valid unicode could not name an entry
+        for (name, value) in env::vars_os()
+            .filter_map(|(name, value)| Some((name.into_string().ok()?, value.into_string().ok()?)))
+        {
+            if let Some(key) = overridden(&name) {
+                builder = builder.set_override(&key, value).map_err(|err| {
+                    format!(
+                        "could not override '{}' using variable '{}', {}",
+                        key, name, err
+                    )
+                })?;
+            }
+        }
+
+        // The path of the invalid entry is worth more than the bare error
+        let mut conf: Self = serde_path_to_error::deserialize(builder.build()?).map_err(|err| {
+            format!(
+                "could not parse entry '{}' of configuration, {}",
+                err.path(),
+                err.inner()
+            )
+        })?;
+
+        for repository in &mut conf.repositories {
+            repository.path = simplify(&repository.path);
+        }
+
+        Ok(conf)
+    }
+
+    /// Returns the entries of the configuration file, along with the ones of
+    /// the files it includes, once the given profile has been applied
+    pub fn entries(
+        path: &Path,
+        profile: Option<&str>,
+    ) -> Result<Value, Box<dyn Error + Send + Sync>> {
+        let mut value = load(path, &mut vec![], &mut vec![])?;
+        if let Some(map) = value.as_object_mut() {
+            let profiles = map.remove("profiles");
+            if let Some(name) = profile {
+                let entries = profiles
+                    .as_ref()
+                    .and_then(|profiles| profiles.get(name))
+                    .and_then(Value::as_object)
+                    .ok_or_else(|| format!("could not find profile '{}'", name))?;
+
+                for (key, entry) in entries {
+                    map.insert(key.to_owned(), entry.to_owned());
+                }
+            }
+        }
+
+        Ok(value)
+    }
+
+    /// Returns the configuration file along with the files it includes,
+    /// recursively, in order to watch them
+    pub fn files(path: &Path) -> Result<Vec<PathBuf>, Box<dyn Error + Send + Sync>> {
+        let mut files = vec![];
+        load(path, &mut vec![], &mut files)?;
+
+        Ok(files)
+    }
+}
+
+/// Returns the path without its verbatim prefix (e.g. `\\?\C:\repo` or
+/// `\\?\UNC\server\share`, as given by canonicalize on Windows) which is
+/// not supported by libgit2, long paths being handled by libgit2 itself
+pub fn simplify(path: &Path) -> PathBuf {
+    let text = match path.to_str() {
+        Some(text) => text,
+        None => return path.to_owned(),
+    };
+
+    if let Some(unc) = text.strip_prefix(r"\\?\UNC\") {
+        return PathBuf::from(format!(r"\\{}", unc));
+    }
+
+    match text.strip_prefix(r"\\?\") {
+        Some(local) => PathBuf::from(local),
+        None => path.to_owned(),
+    }
+}
+
+/// Returns the path using slashes as separators, so that the names derived
+/// from it, and the links using them, are the same on every platform
+pub fn slashed(path: &Path) -> String {
+    path.to_string_lossy()
+        .replace(std::path::MAIN_SEPARATOR, "/")
+}
+
+/// Load the configuration file along with the files it includes, which are
+/// resolved relative to it and could be glob patterns. The entries of the
+/// included files come first, tables are merged, arrays are concatenated and
+/// the other values of the including file take precedence.
+fn load(
+    path: &Path,
+    stack: &mut Vec<PathBuf>,
+    files: &mut Vec<PathBuf>,
+) -> Result<Value, Box<dyn Error + Send + Sync>> {
+    let canonical = path
+        .canonicalize()
+        .map_err(|err| format!("could not load configuration '{:?}', {}", path, err))?;
+
+    files.push(path.to_owned());
+
+    if stack.contains(&canonical) {
+        return Err(format!("could not include '{:?}', it includes itself", path).into());
+    }
+
+    let mut value: Value = Config::builder()
+        .add_source(File::from(path).format(format(path)?).required(true))
+        .build()?
+        .try_deserialize()?;
+
+    let includes: Vec<String> = match value.as_object_mut().and_then(|map| map.remove("include")) {
+        Some(includes) => serde_json::from_value(includes)
+            .map_err(|err| format!("could not parse include of '{:?}', {}", path, err))?,
+        None => return Ok(value),
+    };
+
+    stack.push(canonical);
+    let directory = path.parent().unwrap_or_else(|| Path::new(""));
+    let mut merged = Value::Object(Default::default());
+    for include in includes {
+        let pattern = directory.join(&include);
+        let mut paths = glob::glob(&pattern.to_string_lossy())
+            .map_err(|err| format!("could not parse include '{}', {}", include, err))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|err| format!("could not read include '{}', {}", include, err))?;
+
+        // Patterns may match nothing, plain paths should exist
+        if paths.is_empty() && glob::Pattern::escape(&include) == include {
+            return Err(
+                format!("could not include '{:?}', the file does not exist", pattern).into(),
+            );
+        }
+
+        paths.sort();
+        for path in paths {
+            merge(&mut merged, load(&path, stack, files)?);
+        }
+    }
+    stack.pop();
+
+    merge(&mut merged, value);
+    Ok(merged)
+}
+
+/// Merge the value into the base one
+fn merge(base: &mut Value, value: Value) {
+    match (base, value) {
+        (Value::Object(base), Value::Object(map)) => {
+            for (key, value) in map {
+                match base.get_mut(&key) {
+                    Some(entry) => merge(entry, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (Value::Array(base), Value::Array(items)) => base.extend(items),
+        (base, value) => *base = value,
+    }
+}
+
+/// Add a repository for each git repository matching the discover patterns
+/// which is not declared yet, named after its directory
+fn discover(value: &mut Value) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let patterns: Vec<String> = match value.as_object_mut().and_then(|map| map.remove("discover")) {
+        Some(Value::String(pattern)) => vec![pattern],
+        Some(patterns) => serde_json::from_value(patterns)
+            .map_err(|err| format!("could not parse discover, {}", err))?,
+        None => return Ok(()),
+    };
+
+    let repositories = value
+        .as_object_mut()
+        .map(|map| map.entry("repositories").or_insert_with(|| json!([])))
+        .and_then(Value::as_array_mut)
+        .ok_or("could not discover repositories, repositories should be an array")?;
+
+    let mut known: Vec<PathBuf> = repositories
+        .iter()
+        .filter_map(|repository| repository.get("path")?.as_str())
+        .filter_map(|path| Path::new(path).canonicalize().ok())
+        .collect();
+    let mut names: Vec<String> = repositories
+        .iter()
+        .filter_map(|repository| Some(repository.get("name")?.as_str()?.to_owned()))
+        .collect();
+
+    for pattern in patterns {
+        let expanded = match (pattern.strip_prefix("~/"), env::var_os("HOME")) {
+            (Some(pattern), Some(home)) => Path::new(&home).join(pattern),
+            _ => PathBuf::from(&pattern),
+        };
+
+        let paths = glob::glob(&expanded.to_string_lossy())
+            .map_err(|err| format!("could not parse discover '{}', {}", pattern, err))?;
+
+        for path in paths {
+            // Unreadable directories should not prevent discovering the others
+            let path = match path {
+                Ok(path) => path,
+                Err(err) => {
+                    warn!("could not read directory while discovering repositories"; "pattern" => &pattern, "error" => err.to_string());
+                    continue;
+                }
+            };
+
+            let canonical = match path.canonicalize() {
+                Ok(canonical) if path.join(".git").exists() => canonical,
+                _ => continue,
+            };
+
+            if known.contains(&canonical) {
+                continue;
+            }
+
+            let name = match path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+            {
+                Some(name) if !names.contains(&name) => name,
+                _ => slashed(&path),
+            };
+
+            debug!("Discover repository"; "name" => &name, "path" => path.display().to_string());
+            repositories.push(json!({ "name": name, "path": path }));
+            names.push(name);
+            known.push(canonical);
+        }
+    }
+
+    Ok(())
+}
+
+/// Remove the defaults from the entries of the configuration and give them to
+/// every repository which does not have its own value
+fn inherit(value: &mut Value) {
+    let defaults = match value.as_object_mut().and_then(|map| map.remove("defaults")) {
+        Some(defaults) => defaults,
+        None => return,
+    };
+
+    if let Some(repositories) = value.get_mut("repositories").and_then(Value::as_array_mut) {
+        for repository in repositories {
+            fill(repository, &defaults);
+        }
+    }
+}
+
+/// Insert the missing entries of the given table, tables are filled entry by
+/// entry
+fn fill(value: &mut Value, defaults: &Value) {
+    if let (Value::Object(map), Value::Object(defaults)) = (value, defaults) {
+        for (key, default) in defaults {
+            match map.get_mut(key) {
+                Some(entry) => fill(entry, default),
+                None => {
+                    map.insert(key.to_owned(), default.to_owned());
+                }
+            }
+        }
+    }
+}
+
+/// Returns the path of the entry overridden by the environment variable, if
+/// any, the segments of its name are separated by a double underscore (e.g.
+/// `repositories[0].range` for `CHANGELOG__REPOSITORIES__0__RANGE`)
+pub fn overridden(name: &str) -> Option<String> {
+    let mut path = String::new();
+    for segment in name.strip_prefix(ENVIRONMENT_PREFIX)?.split("__") {
+        if segment.is_empty() {
+            return None;
+        }
+
+        match segment.parse::<usize>() {
+            Ok(index) if !path.is_empty() => path.push_str(&format!("[{}]", index)),
+            _ => {
+                if !path.is_empty() {
+                    path.push('.');
+                }
+
+                path.push_str(&segment.to_lowercase());
+            }
+        }
+    }
+
+    Some(path)
+}
+
+/// Returns the format of the configuration file given its extension, toml is
+/// assumed if it has none
+pub fn format(path: &Path) -> Result<FileFormat, Box<dyn Error + Send + Sync>> {
+    match path.extension().and_then(|extension| extension.to_str()) {
+        None | Some("toml") => Ok(FileFormat::Toml),
+        Some("yaml") | Some("yml") => Ok(FileFormat::Yaml),
+        Some("json") => Ok(FileFormat::Json),
+        Some(extension) => Err(format!(
+            "could not detect the format of '{:?}', the extension '{}' is not one of toml, yaml, yml or json",
+            path, extension
+        )
+        .into()),
+    }
+}
+
+/// Returns the JSON schema of the configuration file, which editors use to
+/// complete and check it
+pub fn schema() -> Value {
+    let mut schema = Value::from(schemars::schema_for!(Configuration));
+
+    // Repositories inherit the defaults, so that none of them is required
+    let mut defaults = schema
+        .pointer("/$defs/Repository")
+        .cloned()
+        .unwrap_or_else(|| json!({ "type": "object" }));
+    if let Some(map) = defaults.as_object_mut() {
+        map.remove("required");
+        map.insert(
+            String::from("description"),
+            json!("Entries inherited by every repository which does not give its own value"),
+        );
+    }
+
+    // Repositories could all be discovered
+    if let Some(required) = schema.get_mut("required").and_then(Value::as_array_mut) {
+        required.retain(|name| name != "repositories");
+    }
+
+    if let Some(properties) = schema.get_mut("properties").and_then(Value::as_object_mut) {
+        properties.insert(
+            String::from("include"),
+            json!({
+                "description": "Paths to the files included by this one, relative to it, which could be glob patterns",
+                "type": "array",
+                "items": { "type": "string" },
+            }),
+        );
+        properties.insert(String::from("defaults"), defaults);
+        properties.insert(
+            String::from("discover"),
+            json!({
+                "description": "Glob patterns matching the git repositories to add to the declared ones, which inherit the defaults",
+                "anyOf": [
+                    { "type": "string" },
+                    { "type": "array", "items": { "type": "string" } },
+                ],
+            }),
+        );
+        properties.insert(
+            String::from("profiles"),
+            json!({
+                "description": "Entries replacing the top-level ones of the file by name of profile, selected using --profile",
+                "type": "object",
+                "additionalProperties": { "type": "object" },
+            }),
+        );
+    }
+
+    schema
+}
+
+/// Returns the path of the entries unknown to the schema of the configuration
+/// (e.g. `repositories[0].tag_prefx`), along with the closest known key
+pub fn unknown(value: &Value) -> Vec<(String, Option<String>)> {
+    let root = schema();
+    let mut found = vec![];
+
+    walk(&root, &root, value, "", &mut found);
+    found
+}
+
+fn walk(
+    root: &Value,
+    schema: &Value,
+    value: &Value,
+    path: &str,
+    found: &mut Vec<(String, Option<String>)>,
+) {
+    let schema = match schema.get("$ref").and_then(Value::as_str) {
+        Some(reference) => match root.pointer(reference.trim_start_matches('#')) {
+            Some(schema) => schema,
+            None => return,
+        },
+        None => schema,
+    };
+
+    for key in ["anyOf", "oneOf", "allOf"] {
+        for schema in schema
+            .get(key)
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+        {
+            walk(root, schema, value, path, found);
+        }
+    }
+
+    match value {
+        Value::Object(map) => {
+            let properties = schema.get("properties").and_then(Value::as_object);
+            let additional = schema
+                .get("additionalProperties")
+                .filter(|schema| schema.is_object());
+
+            for (key, value) in map {
+                let child = if path.is_empty() {
+                    key.to_owned()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+
+                match (
+                    properties.and_then(|properties| properties.get(key)),
+                    additional,
+                ) {
+                    (Some(schema), _) | (None, Some(schema)) => {
+                        walk(root, schema, value, &child, found)
+                    }
+                    (None, None) => {
+                        if let Some(properties) = properties {
+                            let suggestion = properties
+                                .keys()
+                                .map(|name| (strsim::levenshtein(key, name), name))
+                                .filter(|(distance, _)| *distance <= 3 && *distance < key.len())
+                                .min()
+                                .map(|(_, name)| name.to_owned());
+
+                            found.push((child, suggestion));
+                        }
+                    }
+                }
+            }
+        }
+        Value::Array(items) => {
+            if let Some(schema) = schema.get("items").filter(|schema| schema.is_object()) {
+                for (idx, item) in items.iter().enumerate() {
+                    walk(root, schema, item, &format!("{}[{}]", path, idx), found);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, process};
+
+    use super::*;
+
+    /// Returns a directory holding the given files, unique to the test
+    fn directory(test: &str, files: &[(&str, &str)]) -> PathBuf {
+        let directory = env::temp_dir().join(format!("git-changelog-{}-{}", test, process::id()));
+        for (name, content) in files {
+            let path = directory.join(name);
+            fs::create_dir_all(path.parent().expect("file to have a parent"))
+                .expect("directory to be created");
+            fs::write(path, content).expect("file to be written");
+        }
+
+        directory
+    }
+
+    #[test]
+    fn overridden_names_the_nested_entries() {
+        assert_eq!(
+            overridden("CHANGELOG__REPOSITORIES__0__RANGE").as_deref(),
+            Some("repositories[0].range")
+        );
+        assert_eq!(
+            overridden("CHANGELOG__EMAIL__TOKEN").as_deref(),
+            Some("email.token")
+        );
+        assert_eq!(overridden("CHANGELOG__REPOSITORIES____RANGE"), None);
+        assert_eq!(overridden("CHANGELOG_OUTPUT"), None);
+    }
+
+    #[test]
+    fn load_overrides_a_nested_entry_using_the_environment() {
+        let directory = directory(
+            "environment",
+            &[(
+                "changelog.toml",
+                r#"
+[kinds]
+feat = "Features"
+
+[[repositories]]
+name = "api"
+path = "."
+range = "v1.0.0..HEAD"
+"#,
+            )],
+        );
+
+        // Only this test sets the variable, the others do not check the range
+        env::set_var("CHANGELOG__REPOSITORIES__0__RANGE", "v2.0.0..HEAD");
+        let conf = Configuration::load(&directory.join("changelog.toml"), None);
+        env::remove_var("CHANGELOG__REPOSITORIES__0__RANGE");
+
+        let conf = conf.unwrap();
+        assert_eq!(conf.repositories[0].range.as_deref(), Some("v2.0.0..HEAD"));
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn load_merges_the_included_files() {
+        let directory = directory(
+            "include",
+            &[
+                (
+                    "changelog.toml",
+                    r#"
+include = ["common.toml", "repos/*.toml"]
+overview = true
+
+[kinds]
+feat = "New features"
+"#,
+                ),
+                (
+                    "common.toml",
+                    r#"
+overview = false
+
+[kinds]
+feat = "Features"
+fix = "Fix changes"
+"#,
+                ),
+                (
+                    "repos/api.toml",
+                    "[[repositories]]\nname = \"api\"\npath = \".\"\n",
+                ),
+                (
+                    "repos/web.toml",
+                    "[[repositories]]\nname = \"web\"\npath = \".\"\n",
+                ),
+            ],
+        );
+
+        let conf = Configuration::load(&directory.join("changelog.toml"), None).unwrap();
+
+        // Tables are merged, arrays concatenated and the including file wins
+        assert!(conf.overview);
+        assert_eq!(conf.kinds["feat"], "New features");
+        assert_eq!(conf.kinds["fix"], "Fix changes");
+        let names: Vec<_> = conf.repositories.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, ["api", "web"]);
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn load_rejects_an_include_cycle() {
+        let directory = directory(
+            "cycle",
+            &[
+                ("changelog.toml", "include = [\"common.toml\"]\n"),
+                ("common.toml", "include = [\"changelog.toml\"]\n"),
+            ],
+        );
+
+        let err = Configuration::load(&directory.join("changelog.toml"), None).unwrap_err();
+        assert!(err.to_string().contains("it includes itself"), "{}", err);
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn load_gives_the_defaults_to_the_repositories_without_their_own_value() {
+        let directory = directory(
+            "defaults",
+            &[(
+                "changelog.toml",
+                r#"
+[kinds]
+feat = "Features"
+
+[defaults]
+branch = "main"
+link = "https://example.com/{repo}/{hash}"
+
+[defaults.dependencies]
+collapse = true
+title = "Updates"
+
+[[repositories]]
+name = "api"
+path = "."
+branch = "develop"
+
+[repositories.dependencies]
+title = "Dependencies"
+
+[[repositories]]
+name = "web"
+path = "."
+"#,
+            )],
+        );
+
+        let conf = Configuration::load(&directory.join("changelog.toml"), None).unwrap();
+        let (api, web) = (&conf.repositories[0], &conf.repositories[1]);
+
+        // The values of a repository beat the defaults, tables entry by entry
+        assert_eq!(api.branch.as_deref(), Some("develop"));
+        assert_eq!(
+            api.link.as_deref(),
+            Some("https://example.com/{repo}/{hash}")
+        );
+        assert!(api.dependencies.collapse);
+        assert_eq!(api.dependencies.title, "Dependencies");
+
+        assert_eq!(web.branch.as_deref(), Some("main"));
+        assert_eq!(web.dependencies.title, "Updates");
+
+        fs::remove_dir_all(&directory).unwrap();
     }
 }
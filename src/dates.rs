@@ -0,0 +1,79 @@
+//! # Dates module
+//!
+//! The dates module selects the date of the commits (author or committer),
+//! moves it into the configured timezone and formats it for the outputs.
+use std::error::Error;
+
+use chrono::{
+    format::{Item, StrftimeItems},
+    DateTime, FixedOffset, Local, NaiveDateTime, Offset, TimeZone, Utc,
+};
+use git2 as git;
+
+use crate::{
+    conf::{DateSource, Dates},
+    parser::Commit,
+};
+
+/// Returns the time of the git signature along with its offset
+pub fn of(time: git::Time) -> DateTime<FixedOffset> {
+    let offset = FixedOffset::east_opt(time.offset_minutes() * 60).unwrap_or_else(|| Utc.fix());
+
+    DateTime::<Utc>::from_utc(NaiveDateTime::from_timestamp(time.seconds(), 0), Utc)
+        .with_timezone(&offset)
+}
+
+/// Returns the offset of the timezone at the given time, the timezone is
+/// either utc, local, commit (the offset recorded in the commit) or a fixed
+/// offset (e.g. +02:00)
+pub fn offset(
+    timezone: &str,
+    time: &DateTime<FixedOffset>,
+) -> Result<FixedOffset, Box<dyn Error + Send + Sync>> {
+    match timezone.to_lowercase().as_str() {
+        "utc" => Ok(Utc.fix()),
+        "local" => Ok(Local.offset_from_utc_datetime(&time.naive_utc()).fix()),
+        "commit" => Ok(*time.offset()),
+        offset => {
+            let date = format!("1970-01-01 00:00 {}", offset);
+            let parsed = DateTime::parse_from_str(&date, "%F %R %:z")
+                .or_else(|_| DateTime::parse_from_str(&date, "%F %R %z"))
+                .map_err(|err| format!("could not parse timezone '{}', {}", timezone, err))?;
+
+            Ok(*parsed.offset())
+        }
+    }
+}
+
+/// Returns an error if the strftime format holds an invalid specifier
+pub fn check(format: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
+    if StrftimeItems::new(format).any(|item| item == Item::Error) {
+        return Err(format!("could not parse date format '{}'", format).into());
+    }
+
+    Ok(())
+}
+
+/// Returns the configured date of the commit, in the configured timezone
+pub fn time(commit: &Commit, dates: &Dates) -> DateTime<FixedOffset> {
+    let time = match dates.source {
+        DateSource::Author => commit.authored,
+        DateSource::Committer => commit.committed,
+    };
+
+    match offset(&dates.timezone, &time) {
+        Ok(offset) => time.with_timezone(&offset),
+        Err(_) => time.with_timezone(&Utc.fix()),
+    }
+}
+
+/// Returns the date of the commit using the format configured for the output,
+/// if any
+pub fn format(commit: &Commit, dates: &Dates, output: &str) -> Option<String> {
+    let format = dates.formats.get(output)?;
+    if check(format).is_err() {
+        return None;
+    }
+
+    Some(time(commit, dates).format(format).to_string())
+}
@@ -0,0 +1,56 @@
+//! # Exit module
+//!
+//! The exit module gives a distinct exit code to each outcome of a run, so
+//! that CI pipelines could branch on it without scraping the logs.
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+};
+
+/// Exit codes of the binary
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Code {
+    Success = 0,
+    /// Failure which is not one of the following ones
+    Failure = 1,
+    /// Success, but commits do not follow the convention (with --fail-on-skipped)
+    Warnings = 2,
+    /// The configuration could not be loaded or is not valid
+    Configuration = 3,
+    /// The history of a repository could not be read
+    Git = 4,
+    /// The remote could not be reached
+    Network = 5,
+    /// The changelog on disk is not up to date (with --check-stale)
+    Stale = 6,
+    /// Commits do not follow the convention (with the strict configuration)
+    Violations = 7,
+}
+
+/// Error along with the exit code of the run
+#[derive(Debug)]
+pub struct Failure {
+    pub code: Code,
+    pub error: Box<dyn Error + Send + Sync>,
+}
+
+impl Display for Failure {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.error)
+    }
+}
+
+impl Error for Failure {}
+
+/// Give the exit code to the error
+pub fn with(code: Code, error: Box<dyn Error + Send + Sync>) -> Box<dyn Error + Send + Sync> {
+    Box::new(Failure { code, error })
+}
+
+/// Returns the exit code given to the error, if any
+pub fn code(error: &(dyn Error + Send + Sync + 'static)) -> Code {
+    error
+        .downcast_ref::<Failure>()
+        .map(|failure| failure.code)
+        .unwrap_or(Code::Failure)
+}
@@ -0,0 +1,117 @@
+//! # Hooks module
+//!
+//! The hooks module exposes the extension points of the parsing pipeline, so
+//! that tools embedding the library could apply their own rules to the entries
+//! (e.g. dropping commits touching internal tickets) without forking it.
+//!
+//! ```no_run
+//! use std::{convert::TryFrom, path::PathBuf, rc::Rc};
+//!
+//! use git_changelog::{
+//!     conf::Configuration,
+//!     hooks::{Hooks, Verdict},
+//!     parser::{Changelog, Commit},
+//! };
+//!
+//! struct Internal;
+//!
+//! impl Hooks for Internal {
+//!     fn on_commit_parsed(&mut self, _: &str, _: &mut String, commit: &mut Commit) -> Verdict {
+//!         if commit.message.contains("[internal]") {
+//!             return Verdict::Drop;
+//!         }
+//!
+//!         Verdict::Keep
+//!     }
+//! }
+//!
+//! let conf = Configuration::try_from(PathBuf::from("changelog.toml"))?;
+//! let changelog = Changelog::generate(Rc::new(conf), &mut Internal)?;
+//! # Ok::<(), Box<dyn std::error::Error + Send + Sync>>(())
+//! ```
+use crate::parser::{Changelog, Commit, Skipped, Tag};
+
+/// Decision of a hook about a parsed commit
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Verdict {
+    Keep,
+    Drop,
+}
+
+/// Callbacks invoked while the changelog is generated, every one of them does
+/// nothing by default
+pub trait Hooks {
+    /// Called once a commit of the repository has been parsed and classified
+    /// into the given section, before the configured rewrites are applied.
+    /// The commit is left out of the changelog if the hook drops it.
+    fn on_commit_parsed(
+        &mut self,
+        _repository: &str,
+        _section: &mut String,
+        _commit: &mut Commit,
+    ) -> Verdict {
+        Verdict::Keep
+    }
+
+    /// Called once a commit of the repository has been skipped
+    fn on_commit_skipped(&mut self, _repository: &str, _skipped: &Skipped) {}
+
+    /// Called once every commit of the tag has been read, including the
+    /// unreleased one
+    fn on_tag_closed(&mut self, _repository: &str, _tag: &mut Tag) {}
+
+    /// Called once every commit of the repository has been read, before its
+    /// entries are enriched (links, authors, deduplication, ...)
+    fn on_repository_walked(&mut self, _repository: &str) {}
+
+    /// Called once the changelog is complete, before it is rendered
+    fn before_render(&mut self, _changelog: &mut Changelog) {}
+}
+
+/// No hooks
+impl Hooks for () {}
+
+/// Hooks calling each of the given ones in turn, a commit dropped by one of
+/// them is not given to the next ones
+pub struct Chain<'a>(pub Vec<&'a mut dyn Hooks>);
+
+impl Hooks for Chain<'_> {
+    fn on_commit_parsed(
+        &mut self,
+        repository: &str,
+        section: &mut String,
+        commit: &mut Commit,
+    ) -> Verdict {
+        for hooks in &mut self.0 {
+            if hooks.on_commit_parsed(repository, section, commit) == Verdict::Drop {
+                return Verdict::Drop;
+            }
+        }
+
+        Verdict::Keep
+    }
+
+    fn on_commit_skipped(&mut self, repository: &str, skipped: &Skipped) {
+        for hooks in &mut self.0 {
+            hooks.on_commit_skipped(repository, skipped);
+        }
+    }
+
+    fn on_tag_closed(&mut self, repository: &str, tag: &mut Tag) {
+        for hooks in &mut self.0 {
+            hooks.on_tag_closed(repository, tag);
+        }
+    }
+
+    fn on_repository_walked(&mut self, repository: &str) {
+        for hooks in &mut self.0 {
+            hooks.on_repository_walked(repository);
+        }
+    }
+
+    fn before_render(&mut self, changelog: &mut Changelog) {
+        for hooks in &mut self.0 {
+            hooks.before_render(changelog);
+        }
+    }
+}
@@ -0,0 +1,89 @@
+//! # I18n module
+//!
+//! The i18n module translates the fixed strings of the generated changelog
+//! (headings, the unreleased label, the default titles of the sections and
+//! the dates) into the language selected in the configuration.
+use chrono::{Datelike, NaiveDate};
+
+use crate::{
+    conf::Language,
+    parser::{SHALLOW, UNRELEASED},
+};
+
+/// Translations of the fixed strings into french, the kinds are the default ones
+const FRENCH: &[(&str, &str)] = &[
+    ("Changelog", "Journal des modifications"),
+    (UNRELEASED, "Aperçu technique"),
+    ("also in", "aussi dans"),
+    ("first contribution", "première contribution"),
+    ("files", "fichiers"),
+    ("verified", "vérifié"),
+    ("unverified", "non vérifié"),
+    ("unsigned", "non signé"),
+    ("signed off by", "approuvé par"),
+    ("Repository", "Dépôt"),
+    ("Latest release", "Dernière version"),
+    ("Generated with", "Généré avec"),
+    ("by", "par"),
+    ("Other changes", "Autres modifications"),
+    ("Automated updates", "Mises à jour automatisées"),
+    ("Dependency updates", "Mises à jour des dépendances"),
+    ("Build improvements", "Améliorations de la compilation"),
+    (
+        "Continuous integration improvements",
+        "Améliorations de l'intégration continue",
+    ),
+    (
+        "Documentation enhancements",
+        "Améliorations de la documentation",
+    ),
+    ("Features", "Fonctionnalités"),
+    ("Fix changes", "Corrections"),
+    ("Performance improvements", "Améliorations des performances"),
+    ("Refactor enhancements", "Refactorisations"),
+    ("Style changes", "Modifications de style"),
+    ("Unit test changes", "Modifications des tests unitaires"),
+    (
+        "Infrastructure changes",
+        "Modifications de l'infrastructure",
+    ),
+    ("Chore tasks", "Tâches de maintenance"),
+    (
+        SHALLOW,
+        "L'historique est incomplet, le dépôt est un clone superficiel",
+    ),
+];
+
+/// Returns the text translated into the language, the text is returned as is
+/// if there is no translation for it
+pub fn translate(language: Language, text: &str) -> String {
+    let catalog = match language {
+        Language::En => return text.to_string(),
+        Language::Fr => FRENCH,
+    };
+
+    catalog
+        .iter()
+        .find(|(from, _)| *from == text)
+        .map(|(_, to)| to.to_string())
+        .unwrap_or_else(|| text.to_string())
+}
+
+/// Returns the date (formatted as YYYY-MM-DD) using the convention of the
+/// language, the date is returned as is if it could not be parsed
+pub fn date(language: Language, date: &str) -> String {
+    let parsed = match NaiveDate::parse_from_str(date, "%F") {
+        Ok(parsed) => parsed,
+        Err(_) => return date.to_string(),
+    };
+
+    match language {
+        Language::En => date.to_string(),
+        Language::Fr => format!(
+            "{:02}/{:02}/{}",
+            parsed.day(),
+            parsed.month(),
+            parsed.year()
+        ),
+    }
+}
@@ -0,0 +1,72 @@
+//! # Inject module
+//!
+//! The inject module splices the generated changelog into an arbitrary file
+//! (e.g. README.md or a page of the documentation) between markers, keeping
+//! the hand-written content around them.
+use std::{error::Error, fs, path::Path};
+
+/// Marker starting the generated content
+pub const START: &str = "<!-- changelog:start -->";
+
+/// Marker ending the generated content
+pub const END: &str = "<!-- changelog:end -->";
+
+/// Returns the existing content with the text between the markers replaced
+/// by the given one
+pub fn splice(existing: &str, content: &str) -> Result<String, Box<dyn Error + Send + Sync>> {
+    let start = existing
+        .find(START)
+        .ok_or_else(|| format!("could not find the start marker '{}'", START))?;
+
+    let end = existing[start..]
+        .find(END)
+        .map(|idx| start + idx)
+        .ok_or_else(|| {
+            format!(
+                "could not find the end marker '{}' after the start one",
+                END
+            )
+        })?;
+
+    Ok(format!(
+        "{}{}\n{}\n{}",
+        &existing[..start],
+        START,
+        content.trim_matches('\n'),
+        &existing[end..]
+    ))
+}
+
+/// Read the file at the given path and returns its content with the
+/// generated one injected between the markers
+pub fn inject(path: &Path, content: &str) -> Result<String, Box<dyn Error + Send + Sync>> {
+    let existing = fs::read_to_string(path)
+        .map_err(|err| format!("could not read file '{:?}', {}", path, err))?;
+
+    splice(&existing, content)
+        .map_err(|err| format!("could not inject the changelog into '{:?}', {}", path, err).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splice_replaces_the_content_between_the_markers() {
+        let existing = format!("# Readme\n\n{}\nold\n{}\n\nFooter\n", START, END);
+
+        let spliced = splice(&existing, "\n## Changelog\n\n").expect("markers to be found");
+
+        assert_eq!(
+            spliced,
+            format!("# Readme\n\n{}\n## Changelog\n{}\n\nFooter\n", START, END)
+        );
+    }
+
+    #[test]
+    fn splice_fails_without_both_markers_in_order() {
+        assert!(splice("# Readme\n", "content").is_err());
+        assert!(splice(&format!("{}\n", START), "content").is_err());
+        assert!(splice(&format!("{}\n{}\n", END, START), "content").is_err());
+    }
+}
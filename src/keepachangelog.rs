@@ -0,0 +1,461 @@
+//! # Keep a Changelog module
+//!
+//! The keepachangelog module merges the generated releases into a changelog
+//! following the https://keepachangelog.com format, the versions and entries
+//! already recorded (even manually) are parsed in order to not write them
+//! twice. Entries are matched by the hash of their commit, within their
+//! version, and move from the unreleased section once their commit is
+//! released.
+use std::{
+    collections::HashSet,
+    fmt::{self, Display, Formatter},
+};
+
+use crate::parser::{Changelog, Commit, Tag, UNRELEASED};
+
+/// Name of the section holding the unreleased changes
+const UNRELEASED_SECTION: &str = "Unreleased";
+
+/// Release section of the changelog, starting at a `## [version]` heading
+#[derive(Clone, Debug)]
+struct Section {
+    version: String,
+    lines: Vec<String>,
+}
+
+/// Changelog in the Keep a Changelog format
+#[derive(Clone, Debug)]
+struct Document {
+    preamble: Vec<String>,
+    sections: Vec<Section>,
+}
+
+/// Returns true if the content follows the Keep a Changelog format
+pub fn detect(content: &str) -> bool {
+    content.lines().any(|line| version(line).is_some())
+}
+
+/// Returns the version of a `## [version] - date` heading
+fn version(line: &str) -> Option<&str> {
+    let rest = line.strip_prefix("## [")?;
+    let end = rest.find(']')?;
+
+    Some(&rest[..end])
+}
+
+/// Returns the version without its prefix (e.g. v1.0.0 and 1.0.0 are the same)
+fn normalize_version(version: &str) -> &str {
+    match version.find(|c: char| c.is_ascii_digit()) {
+        Some(idx) => &version[idx..],
+        None => version,
+    }
+}
+
+/// Returns the description of a message, without the kind and the scope
+fn description(message: &str) -> String {
+    let message = match message.split_once(": ") {
+        Some((prefix, rest)) if !prefix.contains(' ') => rest,
+        _ => message,
+    };
+
+    message.trim().trim_end_matches('.').to_lowercase()
+}
+
+/// Returns the description of an entry, without the reference to the commit
+fn entry(line: &str) -> Option<String> {
+    let text = line
+        .trim_start()
+        .strip_prefix("- ")
+        .or_else(|| line.trim_start().strip_prefix("* "))?;
+
+    let text = match text.rfind(" (") {
+        Some(idx) if text.ends_with(')') => &text[..idx],
+        _ => text,
+    };
+
+    Some(description(text))
+}
+
+impl From<&str> for Document {
+    fn from(content: &str) -> Self {
+        let mut document = Document {
+            preamble: vec![],
+            sections: vec![],
+        };
+
+        for line in content.lines() {
+            if let Some(version) = version(line) {
+                document.sections.push(Section {
+                    version: version.to_string(),
+                    lines: vec![line.to_string()],
+                });
+            } else if let Some(section) = document.sections.last_mut() {
+                section.lines.push(line.to_string());
+            } else {
+                document.preamble.push(line.to_string());
+            }
+        }
+
+        document
+    }
+}
+
+impl Document {
+    /// Returns the section of the given version
+    fn section(&mut self, version: &str) -> Option<&mut Section> {
+        self.sections.iter_mut().find(|section| {
+            normalize_version(&section.version).eq_ignore_ascii_case(normalize_version(version))
+        })
+    }
+
+    /// Remove the entry of the commit from the unreleased section, returns
+    /// it as written (it may have been edited) once the commit is released
+    fn take(&mut self, commit: &Commit) -> Option<String> {
+        let section = self
+            .sections
+            .iter_mut()
+            .find(|section| section.version == UNRELEASED_SECTION)?;
+
+        let idx = section
+            .lines
+            .iter()
+            .position(|line| records(line, commit))?;
+        let line = section.lines.remove(idx);
+        section.prune();
+
+        Some(line)
+    }
+}
+
+/// Returns true if the line is the entry of the commit, referenced by its hash
+fn records(line: &str, commit: &Commit) -> bool {
+    entry(line).is_some() && line.contains(&commit.short_hash)
+}
+
+impl Display for Document {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let sections = self.sections.iter().flat_map(|section| &section.lines);
+        for line in self.preamble.iter().chain(sections) {
+            writeln!(f, "{}", line)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Section {
+    /// Returns true if the commit is already recorded in the section
+    fn contains(&self, commit: &Commit) -> bool {
+        self.lines.iter().any(|line| records(line, commit))
+    }
+
+    /// Remove the headings left without any entry
+    fn prune(&mut self) {
+        let mut idx = 1;
+        while idx < self.lines.len() {
+            if !self.lines[idx].starts_with("### ") {
+                idx += 1;
+                continue;
+            }
+
+            let end = self.lines[idx + 1..]
+                .iter()
+                .position(|line| line.starts_with('#'))
+                .map_or(self.lines.len(), |end| idx + 1 + end);
+
+            if self.lines[idx + 1..end]
+                .iter()
+                .any(|line| entry(line).is_some())
+            {
+                idx = end;
+                continue;
+            }
+
+            self.lines.drain(idx..end);
+            while idx < self.lines.len() && self.lines[idx].trim().is_empty() {
+                self.lines.remove(idx);
+            }
+        }
+    }
+
+    /// Append the entries below the heading of the given kind, the heading
+    /// is created at the end of the section if it does not exist
+    fn push(&mut self, kind: &str, entries: Vec<String>) {
+        let heading = format!("### {}", kind);
+        let start = match self.lines.iter().position(|line| line.trim() == heading) {
+            Some(start) => start,
+            None => {
+                while self.lines.last().is_some_and(|line| line.trim().is_empty()) {
+                    self.lines.pop();
+                }
+
+                self.lines.push(String::new());
+                self.lines.push(heading);
+                self.lines.push(String::new());
+                self.lines.extend(entries);
+                self.lines.push(String::new());
+                return;
+            }
+        };
+
+        // Insert after the last entry of the heading
+        let mut idx = start + 1;
+        for (i, line) in self.lines.iter().enumerate().skip(start + 1) {
+            if line.starts_with('#') {
+                break;
+            }
+
+            if entry(line).is_some() {
+                idx = i + 1;
+            }
+        }
+
+        if idx == start + 1 {
+            self.lines.insert(idx, String::new());
+            idx += 1;
+        }
+
+        for (i, entry) in entries.into_iter().enumerate() {
+            self.lines.insert(idx + i, entry);
+        }
+    }
+}
+
+/// Returns the entry of a commit
+fn format(commit: &Commit) -> String {
+    let description = match commit.message.split_once(": ") {
+        Some((prefix, rest)) if !prefix.contains(' ') => rest,
+        _ => commit.message.as_str(),
+    };
+
+    match commit.link {
+        Some(ref link) => format!("- {} ([{}]({}))", description, commit.short_hash, link),
+        None => format!("- {} ({})", description, commit.short_hash),
+    }
+}
+
+/// Merge the commits of the tag which are not yet recorded into the
+/// section, returns true if any entry has been added. The entries of the
+/// released commits are moved from the unreleased section.
+fn merge(document: &mut Document, section: &mut Section, tag: &Tag) -> bool {
+    let mut kinds: Vec<_> = tag.commits.iter().collect();
+    kinds.sort_by(|a, b| a.0.cmp(b.0));
+
+    let mut merged = false;
+    for (kind, commits) in kinds {
+        let mut entries = vec![];
+        for commit in commits {
+            let taken = match section.version == UNRELEASED_SECTION {
+                true => None,
+                false => document.take(commit),
+            };
+
+            if !section.contains(commit) {
+                entries.push(taken.unwrap_or_else(|| format(commit)));
+            }
+        }
+
+        if !entries.is_empty() {
+            section.push(kind, entries);
+            merged = true;
+        }
+    }
+
+    merged
+}
+
+/// Merge the releases of the changelog into the existing content, entries
+/// which are already recorded are not written twice
+pub fn update(existing: &str, changelog: Changelog) -> String {
+    let mut document = Document::from(existing);
+    let mut fresh = vec![];
+    let mut seen = HashSet::new();
+
+    let tags = changelog
+        .repositories
+        .iter()
+        .flat_map(|repository| &repository.tags);
+
+    for tag in tags {
+        let name = match tag.name.as_str() {
+            UNRELEASED => UNRELEASED_SECTION,
+            name => name,
+        };
+
+        if let Some(mut section) = document.section(name).cloned() {
+            if merge(&mut document, &mut section, tag) {
+                *document.section(name).expect("section to exist") = section;
+            }
+
+            continue;
+        }
+
+        if !seen.insert(name.to_string()) {
+            continue;
+        }
+
+        let heading = match (name, tag.date()) {
+            (UNRELEASED_SECTION, _) | (_, None) => format!("## [{}]", name),
+            (_, Some(date)) => format!("## [{}] - {}", name, date),
+        };
+
+        let mut section = Section {
+            version: name.to_string(),
+            lines: vec![heading],
+        };
+
+        if merge(&mut document, &mut section, tag) {
+            fresh.push(section);
+        }
+    }
+
+    // New releases are written above the existing ones, but below the
+    // unreleased section
+    let (unreleased, releases): (Vec<_>, Vec<_>) = fresh
+        .into_iter()
+        .partition(|section| section.version == UNRELEASED_SECTION);
+
+    let idx = document
+        .sections
+        .iter()
+        .position(|section| section.version != UNRELEASED_SECTION)
+        .unwrap_or(document.sections.len());
+
+    document.sections.splice(idx..idx, releases);
+    document.sections.splice(0..0, unreleased);
+
+    for section in &mut document.sections {
+        while section
+            .lines
+            .last()
+            .is_some_and(|line| line.trim().is_empty())
+        {
+            section.lines.pop();
+        }
+
+        section.lines.push(String::new());
+    }
+
+    if let Some(section) = document.sections.last_mut() {
+        section.lines.pop();
+    }
+
+    document.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::parser::{commit, Repository};
+
+    const EXISTING: &str = "# Changelog
+
+All notable changes to this project will be documented in this file.
+
+## [Unreleased]
+
+### Fixed
+
+- Handle the empty input with care (abc1234)
+
+## [1.0.0] - 2024-01-01
+
+### Added
+
+- first release (0000001)
+";
+
+    /// Returns a changelog whose tags hold the given commits, from the most
+    /// recent tag
+    fn changelog(tags: Vec<(&str, Vec<(&str, Commit)>)>) -> Changelog {
+        let mut repository = Repository::from(String::from("api"));
+        for (name, commits) in tags {
+            let mut sections: BTreeMap<String, Vec<Commit>> = BTreeMap::new();
+            for (section, commit) in commits {
+                sections
+                    .entry(section.to_string())
+                    .or_default()
+                    .push(commit);
+            }
+
+            repository
+                .tags
+                .push(Tag::from((name.to_string(), sections)));
+        }
+
+        Changelog {
+            repositories: vec![repository],
+            ..Changelog::default()
+        }
+    }
+
+    #[test]
+    fn detect_requires_a_version_heading() {
+        assert!(detect(EXISTING));
+        assert!(!detect("# Changelog\n\n## api\n\n### v1.0.0\n"));
+    }
+
+    #[test]
+    fn update_moves_the_released_entries_out_of_unreleased() {
+        let fix = commit("abc1234def", "fix", "fix: handle empty input", "2024-02-01");
+        let feat = commit("fed4321cba", "feat", "feat: add the parser", "2024-02-02");
+        let changelog = changelog(vec![
+            ("v1.1.0", vec![("Added", feat), ("Fixed", fix)]),
+            ("v1.0.0", vec![]),
+        ]);
+
+        let content = update(EXISTING, changelog);
+
+        assert_eq!(
+            content,
+            "# Changelog
+
+All notable changes to this project will be documented in this file.
+
+## [Unreleased]
+
+## [v1.1.0] - 2024-02-02
+
+### Added
+
+- add the parser (fed4321)
+
+### Fixed
+
+- Handle the empty input with care (abc1234)
+
+## [1.0.0] - 2024-01-01
+
+### Added
+
+- first release (0000001)
+"
+        );
+    }
+
+    #[test]
+    fn update_writes_the_unreleased_entries_above_the_releases() {
+        let fix = commit("1234567890", "fix", "fix(parser): trim lines", "2024-02-01");
+        let changelog = changelog(vec![(UNRELEASED, vec![("Fixed", fix)])]);
+
+        let content = update("# Changelog\n\n## [1.0.0] - 2024-01-01\n", changelog);
+
+        assert_eq!(
+            content,
+            "# Changelog\n\n## [Unreleased]\n\n### Fixed\n\n- trim lines (1234567)\n\n## [1.0.0] - 2024-01-01\n"
+        );
+    }
+
+    #[test]
+    fn update_does_not_record_an_entry_twice() {
+        let fix = commit("abc1234def", "fix", "fix: handle empty input", "2024-02-01");
+        let changelog = changelog(vec![(UNRELEASED, vec![("Fixed", fix)])]);
+
+        let content = update(EXISTING, changelog.to_owned());
+
+        assert_eq!(content, EXISTING);
+        assert_eq!(update(&content, changelog), EXISTING);
+    }
+}
@@ -0,0 +1,52 @@
+//! # Changelog
+//!
+//! Generate a changelog using the git commit history. The library provides the
+//! configuration, the parser and the renderers used by the `git-changelog`
+//! binary, so that other tools (e.g. release bots or cargo xtask scripts) could
+//! embed the generation of the changelog.
+//!
+//! ```no_run
+//! use std::{convert::TryFrom, path::PathBuf, rc::Rc};
+//!
+//! use git_changelog::{
+//!     conf::Configuration,
+//!     parser::{Changelog, MarkdownChangelog},
+//! };
+//!
+//! let conf = Configuration::try_from(PathBuf::from("changelog.toml"))?;
+//! let changelog = Changelog::try_from(Rc::new(conf))?;
+//!
+//! println!("{}", MarkdownChangelog::from(changelog));
+//! # Ok::<(), Box<dyn std::error::Error + Send + Sync>>(())
+//! ```
+//!
+//! The [`builder::ChangelogBuilder`] generates a changelog without a
+//! configuration file, the [`hooks::Hooks`] apply custom rules to its entries.
+//! The [`parser::History`] iterator walks over the history of a repository
+//! read by any [`backend::Backend`], for tools which process the commits
+//! incrementally. The [`renderer::Registry`] renders the changelog using any
+//! of the supported formats, to which new [`renderer::Renderer`] could be
+//! added.
+pub mod atomic;
+pub mod backend;
+pub mod builder;
+pub mod bump;
+pub mod cache;
+pub mod conf;
+pub mod dates;
+pub mod hooks;
+pub mod i18n;
+pub mod inject;
+pub mod keepachangelog;
+pub mod log;
+pub mod manifest;
+pub mod normalize;
+pub mod parser;
+pub mod plugin;
+pub mod renderer;
+pub mod rules;
+pub mod script;
+pub mod sign;
+pub mod stale;
+pub mod update;
+pub mod verify;
@@ -0,0 +1,240 @@
+//! # Log module
+//!
+//! The log module reads the commits from the output of git log, so that the
+//! changelog could be generated where the repository could not be opened
+//! (e.g. sparse checkouts on continuous integration runners).
+//!
+//! The output should be produced using the following format:
+//!
+//! ```shell
+//! $ git log --pretty=format:'%H%x1f%aN%x1f%aE%x1f%aI%x1f%cI%x1f%D%x1f%B%x1e'
+//! ```
+use std::{
+    borrow::Cow,
+    error::Error,
+    fs,
+    io::{self, Read},
+    path::Path,
+};
+
+use chrono::{DateTime, FixedOffset, Utc};
+use slog_scope::warn;
+
+use crate::{
+    conf,
+    parser::{self, Commit},
+};
+
+/// Format to give to git log in order to produce the expected output
+pub const FORMAT: &str = "%H%x1f%aN%x1f%aE%x1f%aI%x1f%cI%x1f%D%x1f%B%x1e";
+
+/// Separator of the fields of a commit
+const FIELD: char = '\u{1f}';
+
+/// Separator of the commits
+const RECORD: char = '\u{1e}';
+
+/// Commit read from the output of git log
+#[derive(Clone, Debug)]
+pub struct Entry {
+    pub hash: String,
+    pub author: String,
+    pub email: String,
+    pub authored: DateTime<FixedOffset>,
+    pub committed: DateTime<FixedOffset>,
+    /// Refs pointing at the commit (e.g. `HEAD -> main, tag: v1.0.0`)
+    pub refs: Vec<String>,
+    /// Full message of the commit
+    pub message: String,
+}
+
+impl Entry {
+    /// Returns the first tag pointing at the commit with the given prefix, if any
+    pub fn tag(&self, prefix: Option<&str>) -> Option<String> {
+        self.refs
+            .iter()
+            .filter_map(|name| name.strip_prefix("tag: "))
+            .find(|name| prefix.map_or(true, |prefix| name.starts_with(prefix)))
+            .map(String::from)
+    }
+
+    /// Returns the first paragraph of the message on a single line, as done
+    /// by git for the summary of a commit
+    pub fn summary(&self) -> String {
+        self.message
+            .trim()
+            .split("\n\n")
+            .next()
+            .unwrap_or_default()
+            .lines()
+            .map(str::trim)
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+impl From<(&conf::Repository, &Entry)> for Commit {
+    fn from(tuple: (&conf::Repository, &Entry)) -> Self {
+        let (conf, entry) = tuple;
+
+        Commit {
+            hash: entry.hash.to_owned(),
+            short_hash: entry.hash.chars().take(conf.hash_length).collect(),
+            message: entry.summary(),
+            author: entry.author.to_owned(),
+            email: entry.email.to_owned(),
+            date: entry.committed.with_timezone(&Utc).format("%F").to_string(),
+            authored: entry.authored,
+            committed: entry.committed,
+            link: None,
+            kind: String::new(),
+            scope: None,
+            breaking: parser::is_breaking(&entry.message),
+            origin: parser::origin(&entry.message),
+            duplicates: vec![],
+            first_contribution: false,
+            diffstat: None,
+            submodule: None,
+            verification: None,
+            sign_offs: if conf.sign_offs {
+                parser::sign_offs(&entry.message)
+                    .into_iter()
+                    .map(|(name, _)| name)
+                    .collect()
+            } else {
+                vec![]
+            },
+        }
+    }
+}
+
+/// Parse a commit formatted using the expected format
+fn entry(record: &str) -> Result<Entry, Box<dyn Error + Send + Sync>> {
+    let fields: Vec<_> = record.splitn(7, FIELD).collect();
+    if fields.len() != 7 {
+        return Err(format!(
+            "could not parse commit, expected 7 fields but got {}, use the format '{}'",
+            fields.len(),
+            FORMAT
+        )
+        .into());
+    }
+
+    let date = |field: &str| {
+        DateTime::parse_from_rfc3339(field).map_err(|err| {
+            format!(
+                "could not parse date '{}' of commit '{}', {}",
+                field, fields[0], err
+            )
+        })
+    };
+
+    Ok(Entry {
+        hash: fields[0].to_string(),
+        author: fields[1].to_string(),
+        email: fields[2].to_string(),
+        authored: date(fields[3])?,
+        committed: date(fields[4])?,
+        refs: fields[5]
+            .split(", ")
+            .filter(|name| !name.is_empty())
+            .map(String::from)
+            .collect(),
+        message: parser::newlines(fields[6]),
+    })
+}
+
+/// Parse the output of git log, returns the commits from the oldest one
+pub fn parse(content: &str) -> Result<Vec<Entry>, Box<dyn Error + Send + Sync>> {
+    let mut entries = content
+        .split(RECORD)
+        .map(|record| record.trim_start_matches(['\r', '\n']))
+        .filter(|record| !record.trim().is_empty())
+        .map(entry)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // git log starts from the most recent commit
+    entries.reverse();
+
+    Ok(entries)
+}
+
+/// Read the output of git log from the file at the given path or from the
+/// standard input if the path is -
+pub fn read(path: &Path) -> Result<Vec<Entry>, Box<dyn Error + Send + Sync>> {
+    let content = if path == Path::new("-") {
+        let mut content = vec![];
+        io::stdin()
+            .read_to_end(&mut content)
+            .map_err(|err| format!("could not read the standard input, {}", err))?;
+
+        content
+    } else {
+        fs::read(path).map_err(|err| format!("could not read file '{:?}', {}", path, err))?
+    };
+
+    // Histories may hold messages written using another encoding
+    let content = String::from_utf8_lossy(&content);
+    if let Cow::Owned(_) = content {
+        warn!("Could not decode the output of git log as utf-8, invalid sequences are replaced"; "path" => path.to_string_lossy().to_string());
+    }
+
+    parse(&content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns a record of git log using the expected format
+    fn record(hash: &str, refs: &str, message: &str) -> String {
+        let fields = [
+            hash,
+            "Jane Doe",
+            "jane@example.com",
+            "2024-01-02T10:00:00+01:00",
+            "2024-01-03T10:00:00+01:00",
+            refs,
+            message,
+        ];
+
+        format!("{}{}", fields.join(&FIELD.to_string()), RECORD)
+    }
+
+    #[test]
+    fn parse_returns_the_commits_from_the_oldest_one() {
+        let content = format!(
+            "{}\n{}\n",
+            record("b2", "HEAD -> main, tag: v1.0.0", "feat: second\n\nBody\n"),
+            record("a1", "", "fix: first\r\n")
+        );
+
+        let entries = parse(&content).expect("log to be parsed");
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].hash, "a1");
+        assert!(entries[0].refs.is_empty());
+        assert_eq!(entries[0].message, "fix: first\n");
+        assert_eq!(entries[1].refs, vec!["HEAD -> main", "tag: v1.0.0"]);
+        assert_eq!(entries[1].tag(Some("v")).as_deref(), Some("v1.0.0"));
+        assert_eq!(entries[1].summary(), "feat: second");
+        assert_eq!(
+            entries[1].authored.to_rfc3339(),
+            "2024-01-02T10:00:00+01:00"
+        );
+    }
+
+    #[test]
+    fn parse_fails_on_records_of_another_format() {
+        assert!(parse("a1 fix: first\n").is_err());
+
+        let content =
+            record("a1", "", "fix: first").replace("2024-01-02T10:00:00+01:00", "yesterday");
+        assert!(parse(&content).is_err());
+    }
+
+    #[test]
+    fn parse_accepts_an_empty_log() {
+        assert!(parse("").expect("log to be parsed").is_empty());
+    }
+}
@@ -1,24 +1,53 @@
 //! # Logger module.
 //!
 //! The logger module provides the log facility.
-use std::cmp::min;
+use std::{cmp::min, io::stderr};
 
-use slog::{slog_o, Drain, Level, LevelFilter, Logger};
+use slog::{slog_o, Discard, Drain, Level, LevelFilter, Logger};
 use slog_async::Async;
+use slog_json::Json;
 use slog_scope::{set_global_logger, GlobalLoggerGuard as Guard};
 use slog_term::{FullFormat, TermDecorator};
 
-/// Initialize the logger. Set the verbosity.
-pub fn initialize(verbose: usize) -> Guard {
-    let term_decorator = TermDecorator::new().build();
-    let term_drain = FullFormat::new(term_decorator).build().fuse();
-    let term_drain = Async::new(term_drain).build().fuse();
+/// Names of the levels accepted by --log-level
+pub const LEVELS: &[&str] = &["critical", "error", "warning", "info", "debug", "trace"];
 
-    let level = Level::Critical.as_usize() + verbose;
-    let level = min(level, Level::Trace.as_usize());
-    let level = Level::from_usize(level).unwrap_or(Level::Info);
+/// Parse the name of a level
+pub fn level(name: &str) -> Result<Level, String> {
+    name.parse()
+        .map_err(|_| format!("could not parse log level '{}'", name))
+}
+
+/// Names of the formats accepted by --log-format
+pub const FORMATS: &[&str] = &["text", "json"];
+
+/// Initialize the logger. Set the verbosity, the given level takes precedence
+/// over it and nothing is logged if quiet. Events are written as one json
+/// object per line using the json format, for CI systems to aggregate them.
+pub fn initialize(verbose: usize, level: Option<Level>, quiet: bool, format: &str) -> Guard {
+    if quiet {
+        return set_global_logger(Logger::root(Discard, slog_o!()));
+    }
+
+    let level = level.unwrap_or_else(|| {
+        let level = Level::Critical.as_usize() + verbose;
+        let level = min(level, Level::Trace.as_usize());
+        Level::from_usize(level).unwrap_or(Level::Info)
+    });
+
+    let drain = match format {
+        "json" => {
+            let json_drain = Json::new(stderr()).add_default_keys().build().fuse();
+            Async::new(json_drain).build().fuse()
+        }
+        _ => {
+            let term_decorator = TermDecorator::new().build();
+            let term_drain = FullFormat::new(term_decorator).build().fuse();
+            Async::new(term_drain).build().fuse()
+        }
+    };
 
-    let drain = LevelFilter::new(term_drain, level).fuse();
+    let drain = LevelFilter::new(drain, level).fuse();
 
     set_global_logger(Logger::root(drain, slog_o!()))
 }
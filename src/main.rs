@@ -1,21 +1,45 @@
 //! # Changelog
 //!
 //! Generate a changelog using the git commit history
-use std::{convert::TryFrom, error::Error, fs::File, io::Write, path::PathBuf, rc::Rc};
+use std::{
+    error::Error,
+    path::{Path, PathBuf},
+    process,
+    rc::Rc,
+};
 
-use slog_scope::{crit, debug, warn};
+use serde_json::{json, Value};
+use slog::Level;
+use slog_scope::{crit, debug, error, warn};
 use structopt::StructOpt;
 
+use git_changelog::{
+    atomic, builder, bump,
+    conf::{self, Configuration},
+    dates, inject, manifest, parser,
+    parser::Changelog,
+    renderer, rules, sign,
+    sign::Signer,
+    stale, update,
+};
+
 use crate::{
-    conf::Configuration,
-    parser::{Changelog, HTMLChangelog, MarkdownChangelog},
+    cmd::Command,
+    exit::Code,
+    progress::Progress,
+    summary::{Planned, Summary},
     version::{BUILD_DATE, GITHASH, PROFILE},
+    watch::{Change, Watcher},
 };
 
-mod conf;
+mod cmd;
+mod exit;
 mod logger;
-mod parser;
+mod pager;
+mod progress;
+mod summary;
 mod version;
+mod watch;
 
 #[derive(StructOpt, Clone, Debug)]
 pub struct Args {
@@ -27,10 +51,54 @@ pub struct Args {
     #[structopt(short = "t", long = "check")]
     pub check: bool,
 
+    /// Check that the changelog on disk is up to date instead of writing it
+    #[structopt(long = "check-stale")]
+    pub check_stale: bool,
+
+    /// Generate the changelog again each time a commit or a tag is added to a repository
+    #[structopt(short = "W", long = "watch", conflicts_with = "check-stale")]
+    pub watch: bool,
+
+    /// Report the files which would be written, without writing anything
+    #[structopt(long = "dry-run", conflicts_with_all = &["check-stale", "watch"])]
+    pub dry_run: bool,
+
+    /// Exit with the code 2 when commits are skipped because they do not follow the convention
+    #[structopt(long = "fail-on-skipped")]
+    pub fail_on_skipped: bool,
+
+    /// Prepend the new releases to the existing markdown changelog instead of overwriting it
+    #[structopt(short = "u", long = "update")]
+    pub update: bool,
+
     /// Increase the log verbosity
     #[structopt(short = "v", global = true, parse(from_occurrences))]
     pub verbose: usize,
 
+    /// Do not log anything nor print the summary
+    #[structopt(short = "q", long = "quiet", global = true, conflicts_with_all = &["verbose", "log-level"])]
+    pub quiet: bool,
+
+    /// Log the events of the given level and the more severe ones, instead of using the verbosity
+    #[structopt(
+        long = "log-level",
+        env = "CHANGELOG_LOG_LEVEL",
+        global = true,
+        possible_values = logger::LEVELS,
+        parse(try_from_str = logger::level)
+    )]
+    pub log_level: Option<Level>,
+
+    /// Write the logs using the specified format
+    #[structopt(
+        long = "log-format",
+        env = "CHANGELOG_LOG_FORMAT",
+        global = true,
+        possible_values = logger::FORMATS,
+        default_value = "text"
+    )]
+    pub log_format: String,
+
     /// Use the specified configuration file
     #[structopt(
         short = "c",
@@ -40,18 +108,87 @@ pub struct Args {
     )]
     pub config: PathBuf,
 
-    /// Output using the specified format (available formats are: html or markdown)
+    /// Apply the entries of the given profile of the configuration
+    #[structopt(long = "profile", env = "CHANGELOG_PROFILE", global = true)]
+    pub profile: Option<String>,
+
+    /// Generate the changelog of the git repository at the given path using the default kinds instead of the configuration file
+    #[structopt(long = "path")]
+    pub path: Option<PathBuf>,
+
+    /// Use the specified range of commits of the repository given by --path (e.g. v1.0.0..v2.0.0)
+    #[structopt(long = "range", requires = "path")]
+    pub range: Option<String>,
+
+    /// Use the specified kind and title (e.g. feat=Features) instead of the default kinds, could be repeated
+    #[structopt(long = "kind", requires = "path", number_of_values = 1, parse(try_from_str = kind))]
+    pub kinds: Vec<(String, String)>,
+
+    /// Output using the specified format (available formats are: html, markdown, badge, ical, bulk, term or analytics, which could also be given by their extension, e.g. md)
     #[structopt(short = "f", long = "format", default_value = "markdown")]
     pub format: String,
 
     /// Set the output destination
-    #[structopt(short = "o", long = "output", default_value = "CHANGELOG")]
+    #[structopt(
+        short = "o",
+        long = "output",
+        env = "CHANGELOG_OUTPUT",
+        default_value = "CHANGELOG"
+    )]
     pub output: PathBuf,
+
+    /// Inject the changelog between the changelog:start and changelog:end markers of the given file instead of writing the output
+    #[structopt(short = "i", long = "inject")]
+    pub inject: Option<PathBuf>,
+
+    /// Write the commits excluded from the changelog, along with the reason, in the given json file
+    #[structopt(long = "skipped")]
+    pub skipped: Option<PathBuf>,
+
+    /// Write a sha256 checksum file next to the generated file
+    #[structopt(long = "checksum")]
+    pub checksum: bool,
+
+    /// Write a detached signature next to the generated file using the given tool
+    #[structopt(long = "sign", possible_values = Signer::VARIANTS)]
+    pub sign: Option<Signer>,
+
+    /// Use the specified key to sign the generated file
+    #[structopt(long = "sign-key", requires = "sign")]
+    pub sign_key: Option<String>,
+
+    #[structopt(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// Parse a kind given as kind=title
+fn kind(value: &str) -> Result<(String, String), String> {
+    match value.split_once('=') {
+        Some((kind, title)) if !kind.is_empty() && !title.is_empty() => {
+            Ok((kind.to_owned(), title.to_owned()))
+        }
+        _ => Err(format!(
+            "could not parse kind '{}', expected kind=title (e.g. feat=Features)",
+            value
+        )),
+    }
 }
 
 #[paw::main]
-fn main(args: Args) -> Result<(), Box<dyn Error + Send + Sync>> {
-    let _guard = logger::initialize(args.verbose);
+fn main(args: Args) {
+    let code = match run(args) {
+        Ok(code) => code,
+        Err(err) => {
+            eprintln!("Error: {:?}", err.to_string());
+            exit::code(err.as_ref())
+        }
+    };
+
+    process::exit(code as i32);
+}
+
+fn run(mut args: Args) -> Result<Code, Box<dyn Error + Send + Sync>> {
+    let _guard = logger::initialize(args.verbose, args.log_level, args.quiet, &args.log_format);
 
     if PROFILE == "debug" {
         warn!("{} is running in \"debug\" mode", env!("CARGO_PKG_NAME"));
@@ -68,50 +205,262 @@ fn main(args: Args) -> Result<(), Box<dyn Error + Send + Sync>> {
         version += &format!("{} profile {}\n", env!("CARGO_PKG_NAME"), PROFILE);
 
         println!("{}", version);
-        return Ok(());
+        return Ok(Code::Success);
     }
 
-    let conf = match Configuration::try_from(args.config.to_owned()) {
-        Ok(conf) => Rc::new(conf),
-        Err(err) => {
-            return Err(format!("could not load configuration, {}", err).into());
-        }
-    };
+    // Formats could be given by their extension, the commands expect their name
+    if let Some(renderer) = renderer::Registry::default().get(&args.format) {
+        args.format = renderer.name().to_owned();
+    }
+
+    if let Some(ref command) = args.command {
+        return command.execute(&args).map(|_| Code::Success);
+    }
 
+    let conf = cmd::configuration(&args)?;
+
+    debug!("{:?}", conf);
     if args.check {
-        debug!("{:?}", conf);
         println!("Configuration is healthy");
     }
 
-    let changelog = match Changelog::try_from(conf) {
+    if !args.watch {
+        return generate(&args, conf);
+    }
+
+    // The configuration file is not used along with --path
+    let config = args.path.is_none().then(|| args.config.to_owned());
+    let mut watcher = Watcher::new(conf.as_ref(), config);
+    let (mut conf, mut next) = (conf.to_owned(), conf);
+    loop {
+        // Failures are reported and the next change is awaited, they are
+        // likely fixed by the next commit. A reloaded configuration is
+        // swapped only once the changelog has been generated using it.
+        match generate(&args, next.to_owned()) {
+            Ok(_) if !Rc::ptr_eq(&conf, &next) => {
+                watcher.follow(&next);
+                conf = next;
+            }
+            Ok(_) => {}
+            Err(err) => eprintln!("Error: {:?}", err.to_string()),
+        }
+
+        next = match watcher.wait() {
+            Change::Repositories => conf.to_owned(),
+            Change::Configuration => match cmd::reload(&args) {
+                Ok(reloaded) => reloaded,
+                Err(err) => {
+                    error!("could not reload the configuration, keep the previous one"; "error" => err.to_string());
+                    conf.to_owned()
+                }
+            },
+        };
+    }
+}
+
+/// Generate the changelog and write it, returns the exit code of the run
+fn generate(args: &Args, conf: Rc<Configuration>) -> Result<Code, Box<dyn Error + Send + Sync>> {
+    // Logs written on the standard error would break the progress line
+    let mut progress = Progress::new(!args.quiet && args.verbose == 0 && args.log_level.is_none());
+    let changelog = match Changelog::generate(conf, &mut progress) {
         Ok(changelog) => changelog,
         Err(err) => {
+            progress.finish();
             crit!("could not generate the changelog"; "error" => err.to_string());
-            return Err(format!("could not generate the changelog, {}", err).into());
+
+            // Other errors come from the configuration (e.g. invalid patterns)
+            let code = match err.downcast_ref::<parser::RepositoryError>() {
+                Some(err) if err.error.is::<parser::Violations>() => Code::Violations,
+                Some(_) => Code::Git,
+                None => Code::Configuration,
+            };
+
+            return Err(exit::with(
+                code,
+                format!("could not generate the changelog, {}", err).into(),
+            ));
         }
     };
 
-    let (extension, content) = match args.format.as_str() {
-        "html" => ("html", format!("{}", HTMLChangelog::from(changelog))),
-        "markdown" => ("md", format!("{}", MarkdownChangelog::from(changelog))),
-        format => {
-            crit!("could not use the given value for formatting, the format '{}' is not yet implemented", format);
-            return Err(format!("could not use the given value for formatting, the format '{}' is not yet implemented", format).into());
+    let summary = Summary::from(&changelog);
+    let code = match args.fail_on_skipped && summary.warnings() > 0 {
+        true => Code::Warnings,
+        false => Code::Success,
+    };
+
+    let skipped = match args.skipped {
+        Some(ref path) => {
+            let content = serde_json::to_string_pretty(&report(&changelog))
+                .map_err(|err| format!("could not serialize the skipped commits, {}", err))?;
+            Some((path.to_owned(), content))
         }
+        None => None,
     };
 
-    let mut output = args.output;
+    if args.dry_run {
+        let planned = plan(args, changelog);
+        progress.finish();
+
+        let mut planned = planned?;
+        if let Some((path, content)) = skipped {
+            planned.push((path, Some(content.len())));
+        }
+
+        if !args.quiet {
+            summary.plan(&planned);
+        }
+
+        return Ok(code);
+    }
+
+    let paged = paged(args);
+    if paged {
+        // The pager takes over the terminal, the progress is erased beforehand
+        progress.finish();
+    }
 
-    output.set_extension(extension);
-    let mut file = File::create(&output)
-        .map_err(|err| format!("could not create file '{:?}', {}", output, err))?;
+    let outputs = write(args.to_owned(), changelog);
+    progress.finish();
+
+    let mut outputs = outputs?;
+
+    if let Some((path, content)) = skipped {
+        cmd::write(&path, &content)?;
+        outputs.push(path);
+    }
 
-    file.write_all(content.as_bytes())
-        .map_err(|err| format!("could not write content, {}", err))?;
-    file.flush()
-        .map_err(|err| format!("could not flush content on disk, {}", err))?;
-    file.sync_all()
-        .map_err(|err| format!("could not sync content on disk, {}", err))?;
+    if !args.quiet && !paged {
+        summary.print(&outputs);
+    }
+
+    Ok(code)
+}
+
+/// Returns the commits excluded from the changelog of each repository
+fn report(changelog: &Changelog) -> Value {
+    changelog
+        .repositories
+        .iter()
+        .flat_map(|repository| {
+            repository.skipped.iter().map(move |skipped| {
+                json!({
+                    "repository": repository.name,
+                    "hash": skipped.hash,
+                    "message": skipped.message,
+                    "category": skipped.reason.category(),
+                    "reason": skipped.reason.to_string(),
+                })
+            })
+        })
+        .collect()
+}
+
+/// Returns whether the changelog is displayed in the terminal instead of
+/// being written
+fn paged(args: &Args) -> bool {
+    args.format == "term" && !args.update && !args.check_stale && args.inject.is_none()
+}
+
+/// Write the changelog as requested by the flags, returns the written files
+fn write(args: Args, changelog: Changelog) -> Result<Vec<PathBuf>, Box<dyn Error + Send + Sync>> {
+    if paged(&args) {
+        let (_, content) = renderer::render(&args.format, changelog)?;
+        pager::page(&content)?;
+
+        return Ok(vec![]);
+    }
+
+    // The markdown changelog is streamed to the file unless it is processed afterwards
+    if !args.update && !args.check_stale && args.inject.is_none() && args.format == "markdown" {
+        let mut output = args.output;
+        output.set_extension("md");
+        cmd::stream(&output, changelog)?;
+
+        return seal(&output, args.checksum, args.sign, args.sign_key.as_deref());
+    }
+
+    let (output, content) = render(&args, changelog)?;
+
+    if args.check_stale {
+        stale::check(&output, &content).map_err(|err| exit::with(Code::Stale, err))?;
+        return Ok(vec![]);
+    }
+
+    cmd::write(&output, &content)?;
+
+    seal(&output, args.checksum, args.sign, args.sign_key.as_deref())
+}
+
+/// Returns the files which would be written as requested by the flags, along
+/// with their size when known, without touching anything
+fn plan(args: &Args, changelog: Changelog) -> Result<Vec<Planned>, Box<dyn Error + Send + Sync>> {
+    if paged(args) {
+        return Ok(vec![]);
+    }
+
+    let (output, content) = render(args, changelog)?;
+
+    let mut planned = vec![(output.to_owned(), Some(content.len()))];
+    if args.checksum {
+        planned.push((sign::with_suffix(&output, "sha256"), None));
+    }
+
+    if let Some(signer) = args.sign {
+        planned.push((sign::with_suffix(&output, signer.extension()), None));
+    }
+
+    Ok(planned)
+}
+
+/// Render the changelog as requested by the flags, returns the path of the
+/// file to write along with its content
+fn render(
+    args: &Args,
+    changelog: Changelog,
+) -> Result<(PathBuf, String), Box<dyn Error + Send + Sync>> {
+    let mut output = args.output.to_owned();
+    let (extension, content) = match (args.update, args.format.as_str()) {
+        (true, "markdown") => {
+            output.set_extension("md");
+            (String::from("md"), update::render(&output, changelog)?)
+        }
+        (true, format) => {
+            let err = format!("could not update the changelog, the format '{}' is not supported, only markdown is", format);
+            crit!("{}", err);
+            return Err(err.into());
+        }
+        (false, format) => match renderer::render(format, changelog) {
+            Ok(rendered) => rendered,
+            Err(err) => {
+                crit!("{}", err);
+                return Err(err);
+            }
+        },
+    };
+
+    output.set_extension(&extension);
+    match args.inject {
+        Some(ref path) => Ok((path.to_owned(), inject::inject(path, &content)?)),
+        None => Ok((output, content)),
+    }
+}
+
+/// Write the checksum and the signature of the generated file, if requested,
+/// returns the generated file along with them
+fn seal(
+    output: &Path,
+    checksum: bool,
+    signer: Option<Signer>,
+    key: Option<&str>,
+) -> Result<Vec<PathBuf>, Box<dyn Error + Send + Sync>> {
+    let mut written = vec![output.to_owned()];
+    if checksum {
+        written.push(sign::checksum(output)?);
+    }
+
+    if let Some(signer) = signer {
+        written.push(sign::sign(output, signer, key)?);
+    }
 
-    Ok(())
+    Ok(written)
 }
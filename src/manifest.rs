@@ -0,0 +1,148 @@
+//! # Manifest module
+//!
+//! The manifest module rewrites the version field of the package manifests
+//! (Cargo.toml, package.json and pyproject.toml) as part of a release, the
+//! rest of the file is kept as is.
+use std::{error::Error, fs, path::Path};
+
+use regex::Regex;
+use semver::Version;
+
+/// Sections of the toml manifests holding the version of the package
+const TOML_SECTIONS: &[&str] = &[
+    "[package]",
+    "[workspace.package]",
+    "[project]",
+    "[tool.poetry]",
+];
+
+/// Returns the toml content with the version of the package replaced
+fn toml(content: &str, version: &Version) -> Option<String> {
+    let re = Regex::new(r#"^(\s*version\s*=\s*)"[^"]*"(.*)$"#)
+        .expect("pattern to be a valid regular expression");
+
+    let mut section = "";
+    let mut replaced = false;
+    let mut lines = vec![];
+    for line in content.split_inclusive('\n') {
+        let trimmed = line.trim();
+        if trimmed.starts_with('[') {
+            section = trimmed;
+        }
+
+        let (text, eol) = match line.strip_suffix('\n') {
+            Some(text) => (text, "\n"),
+            None => (line, ""),
+        };
+
+        match re.captures(text) {
+            Some(captures) if TOML_SECTIONS.contains(&section) => {
+                lines.push(format!(
+                    "{}\"{}\"{}{}",
+                    &captures[1], version, &captures[2], eol
+                ));
+                replaced = true;
+            }
+            _ => lines.push(line.to_string()),
+        }
+    }
+
+    if replaced {
+        Some(lines.concat())
+    } else {
+        None
+    }
+}
+
+/// Returns the json content with the version of the package replaced
+fn json(content: &str, version: &Version) -> Option<String> {
+    let re = Regex::new(r#"("version"\s*:\s*)"[^"]*""#)
+        .expect("pattern to be a valid regular expression");
+
+    if !re.is_match(content) {
+        return None;
+    }
+
+    Some(
+        re.replacen(content, 1, format!("${{1}}\"{}\"", version).as_str())
+            .to_string(),
+    )
+}
+
+/// Rewrite the version of the manifest at the given path
+pub fn bump(path: &Path, version: &Version) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let rewritten = bumped(path, version)?;
+
+    fs::write(path, rewritten)
+        .map_err(|err| format!("could not write manifest '{:?}', {}", path, err).into())
+}
+
+/// Returns the content of the manifest at the given path with the version
+/// rewritten, without writing it
+pub fn bumped(path: &Path, version: &Version) -> Result<String, Box<dyn Error + Send + Sync>> {
+    let content = fs::read_to_string(path)
+        .map_err(|err| format!("could not read manifest '{:?}', {}", path, err))?;
+
+    let rewritten = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("toml") => toml(&content, version),
+        Some("json") => json(&content, version),
+        _ => {
+            return Err(format!(
+                "could not bump manifest '{:?}', only toml and json manifests are supported",
+                path
+            )
+            .into())
+        }
+    };
+
+    rewritten
+        .ok_or_else(|| format!("could not find the version field in manifest '{:?}'", path).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn toml_replaces_the_version_of_the_package_only() {
+        let content = "[package]\nname = \"crate\"\nversion = \"0.1.0\" # bumped\n\n[dependencies.serde]\nversion = \"1.0\"\n";
+
+        let bumped = toml(content, &Version::new(0, 2, 0)).expect("version to be found");
+
+        assert_eq!(
+            bumped,
+            "[package]\nname = \"crate\"\nversion = \"0.2.0\" # bumped\n\n[dependencies.serde]\nversion = \"1.0\"\n"
+        );
+    }
+
+    #[test]
+    fn toml_supports_the_python_projects() {
+        let content = "[tool.poetry]\nversion = \"1.0.0\"";
+
+        assert_eq!(
+            toml(content, &Version::parse("1.1.0-rc.1").unwrap()).as_deref(),
+            Some("[tool.poetry]\nversion = \"1.1.0-rc.1\"")
+        );
+    }
+
+    #[test]
+    fn toml_without_version_is_not_bumped() {
+        assert_eq!(
+            toml("[workspace]\nmembers = []\n", &Version::new(1, 0, 0)),
+            None
+        );
+    }
+
+    #[test]
+    fn json_replaces_the_first_version_only() {
+        let content = "{\n  \"version\": \"1.0.0\",\n  \"engines\": { \"version\" : \"18\" }\n}\n";
+
+        let bumped = json(content, &Version::new(1, 1, 0)).expect("version to be found");
+
+        assert_eq!(
+            bumped,
+            "{\n  \"version\": \"1.1.0\",\n  \"engines\": { \"version\" : \"18\" }\n}\n"
+        );
+        assert_eq!(json("{}", &Version::new(1, 1, 0)), None);
+    }
+}
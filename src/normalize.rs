@@ -0,0 +1,81 @@
+//! # Normalize module
+//!
+//! The normalize module rewrites the subjects of the commits before they are
+//! rendered, so that the entries of the changelog look consistent even when
+//! the style of the commits is not.
+use std::error::Error;
+
+use regex::Regex;
+
+use crate::conf::{Normalization, Rewrite};
+
+/// Returns the compiled patterns of the rewrite rules along with their
+/// replacement
+pub fn rewrites(rules: &[Rewrite]) -> Result<Vec<(Regex, String)>, Box<dyn Error + Send + Sync>> {
+    rules
+        .iter()
+        .map(|rule| match Regex::new(&rule.pattern) {
+            Ok(re) => Ok((re, rule.replacement.to_owned())),
+            Err(err) => {
+                Err(format!("could not compile pattern '{}', {}", rule.pattern, err).into())
+            }
+        })
+        .collect()
+}
+
+/// Returns the subject rewritten by each rule in order
+pub fn rewrite(subject: &str, rewrites: &[(Regex, String)]) -> String {
+    rewrites
+        .iter()
+        .fold(subject.to_string(), |subject, (re, replacement)| {
+            re.replace_all(&subject, replacement.as_str()).into_owned()
+        })
+}
+
+/// Returns the prefix of the subject (e.g. `feat(parser)!: `) and its
+/// description
+fn split(subject: &str) -> (&str, &str) {
+    match subject.find(": ") {
+        Some(idx) if !subject[..idx].contains(' ') => subject.split_at(idx + 2),
+        _ => ("", subject),
+    }
+}
+
+/// Returns the description with its first word replaced by the imperative
+/// form configured for it, if any
+fn imperative(description: &str, normalization: &Normalization) -> String {
+    let (word, rest) = match description.find(char::is_whitespace) {
+        Some(idx) => description.split_at(idx),
+        None => (description, ""),
+    };
+
+    match normalization
+        .imperative
+        .iter()
+        .find(|(from, _)| from.eq_ignore_ascii_case(word))
+    {
+        Some((_, to)) => format!("{}{}", to, rest),
+        None => description.to_string(),
+    }
+}
+
+/// Returns the subject with the normalizations applied to its description,
+/// the kind and the scope are kept untouched
+pub fn subject(subject: &str, normalization: &Normalization) -> String {
+    let (prefix, description) = split(subject);
+    let mut description = imperative(description.trim(), normalization);
+
+    // An ellipsis is not a period
+    if normalization.strip_period && description.ends_with('.') && !description.ends_with("..") {
+        description.pop();
+    }
+
+    if normalization.capitalize {
+        let mut chars = description.chars();
+        if let Some(first) = chars.next() {
+            description = first.to_uppercase().chain(chars).collect();
+        }
+    }
+
+    format!("{}{}", prefix, description)
+}
@@ -0,0 +1,60 @@
+//! # Pager module
+//!
+//! The pager module displays content in the terminal, through the pager of
+//! the user (`$PAGER`, `less` otherwise) when it does not fit on the screen.
+use std::{
+    env,
+    error::Error,
+    io::{stdout, Write},
+    process::{Command, Stdio},
+};
+
+use crossterm::{terminal, tty::IsTty};
+use slog_scope::warn;
+
+/// Pager used when `$PAGER` is not set, keeping the colors and exiting when
+/// the content fits on the screen
+const DEFAULT: &str = "less -FRX";
+
+/// Display the content, through the pager if it is longer than the terminal
+pub fn page(content: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let fits = match terminal::size() {
+        Ok((_, rows)) => content.lines().count() < rows as usize,
+        Err(_) => true,
+    };
+
+    if fits || !stdout().is_tty() {
+        return print(content);
+    }
+
+    let pager = env::var("PAGER").unwrap_or_else(|_| String::from(DEFAULT));
+    let child = Command::new("sh")
+        .args(["-c", &pager])
+        .stdin(Stdio::piped())
+        .spawn();
+
+    let mut child = match child {
+        Ok(child) => child,
+        Err(err) => {
+            warn!("could not run pager '{}', {}", pager, err);
+            return print(content);
+        }
+    };
+
+    if let Some(mut stdin) = child.stdin.take() {
+        // The pager could be quit before reading everything
+        let _ = stdin.write_all(content.as_bytes());
+    }
+
+    child
+        .wait()
+        .map_err(|err| format!("could not wait for pager '{}', {}", pager, err))?;
+
+    Ok(())
+}
+
+fn print(content: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
+    stdout()
+        .write_all(content.as_bytes())
+        .map_err(|err| format!("could not write content, {}", err).into())
+}
@@ -2,301 +2,2105 @@
 //!
 //! The parser module will parse the git commit history to build changelog
 
-use std::{collections::HashMap, convert::TryFrom, error::Error, rc::Rc};
+use std::{
+    borrow::Cow,
+    cmp::Ordering,
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
+    convert::TryFrom,
+    error::Error,
+    fmt::{self, Display, Formatter},
+    fs,
+    path::PathBuf,
+    process::Command,
+    rc::Rc,
+    time::Instant,
+};
 
 use askama::Template;
-use chrono::{DateTime, NaiveDateTime, Utc};
+use chrono::{DateTime, Datelike, FixedOffset, Utc};
 use git2 as git;
-use regex::Regex;
-use slog_scope::{error, info, warn};
+use regex::{Regex, RegexBuilder, RegexSet};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use slog_scope::{debug, error, info, warn};
 use strfmt::strfmt;
 
-use crate::conf::{self, Configuration};
+use crate::{
+    backend::{self, Backend, Git, Read, Revision},
+    conf::{
+        self, Anonymization, BotPolicy, Configuration, Dates, EmptyPolicy, Language, Layout,
+        Period, Sort, SortKey, SortOrder,
+    },
+    dates,
+    hooks::{Chain, Hooks, Verdict},
+    i18n, log, normalize,
+    plugin::Plugins,
+    script::Scripts,
+    verify::{Verification, Verifier},
+};
 
 // https://regex101.com/r/X9RoUY/4
-const PATTERN: &str =
-    r"(?P<kind>[\w \-\./\\]+)(\((?P<scope>[\w \-\./\\]+)\))?: (?P<message>[\w \-\./\\]+)";
+pub const PATTERN: &str = r"(?P<kind>[\w \-\./\\]+)(\((?P<scope>[\w \-\./\\]+)\))?(?P<breaking>!)?: (?P<message>[\w \-\./\\]+)";
 
-#[derive(Clone, Debug)]
+/// Variables available in the link layout
+pub const LINK_VARIABLES: &[&str] = &[
+    "hash",
+    "fullhash",
+    "shorthash",
+    "kind",
+    "scope",
+    "tag",
+    "author",
+    "repo",
+];
+
+/// Name given to the tag of unreleased commits in links
+pub const UNRELEASED_REF: &str = "HEAD";
+
+/// Name of the section holding commits which are not yet tagged
+pub const UNRELEASED: &str = "Technical preview";
+
+/// Notice of the repositories whose history is incomplete
+pub const SHALLOW: &str = "History is incomplete, the repository is a shallow clone";
+
+/// Reason why a commit is not pushed in the changelog
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Skip {
+    Merge,
+    Unparseable,
+    UnknownKind(String),
+    Filtered(String),
+}
+
+impl Skip {
+    /// Returns the name of the reason, regardless of its details
+    pub fn category(&self) -> &'static str {
+        match self {
+            Self::Merge => "merge",
+            Self::Unparseable => "unparseable",
+            Self::UnknownKind(_) => "unknown_kind",
+            Self::Filtered(_) => "filtered",
+        }
+    }
+}
+
+impl Display for Skip {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Merge => write!(f, "merge commit"),
+            Self::Unparseable => write!(f, "message does not follow the pattern"),
+            Self::UnknownKind(kind) => {
+                write!(f, "kind '{}' is not contained in provided kinds", kind)
+            }
+            Self::Filtered(reason) => write!(f, "{}", reason),
+        }
+    }
+}
+
+/// Kind and scope of a commit message, a breaking change is marked by a `!`
+/// before the colon
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Classification {
+    pub kind: String,
+    pub scope: Option<String>,
+    pub breaking: bool,
+}
+
+/// Name of the variant of the grammar matching merge commits
+const MERGE: &str = "merge";
+
+/// Name of the variant of the grammar following the conventional commits
+const CONVENTIONAL: &str = "conventional";
+
+/// Classify commit messages against the kinds and scopes of a repository
+pub struct Classifier<'a> {
+    /// Variants of the grammar matched in a single pass, the first matching
+    /// one is used
+    set: RegexSet,
+    variants: Vec<(String, Regex)>,
+    kinds: &'a HashMap<String, String>,
+    scopes: Option<&'a Vec<String>>,
+}
+
+impl<'a> Classifier<'a> {
+    /// Build the grammar made of the merges, the given variants and the
+    /// conventional commits, in this order
+    pub fn new(
+        kinds: &'a HashMap<String, String>,
+        scopes: Option<&'a Vec<String>>,
+        grammar: &[conf::Variant],
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let mut variants = vec![(
+            String::from(MERGE),
+            Regex::new(r"^Merge (pull request|branch)")
+                .expect("pattern to be a valid regular expression"),
+        )];
+
+        for variant in grammar {
+            let re = Regex::new(&variant.pattern)
+                .map_err(|err| format!("could not compile grammar '{}', {}", variant.name, err))?;
+
+            if !re.capture_names().any(|name| name == Some("kind")) {
+                return Err(format!(
+                    "could not use grammar '{}', the pattern has no kind group",
+                    variant.name
+                )
+                .into());
+            }
+
+            variants.push((variant.name.to_owned(), re));
+        }
+
+        variants.push((
+            String::from(CONVENTIONAL),
+            Regex::new(PATTERN).expect("pattern to be a valid regular expression"),
+        ));
+
+        let set = RegexSet::new(variants.iter().map(|(_, re)| re.as_str()))
+            .map_err(|err| format!("could not compile grammar, {}", err))?;
+
+        Ok(Self {
+            set,
+            variants,
+            kinds,
+            scopes,
+        })
+    }
+
+    /// Returns the kind and scope of the message or the reason to skip it, only
+    /// the first matching variant is used to capture the groups
+    pub fn classify(&self, message: &str) -> Result<Classification, Skip> {
+        // Matches are yielded by ascending index, so in the grammar order
+        let idx = self
+            .set
+            .matches(message)
+            .into_iter()
+            .next()
+            .ok_or(Skip::Unparseable)?;
+
+        let (name, re) = &self.variants[idx];
+        if name == MERGE {
+            return Err(Skip::Merge);
+        }
+
+        let captures = re.captures(message).ok_or(Skip::Unparseable)?;
+
+        // The kind group of a variant could be optional
+        let kind = String::from(captures.name("kind").ok_or(Skip::Unparseable)?.as_str());
+
+        let scope = captures
+            .name("scope")
+            .map(|scope| String::from(scope.as_str()));
+
+        if !self.kinds.contains_key(&kind) {
+            return Err(Skip::UnknownKind(kind));
+        }
+
+        Ok(Classification {
+            kind,
+            scope,
+            breaking: captures.name("breaking").is_some(),
+        })
+    }
+
+    /// Returns the sub scopes which are not contained in provided scopes, if
+    /// scopes are omitted, all scopes are accepted
+    pub fn unknown_scopes<'s>(&self, scope: &'s str) -> Vec<&'s str> {
+        match self.scopes {
+            Some(scopes) => scope
+                .split(',')
+                .filter(|sub_scope| !scopes.iter().any(|s| s == sub_scope))
+                .collect(),
+            None => vec![],
+        }
+    }
+}
+
+/// Returns the diff of the commit against its first parent, or against the
+/// empty tree for a root commit
+fn diff<'r>(
+    repo: &'r git::Repository,
+    commit: &git::Commit<'_>,
+    options: &mut git::DiffOptions,
+) -> Result<git::Diff<'r>, Box<dyn Error + Send + Sync>> {
+    let tree = commit.tree().map_err(|err| {
+        format!(
+            "could not retrieve tree of commit '{}', {}",
+            commit.id(),
+            err
+        )
+    })?;
+
+    let parent = match commit.parents().next() {
+        Some(parent) => Some(parent.tree().map_err(|err| {
+            format!(
+                "could not retrieve tree of commit '{}', {}",
+                parent.id(),
+                err
+            )
+        })?),
+        None => None,
+    };
+
+    Ok(repo
+        .diff_tree_to_tree(parent.as_ref(), Some(&tree), Some(options))
+        .map_err(|err| format!("could not diff commit '{}', {}", commit.id(), err))?)
+}
+
+/// Returns true if the commit touches any of the given paths compared to its
+/// first parent, or to the empty tree for a root commit
+pub fn touches(
+    repo: &git::Repository,
+    commit: &git::Commit<'_>,
+    paths: &[PathBuf],
+) -> Result<bool, Box<dyn Error + Send + Sync>> {
+    let mut options = git::DiffOptions::new();
+    for path in paths {
+        options.pathspec(path);
+    }
+
+    Ok(diff(repo, commit, &mut options)?.deltas().len() > 0)
+}
+
+/// Returns the number of files changed, insertions and deletions of the
+/// commit compared to its first parent
+pub fn diffstat(
+    repo: &git::Repository,
+    commit: &git::Commit<'_>,
+) -> Result<Diffstat, Box<dyn Error + Send + Sync>> {
+    let stats = diff(repo, commit, &mut git::DiffOptions::new())?
+        .stats()
+        .map_err(|err| format!("could not compute diffstat of '{}', {}", commit.id(), err))?;
+
+    Ok(Diffstat {
+        files: stats.files_changed(),
+        insertions: stats.insertions(),
+        deletions: stats.deletions(),
+    })
+}
+
+/// Path of a submodule along with its old and new recorded commits
+pub type Update = (PathBuf, git::Oid, git::Oid);
+
+/// Returns the submodules whose recorded commit is updated by the commit
+/// compared to its first parent
+pub fn submodules(
+    repo: &git::Repository,
+    commit: &git::Commit<'_>,
+) -> Result<Vec<Update>, Box<dyn Error + Send + Sync>> {
+    let diff = diff(repo, commit, &mut git::DiffOptions::new())?;
+
+    Ok(diff
+        .deltas()
+        .filter(|delta| {
+            delta.old_file().mode() == git::FileMode::Commit
+                && delta.new_file().mode() == git::FileMode::Commit
+        })
+        .filter_map(|delta| {
+            let path = delta.new_file().path()?;
+            Some((
+                path.to_owned(),
+                delta.old_file().id(),
+                delta.new_file().id(),
+            ))
+        })
+        .collect())
+}
+
+/// Returns the package and the tag prefix of a monorepo tag, which is either
+/// of the form `pkg@1.2.3` (e.g. `@scope/pkg@1.2.3`) or `component/v1.2.3`
+pub fn package(tag: &str) -> Option<(&str, &str)> {
+    let idx = match tag.rfind('@') {
+        Some(idx) if idx > 0 => idx,
+        _ => tag.rfind('/')?,
+    };
+
+    let version = tag[idx + 1..].trim_start_matches('v');
+    if !version.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+
+    Some((&tag[..idx], &tag[..idx + 1]))
+}
+
+/// Split a monorepo into one repository per package found in its tags
+pub fn packages(
+    conf: &conf::Repository,
+) -> Result<Vec<conf::Repository>, Box<dyn Error + Send + Sync>> {
+    let repo = git::Repository::discover(&conf.path).map_err(|err| {
+        format!(
+            "could not retrieve git repository at '{:?}', {}",
+            conf.path, err
+        )
+    })?;
+
+    let names = repo
+        .tag_names(None)
+        .map_err(|err| format!("could not retrieve git tags, {}", err))?;
+
+    let mut prefixes: Vec<(String, String)> = names
+        .iter()
+        .flatten()
+        .filter_map(package)
+        .map(|(name, prefix)| (name.to_string(), prefix.to_string()))
+        .collect();
+
+    prefixes.sort();
+    prefixes.dedup();
+
+    Ok(prefixes
+        .into_iter()
+        .map(|(name, prefix)| conf::Repository {
+            paths: match conf.packages.get(&name) {
+                Some(path) => Some(vec![path.to_owned()]),
+                None => conf.paths.to_owned(),
+            },
+            name,
+            tag_prefix: Some(prefix),
+            monorepo: false,
+            ..conf.to_owned()
+        })
+        .collect())
+}
+
+/// Compile the patterns as case insensitive regular expressions
+pub fn patterns(patterns: &[String]) -> Result<Vec<Regex>, regex::Error> {
+    patterns
+        .iter()
+        .map(|pattern| RegexBuilder::new(pattern).case_insensitive(true).build())
+        .collect()
+}
+
+/// Compile the patterns matching the bots of the repository
+fn patterns_of_bots(conf: &conf::Repository) -> Result<Vec<Regex>, Box<dyn Error + Send + Sync>> {
+    patterns(&conf.bots.patterns)
+        .map_err(|err| format!("could not compile bots.patterns, {}", err).into())
+}
+
+/// Exclude commits of a repository according to its configuration
+pub struct Filter<'a> {
+    conf: &'a conf::Repository,
+    authors_include: Vec<Regex>,
+    authors_exclude: Vec<Regex>,
+    bots: Vec<Regex>,
+    include: Option<Regex>,
+    exclude: Option<Regex>,
+}
+
+impl<'a> Filter<'a> {
+    pub fn new(conf: &'a conf::Repository) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        Ok(Self {
+            conf,
+            authors_include: patterns(&conf.authors_include)
+                .map_err(|err| format!("could not compile authors_include, {}", err))?,
+            authors_exclude: patterns(&conf.authors_exclude)
+                .map_err(|err| format!("could not compile authors_exclude, {}", err))?,
+            bots: patterns_of_bots(conf)?,
+            include: conf
+                .include_pattern
+                .as_deref()
+                .map(Regex::new)
+                .transpose()
+                .map_err(|err| format!("could not compile include_pattern, {}", err))?,
+            exclude: conf
+                .exclude_pattern
+                .as_deref()
+                .map(Regex::new)
+                .transpose()
+                .map_err(|err| format!("could not compile exclude_pattern, {}", err))?,
+        })
+    }
+
+    /// Returns the reason to exclude a parsed commit given its full message, if any
+    pub fn check_message(&self, message: &str) -> Result<(), Skip> {
+        if let Some(ref re) = self.exclude {
+            if re.is_match(message) {
+                return Err(Skip::Filtered(String::from(
+                    "message matches exclude_pattern",
+                )));
+            }
+        }
+
+        if let Some(ref re) = self.include {
+            if !re.is_match(message) {
+                return Err(Skip::Filtered(String::from(
+                    "message does not match include_pattern",
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns true if the author, given its name and email, is a bot
+    pub fn is_bot_author(&self, name: &str, email: &str) -> bool {
+        self.bots
+            .iter()
+            .any(|re| re.is_match(name) || re.is_match(email))
+    }
+
+    /// Returns true if the commit is authored by a bot
+    pub fn is_bot(&self, commit: &git::Commit<'_>) -> bool {
+        let author = commit.author();
+
+        self.is_bot_author(
+            &String::from_utf8_lossy(author.name_bytes()),
+            &String::from_utf8_lossy(author.email_bytes()),
+        )
+    }
+
+    /// Returns the reason to exclude the commit, if any
+    pub fn check(&self, commit: &git::Commit<'_>) -> Result<(), Skip> {
+        let author = commit.author();
+
+        self.check_parts(
+            &commit.id().to_string(),
+            &String::from_utf8_lossy(commit.message_bytes()),
+            &String::from_utf8_lossy(author.name_bytes()),
+            &String::from_utf8_lossy(author.email_bytes()),
+        )
+    }
+
+    /// Returns the reason to exclude the commit given its hash, its full
+    /// message and its author, if any
+    pub fn check_parts(
+        &self,
+        hash: &str,
+        message: &str,
+        name: &str,
+        email: &str,
+    ) -> Result<(), Skip> {
+        if self
+            .conf
+            .skip_commits
+            .iter()
+            .any(|skipped| !skipped.is_empty() && hash.starts_with(skipped.as_str()))
+        {
+            return Err(Skip::Filtered(String::from("commit is in the skip list")));
+        }
+
+        let message = message.to_lowercase();
+        if let Some(marker) = self
+            .conf
+            .skip_markers
+            .iter()
+            .find(|marker| !marker.is_empty() && message.contains(&marker.to_lowercase()))
+        {
+            return Err(Skip::Filtered(format!("message contains '{}'", marker)));
+        }
+
+        let matches = |patterns: &[Regex]| {
+            patterns
+                .iter()
+                .any(|re| re.is_match(name) || re.is_match(email))
+        };
+
+        if matches(&self.authors_exclude) {
+            return Err(Skip::Filtered(String::from("author is excluded")));
+        }
+
+        if !self.authors_include.is_empty() && !matches(&self.authors_include) {
+            return Err(Skip::Filtered(String::from("author is not included")));
+        }
+
+        if self.conf.bots.policy == BotPolicy::Skip && self.is_bot_author(name, email) {
+            return Err(Skip::Filtered(String::from("author is a bot")));
+        }
+
+        Ok(())
+    }
+}
+
+/// Fetch the whole history of a shallow clone, using git in order to benefit
+/// from the credentials configured by the user
+fn unshallow(repo: &git::Repository) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let workdir = repo.workdir().unwrap_or_else(|| repo.path());
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(workdir)
+        .args(["fetch", "--unshallow", "--tags"])
+        .output()
+        .map_err(|err| format!("could not execute git, {}", err))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "git {}, {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+        .into());
+    }
+
+    info!("Fetch the whole history of the shallow clone"; "path" => workdir.to_string_lossy().to_string());
+    Ok(())
+}
+
+/// Returns the directory shared by the linked worktrees of the repository
+/// and its main one, holding the shallow file, the hooks and the cache
+pub fn commondir(repo: &git::Repository) -> PathBuf {
+    match fs::read_to_string(repo.path().join("commondir")) {
+        Ok(dir) => {
+            let path = repo.path().join(dir.trim());
+            path.canonicalize().unwrap_or(path)
+        }
+        Err(_) => repo.path().to_owned(),
+    }
+}
+
+/// Returns whether the repository is a shallow clone, libgit2 misses it in
+/// linked worktrees
+pub fn is_shallow(repo: &git::Repository) -> bool {
+    fs::metadata(commondir(repo).join("shallow"))
+        .map(|metadata| metadata.len() > 0)
+        .unwrap_or_default()
+}
+
+/// Returns the commit the history is walked from, the tip of the branch or
+/// HEAD if omitted
+fn tip(
+    repo: &git::Repository,
+    branch: Option<&str>,
+) -> Result<git::Oid, Box<dyn Error + Send + Sync>> {
+    match branch {
+        Some(branch) => repo
+            .resolve_reference_from_short_name(branch)
+            .and_then(|reference| reference.peel_to_commit())
+            .map(|commit| commit.id())
+            .map_err(|err| format!("could not retrieve branch '{}', {}", branch, err).into()),
+        None => repo
+            .head()
+            .and_then(|head| head.peel_to_commit())
+            .map(|commit| commit.id())
+            .map_err(|err| format!("could not push HEAD commit, {}", err).into()),
+    }
+}
+
+/// Commits of the history, sorted from the oldest to the newest one
+pub type Walk<'r> = Box<dyn Iterator<Item = Result<git::Oid, git::Error>> + 'r>;
+
+/// Create a walker over the given range or from the branch, HEAD if omitted,
+/// sorted from the oldest to the newest commit
+pub fn walk<'r>(
+    repo: &'r git::Repository,
+    range: Option<&str>,
+    branch: Option<&str>,
+) -> Result<Walk<'r>, Box<dyn Error + Send + Sync>> {
+    if range.is_none() && branch.is_none() {
+        if let Err(err) = repo.head() {
+            if err.code() == git::ErrorCode::UnbornBranch {
+                let branch = repo
+                    .find_reference("HEAD")
+                    .ok()
+                    .and_then(|head| head.symbolic_target().map(String::from))
+                    .unwrap_or_else(|| String::from("HEAD"));
+
+                return Err(Box::new(Unborn(branch)));
+            }
+        }
+
+        if repo.head_detached().unwrap_or_default() {
+            info!("HEAD is detached, walk the history from the checked out commit"; "path" => repo.path().to_string_lossy().to_string());
+        }
+    }
+
+    // The walker of libgit2 fails on the missing parents of a shallow clone
+    if is_shallow(repo) {
+        return Ok(Box::new(shallow(repo, range, branch)?.into_iter().map(Ok)));
+    }
+
+    let mut revwalk = repo
+        .revwalk()
+        .map_err(|err| format!("could create a walker on git history, {}", err))?;
+
+    match range {
+        Some(range) => {
+            revwalk
+                .push_range(range)
+                .map_err(|err| format!("could not parse commit range, {}", err))?;
+        }
+        None => {
+            revwalk
+                .push(tip(repo, branch)?)
+                .map_err(|err| format!("could not push HEAD commit, {}", err))?;
+        }
+    }
+
+    revwalk
+        .set_sorting(git::Sort::TIME | git::Sort::REVERSE)
+        .map_err(|err| format!("failed to sort git commit history, {}", err))?;
+
+    Ok(Box::new(revwalk))
+}
+
+/// Returns the commits of the range, or from HEAD if omitted, of a shallow
+/// clone sorted from the oldest to the newest one. The walk stops at the
+/// boundary commits of the clone, as their parents have not been fetched.
+fn shallow(
+    repo: &git::Repository,
+    range: Option<&str>,
+    branch: Option<&str>,
+) -> Result<Vec<git::Oid>, Box<dyn Error + Send + Sync>> {
+    let path = commondir(repo).join("shallow");
+    let boundaries: HashSet<_> = fs::read_to_string(&path)
+        .map_err(|err| format!("could not read file '{:?}', {}", path, err))?
+        .lines()
+        .filter_map(|line| git::Oid::from_str(line.trim()).ok())
+        .collect();
+
+    let (from, to) = match range {
+        Some(range) => {
+            let spec = repo
+                .revparse(range)
+                .map_err(|err| format!("could not parse commit range, {}", err))?;
+
+            let to = spec.to().map(|object| object.id()).ok_or_else(|| {
+                format!("could not parse commit range '{}', it has no end", range)
+            })?;
+
+            (spec.from().map(|object| object.id()), to)
+        }
+        None => (None, tip(repo, branch)?),
+    };
+
+    let hidden: HashSet<_> = match from {
+        Some(from) => ancestors(repo, from, &boundaries)?
+            .into_iter()
+            .map(|(oid, _)| oid)
+            .collect(),
+        None => HashSet::new(),
+    };
+
+    let mut commits: Vec<_> = ancestors(repo, to, &boundaries)?
+        .into_iter()
+        .filter(|(oid, _)| !hidden.contains(oid))
+        .collect();
+
+    commits.reverse();
+    commits.sort_by_key(|(_, time)| *time);
+
+    Ok(commits.into_iter().map(|(oid, _)| oid).collect())
+}
+
+/// Returns the commit and its ancestors along with their commit time, without
+/// going past the given boundaries
+fn ancestors(
+    repo: &git::Repository,
+    start: git::Oid,
+    boundaries: &HashSet<git::Oid>,
+) -> Result<Vec<(git::Oid, i64)>, Box<dyn Error + Send + Sync>> {
+    let mut seen = HashSet::new();
+    let mut queue = VecDeque::from([start]);
+    let mut commits = vec![];
+
+    while let Some(oid) = queue.pop_front() {
+        if !seen.insert(oid) {
+            continue;
+        }
+
+        let commit = repo
+            .find_commit(oid)
+            .map_err(|err| format!("could not retrieve commit '{}', {}", oid, err))?;
+
+        commits.push((oid, commit.time().seconds()));
+        if !boundaries.contains(&oid) {
+            queue.extend(commit.parent_ids());
+        }
+    }
+
+    Ok(commits)
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Commit {
+    /// Full object identifier of the commit
     pub hash: String,
+    /// Abbreviated hash of the commit, as displayed
+    pub short_hash: String,
     pub message: String,
     pub author: String,
+    pub email: String,
     pub date: String,
+    /// Date at which the change has been authored
+    pub authored: DateTime<FixedOffset>,
+    /// Date at which the change has been committed
+    pub committed: DateTime<FixedOffset>,
     pub link: Option<String>,
+    pub kind: String,
+    pub scope: Option<String>,
+    pub breaking: bool,
+    /// Hash of the commit this one has been cherry-picked from, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origin: Option<String>,
+    /// Hashes of the other commits holding the same change
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub duplicates: Vec<String>,
+    /// The commit is the first one of its author in the walked history
+    #[serde(default)]
+    pub first_contribution: bool,
+    /// Size of the change, if computed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub diffstat: Option<Diffstat>,
+    /// Path of the submodule holding the commit, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub submodule: Option<String>,
+    /// Outcome of the verification of the signature, if checked
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verification: Option<Verification>,
+    /// Names of the people who signed off the commit, if displayed
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sign_offs: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct Diffstat {
+    pub files: usize,
+    pub insertions: usize,
+    pub deletions: usize,
+}
+
+impl Commit {
+    /// Replace the author of the commit according to the anonymization, the
+    /// email and the sign-offs are never kept
+    pub fn anonymize(&mut self, anonymization: Anonymization) {
+        self.author = match anonymization {
+            Anonymization::None => return,
+            Anonymization::Initials => self
+                .author
+                .split_whitespace()
+                .filter_map(|word| word.chars().next())
+                .map(|c| format!("{}.", c.to_uppercase()))
+                .collect(),
+            Anonymization::Hash => {
+                let identity = if self.email.is_empty() {
+                    self.author.to_lowercase()
+                } else {
+                    self.email.to_lowercase()
+                };
+
+                Sha256::digest(identity.as_bytes())
+                    .iter()
+                    .take(4)
+                    .map(|byte| format!("{:02x}", byte))
+                    .collect()
+            }
+            Anonymization::Omit => String::new(),
+        };
+
+        self.email = String::new();
+        self.sign_offs.clear();
+    }
+
+    /// Format the link of the commit using the layout, the tag is the one
+    /// holding the commit
+    pub fn format_link(
+        &mut self,
+        layout: &str,
+        repository: &str,
+        tag: &str,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        // Commits of a submodule are not part of the linked repository
+        if self.submodule.is_some() {
+            return Ok(());
+        }
+
+        let mut vars = HashMap::new();
+
+        vars.insert(String::from("hash"), self.hash.to_owned());
+        vars.insert(String::from("fullhash"), self.hash.to_owned());
+        vars.insert(String::from("shorthash"), self.short_hash.to_owned());
+        vars.insert(String::from("kind"), self.kind.to_owned());
+        vars.insert(
+            String::from("scope"),
+            self.scope.to_owned().unwrap_or_default(),
+        );
+        vars.insert(String::from("tag"), tag.to_owned());
+        vars.insert(String::from("author"), self.author.to_owned());
+        vars.insert(String::from("repo"), repository.to_owned());
+
+        self.link = Some(
+            strfmt(layout, &vars)
+                .map_err(|err| format!("could not format commit link, {}", err))?,
+        );
+
+        Ok(())
+    }
+}
+
+/// Decode the field of the commit having the given hash, invalid utf-8
+/// sequences are replaced by the replacement character
+pub fn decode(bytes: &[u8], field: &str, hash: &str) -> String {
+    match String::from_utf8_lossy(bytes) {
+        Cow::Borrowed(value) => value.to_owned(),
+        Cow::Owned(value) => {
+            warn!("Could not decode as utf-8, invalid sequences are replaced"; "field" => field, "hash" => hash);
+            value
+        }
+    }
+}
+
+/// Returns the text with its CRLF line endings (e.g. of a commit made on
+/// Windows) replaced by LF, so that its paragraphs are split as usual
+pub fn newlines(text: &str) -> String {
+    text.replace("\r\n", "\n")
+}
+
+/// Returns true if the full message holds a breaking change footer
+///
+/// See https://www.conventionalcommits.org/en/v1.0.0/#specification
+pub fn is_breaking(message: &str) -> bool {
+    message
+        .lines()
+        .any(|line| line.starts_with("BREAKING CHANGE:") || line.starts_with("BREAKING-CHANGE:"))
+}
+
+/// Returns the name and the email of the people who signed off the full
+/// message using a Signed-off-by trailer
+///
+/// See https://developercertificate.org
+pub fn sign_offs(message: &str) -> Vec<(String, String)> {
+    message
+        .lines()
+        .filter_map(|line| line.strip_prefix("Signed-off-by:"))
+        .filter_map(|identity| {
+            let (name, email) = identity.trim().rsplit_once('<')?;
+            let email = email.strip_suffix('>')?;
+
+            Some((name.trim().to_string(), email.trim().to_string()))
+        })
+        .collect()
+}
+
+/// Returns the hash of the commit the full message has been cherry-picked
+/// from, if any
+///
+/// See https://git-scm.com/docs/git-cherry-pick#Documentation/git-cherry-pick.txt--x
+pub fn origin(message: &str) -> Option<String> {
+    message.lines().find_map(|line| {
+        let hash = line
+            .trim()
+            .strip_prefix("(cherry picked from commit ")?
+            .strip_suffix(')')?;
+
+        Some(hash.to_string())
+    })
+}
+
+impl TryFrom<(&conf::Repository, &git::Commit<'_>)> for Commit {
+    type Error = Box<dyn Error + Send + Sync>;
+
+    fn try_from(tuple: (&conf::Repository, &git::Commit<'_>)) -> Result<Self, Self::Error> {
+        let (conf, commit) = tuple;
+        let hash = commit.id().to_string();
+        let author = match decode(commit.author().name_bytes(), "author", &hash) {
+            author if !author.is_empty() => author,
+            _ => decode(commit.committer().name_bytes(), "committer", &hash),
+        };
+
+        let email = decode(commit.author().email_bytes(), "email", &hash);
+
+        let message = match commit.summary_bytes() {
+            Some(summary) => decode(summary, "message", &hash),
+            None => decode(commit.message_bytes(), "message", &hash),
+        };
+
+        // The summary has already been reported if it could not be decoded
+        let full = newlines(&String::from_utf8_lossy(commit.message_bytes()));
+        let breaking = is_breaking(&full);
+        let origin = origin(&full);
+
+        let authored = dates::of(commit.author().when());
+        let committed = dates::of(commit.committer().when());
+        let date = committed.with_timezone(&Utc).format("%F").to_string();
+
+        Ok(Self {
+            short_hash: hash.chars().take(conf.hash_length).collect(),
+            hash,
+            message,
+            author,
+            email,
+            date,
+            authored,
+            committed,
+            link: None,
+            kind: String::new(),
+            scope: None,
+            breaking,
+            origin,
+            duplicates: vec![],
+            first_contribution: false,
+            diffstat: None,
+            submodule: None,
+            verification: None,
+            sign_offs: vec![],
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Tag {
+    pub name: String,
+    pub commits: BTreeMap<String, Vec<Commit>>,
+    /// Section of the commits rendered as a single collapsed entry
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub collapsed: Option<String>,
+    /// Outcome of the verification of the signature, if checked
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verification: Option<Verification>,
+}
+
+impl From<(String, BTreeMap<String, Vec<Commit>>)> for Tag {
+    fn from(tuple: (String, BTreeMap<String, Vec<Commit>>)) -> Self {
+        let (name, commits) = tuple;
+
+        Self {
+            name,
+            commits,
+            collapsed: None,
+            verification: None,
+        }
+    }
+}
+
+impl Tag {
+    /// Returns the number of entries of the tag
+    pub fn count(&self) -> usize {
+        self.commits.values().map(Vec::len).sum()
+    }
+
+    /// Returns true if the section is rendered as a single collapsed entry
+    pub fn is_collapsed(&self, section: &str) -> bool {
+        self.collapsed.as_deref() == Some(section)
+    }
+
+    /// Sort the entries of each section, entries which are equal keep the
+    /// order of the history
+    pub fn sort(&mut self, sort: Sort, conf: &Dates) {
+        if sort.by == SortKey::None {
+            return;
+        }
+
+        for commits in self.commits.values_mut() {
+            commits.sort_by(|a, b| {
+                let ordering = match sort.by {
+                    SortKey::None => Ordering::Equal,
+                    SortKey::Date => dates::time(a, conf).cmp(&dates::time(b, conf)),
+                    SortKey::Scope => a.scope.cmp(&b.scope),
+                    SortKey::Message => a.message.to_lowercase().cmp(&b.message.to_lowercase()),
+                };
+
+                match sort.order {
+                    SortOrder::Ascending => ordering,
+                    SortOrder::Descending => ordering.reverse(),
+                }
+            });
+        }
+    }
+
+    /// Merge the sections having fewer entries than the threshold into the
+    /// given section, if there are at least two of them. The collapsed
+    /// section is never merged.
+    pub fn merge_small(&mut self, section: &str, threshold: usize) {
+        let small: Vec<_> = self
+            .commits
+            .iter()
+            .filter(|(name, commits)| commits.len() < threshold && !self.is_collapsed(name))
+            .map(|(name, _)| name.to_owned())
+            .collect();
+
+        if small.len() < 2 {
+            return;
+        }
+
+        let mut merged = vec![];
+        for name in small {
+            merged.extend(self.commits.remove(&name).unwrap_or_default());
+        }
+
+        self.commits
+            .entry(section.to_string())
+            .or_default()
+            .extend(merged);
+    }
+
+    /// Move the commits matching the predicate into the given section, which
+    /// is then collapsed, if there are at least `minimum` of them
+    pub fn collapse<P>(&mut self, section: &str, minimum: usize, predicate: P)
+    where
+        P: Fn(&Commit) -> bool,
+    {
+        let count = self
+            .commits
+            .values()
+            .flatten()
+            .filter(|c| predicate(c))
+            .count();
+        if count == 0 || count < minimum {
+            return;
+        }
+
+        let mut collapsed = vec![];
+        for commits in self.commits.values_mut() {
+            let (matched, kept) = std::mem::take(commits).into_iter().partition(&predicate);
+            *commits = kept;
+            collapsed.extend::<Vec<_>>(matched);
+        }
+
+        self.commits.retain(|_, commits| !commits.is_empty());
+        self.commits
+            .entry(section.to_string())
+            .or_default()
+            .extend(collapsed);
+        self.collapsed = Some(section.to_string());
+    }
+
+    /// Returns the first and the last commits of the tag, using the configured
+    /// date of the commits
+    pub fn period(&self, conf: &Dates) -> Option<(&Commit, &Commit)> {
+        let commits = self.commits.values().flatten();
+        let first = commits
+            .clone()
+            .min_by_key(|commit| dates::time(commit, conf))?;
+        let last = commits.max_by_key(|commit| dates::time(commit, conf))?;
+
+        Some((first, last))
+    }
+
+    /// Split the commits of the tag into a tag per week or month, the most
+    /// recent first, using the configured date of the commits. Commits of the
+    /// ongoing period stay in this tag as the period is not over yet.
+    pub fn split(self, period: Period, conf: &Dates) -> Vec<Tag> {
+        let name = |time: DateTime<FixedOffset>| match period {
+            Period::Week => {
+                let week = time.iso_week();
+                format!("{}-W{:02}", week.year(), week.week())
+            }
+            Period::Month => time.format("%Y-%m").to_string(),
+        };
+
+        let ongoing = name(match dates::offset(&conf.timezone, &Utc::now().into()) {
+            Ok(offset) => Utc::now().with_timezone(&offset),
+            Err(_) => Utc::now().into(),
+        });
+
+        let mut periods: BTreeMap<String, BTreeMap<String, Vec<Commit>>> = BTreeMap::new();
+        for (section, commits) in self.commits {
+            for commit in commits {
+                let mut key = name(dates::time(&commit, conf));
+                if key == ongoing {
+                    key = self.name.to_owned();
+                }
+
+                periods
+                    .entry(key)
+                    .or_default()
+                    .entry(section.to_owned())
+                    .or_default()
+                    .push(commit);
+            }
+        }
+
+        let mut tags = vec![];
+        if let Some(commits) = periods.remove(&self.name) {
+            tags.push(Tag::from((self.name.to_owned(), commits)));
+        }
+
+        for (name, commits) in periods.into_iter().rev() {
+            tags.push(Tag::from((name, commits)));
+        }
+
+        for tag in &mut tags {
+            tag.collapsed = self.collapsed.to_owned();
+        }
+
+        tags
+    }
+
+    /// Returns the date of the most recent commit of the tag
+    pub fn date(&self) -> Option<&str> {
+        self.commits
+            .values()
+            .flatten()
+            .map(|commit| commit.date.as_str())
+            .max()
+    }
+}
+
+/// Error raised while processing a repository, e.g. its history could not be
+/// read, as opposed to the errors of the configuration
+#[derive(Debug)]
+pub struct RepositoryError {
+    pub repository: String,
+    pub error: Box<dyn Error + Send + Sync>,
+}
+
+impl Display for RepositoryError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "could not process repository '{}', {}",
+            self.repository, self.error
+        )
+    }
+}
+
+impl Error for RepositoryError {}
+
+/// Branch checked out in a repository which has no commits yet
+#[derive(Debug)]
+pub struct Unborn(pub String);
+
+impl Display for Unborn {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "the branch '{}' has no commits yet, set 'empty' to skip or render the repository or 'branch' to walk another one",
+            self.0.trim_start_matches("refs/heads/")
+        )
+    }
+}
+
+impl Error for Unborn {}
+
+/// Commits of a strict repository which do not follow the convention, along
+/// with the reason
+#[derive(Debug)]
+pub struct Violations(pub Vec<(String, String)>);
+
+impl Display for Violations {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let commits: Vec<_> = self
+            .0
+            .iter()
+            .map(|(hash, reason)| format!("{} ({})", hash, reason))
+            .collect();
+
+        write!(
+            f,
+            "{} commit(s) do not follow the convention, {}",
+            commits.len(),
+            commits.join(", ")
+        )
+    }
+}
+
+impl Error for Violations {}
+
+/// Commit which is not pushed in the changelog
+#[derive(Clone, Debug)]
+pub struct Skipped {
+    pub hash: String,
+    pub message: String,
+    pub reason: Skip,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Repository {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    pub tags: Vec<Tag>,
+    /// Warning rendered along with the repository, e.g. its history is incomplete
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notice: Option<String>,
+    #[serde(skip)]
+    pub skipped: Vec<Skipped>,
+}
+
+impl From<String> for Repository {
+    fn from(name: String) -> Self {
+        Repository {
+            name,
+            group: None,
+            tags: Default::default(),
+            notice: None,
+            skipped: Default::default(),
+        }
+    }
+}
+
+/// Event of the walk over the history of a repository
+#[derive(Clone, Debug)]
+pub enum Event {
+    /// Commit to render in the given section
+    Commit(String, Box<Commit>),
+    /// Commit which is not rendered
+    Skipped(Skipped),
+    /// Tag holding the commits yielded since the previous tag
+    Tag(String),
+}
+
+/// Walk over the history of a repository from the oldest commit, yielding the
+/// parsed commits and the tags as they come, the commits yielded after the
+/// last tag are the unreleased ones
+pub struct History<'r> {
+    kinds: &'r HashMap<String, String>,
+    conf: &'r conf::Repository,
+    backend: Box<dyn Backend + 'r>,
+    classifier: Classifier<'r>,
+    filter: Filter<'r>,
+    /// Oldest commit of each author, skipped commits are contributions too
+    firsts: HashMap<String, (i64, String)>,
+    /// Events to yield after the commit, the commits of the submodules it
+    /// updates then the tag pointing to it
+    pending: VecDeque<Event>,
+    /// Commits which do not follow the convention, when strict
+    violations: Vec<(String, String)>,
+}
+
+impl<'r> History<'r> {
+    pub fn new(
+        repo: &'r git::Repository,
+        kinds: &'r HashMap<String, String>,
+        conf: &'r conf::Repository,
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        Self::from_backend(Box::new(Git::open(repo, conf)?), kinds, conf)
+    }
+
+    /// Returns the history made of the entries of the output of git log,
+    /// features requiring to open the repository are not available
+    pub fn from_log(
+        entries: Vec<log::Entry>,
+        kinds: &'r HashMap<String, String>,
+        conf: &'r conf::Repository,
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        if conf.range.is_some()
+            || conf.paths.is_some()
+            || conf.diffstat
+            || conf.cache
+            || conf.verify
+        {
+            warn!("range, paths, diffstat, cache and verify are ignored when reading the output of git log"; "repository" => &conf.name);
+        }
+
+        Self::from_backend(Box::new(backend::Log::new(entries, conf)), kinds, conf)
+    }
+
+    /// Returns the history made of the commits supplied by the given backend
+    pub fn from_backend(
+        backend: Box<dyn Backend + 'r>,
+        kinds: &'r HashMap<String, String>,
+        conf: &'r conf::Repository,
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        Ok(Self {
+            kinds,
+            conf,
+            backend,
+            classifier: Classifier::new(kinds, conf.scopes.as_ref(), &conf.grammar)?,
+            filter: Filter::new(conf)?,
+            firsts: HashMap::new(),
+            pending: VecDeque::new(),
+            violations: vec![],
+        })
+    }
+
+    /// Returns the hashes of the first commit of each author in the history
+    /// walked so far
+    pub fn firsts(&self) -> HashSet<String> {
+        self.firsts
+            .values()
+            .map(|(_, hash)| hash.to_owned())
+            .collect()
+    }
+
+    /// Returns the hash of the commits which do not follow the convention in
+    /// the history walked so far, along with the reason, when strict
+    pub fn violations(&self) -> &[(String, String)] {
+        &self.violations
+    }
+
+    /// Classify the commit, returns the event it leads to
+    fn classify(&mut self, revision: Revision) -> Event {
+        let Revision {
+            mut commit,
+            message: full,
+            name,
+            email,
+            tag: tagged,
+            diffstat: stats,
+            submodules,
+        } = revision;
+
+        let filtered = self.filter.check_parts(&commit.hash, &full, &name, &email);
+        let diverted =
+            self.conf.bots.policy == BotPolicy::Section && self.filter.is_bot_author(&name, &email);
+
+        let identity = match commit.email.as_str() {
+            "" => commit.author.to_lowercase(),
+            email => email.to_lowercase(),
+        };
+
+        let time = commit.committed.timestamp();
+        match self.firsts.get(&identity) {
+            Some((first, _)) if *first < time => {}
+            _ => {
+                self.firsts.insert(identity, (time, commit.hash.to_owned()));
+            }
+        }
+
+        for (path, commits) in submodules {
+            for commit in commits {
+                if let Some(event) = self.submodule(&path, commit) {
+                    self.pending.push_back(event);
+                }
+            }
+        }
+
+        // The tag should be kept even if the tagged commit is skipped
+        self.pending.extend(tagged.map(Event::Tag));
+
+        let Commit {
+            short_hash: hash,
+            message,
+            ..
+        } = commit.to_owned();
+        let Classification {
+            kind,
+            scope,
+            breaking,
+        } = match filtered
+            .and_then(|_| self.classifier.classify(&message))
+            .and_then(|classification| self.filter.check_message(&full).map(|_| classification))
+        {
+            Ok(classification) => classification,
+            // Bots often do not follow the convention, their commits are kept anyway
+            Err(Skip::Unparseable) | Err(Skip::UnknownKind(_)) if diverted => Classification {
+                kind: String::new(),
+                scope: None,
+                breaking: false,
+            },
+            Err(reason) => {
+                let repository = &self.conf.name;
+                match reason {
+                    Skip::Merge => {
+                        info!("Skip merge commit"; "event" => "commit_skipped", "repository" => repository, "hash" => &hash, "reason" => reason.to_string());
+                    }
+                    Skip::Unparseable => {
+                        error!("Could not parse the message"; "event" => "commit_skipped", "repository" => repository, "hash" => &hash, "message" => &message, "reason" => reason.to_string());
+                    }
+                    Skip::UnknownKind(ref kind) => {
+                        warn!("Kind is not contained in provided kinds"; "hash" => &hash, "kind" => kind);
+                        warn!("Skip commit"; "event" => "commit_skipped", "repository" => repository, "hash" => &hash, "reason" => reason.to_string());
+                    }
+                    Skip::Filtered(ref filter) => {
+                        info!("Skip filtered commit"; "event" => "commit_skipped", "repository" => repository, "hash" => &hash, "reason" => filter);
+                    }
+                }
+
+                if self.conf.strict.unwrap_or_default()
+                    && matches!(reason, Skip::Unparseable | Skip::UnknownKind(_))
+                {
+                    self.violations.push((hash.to_owned(), reason.to_string()));
+                }
+
+                return Event::Skipped(Skipped {
+                    hash,
+                    message,
+                    reason,
+                });
+            }
+        };
+
+        if let Some(ref scope) = scope {
+            let unknown = self.classifier.unknown_scopes(scope);
+            if !unknown.is_empty() {
+                warn!("Scope is not contained in provided scopes";  "hash" => &hash, "scope" => scope);
+
+                if self.conf.strict.unwrap_or_default() {
+                    let reason = format!("scope '{}' is not known", unknown.join(","));
+                    self.violations.push((hash.to_owned(), reason));
+                }
+            }
+        }
+
+        commit.kind = kind.to_owned();
+        commit.scope = scope;
+        commit.breaking |= breaking;
+        commit.diffstat = stats;
+
+        let section = if diverted {
+            self.conf.bots.section.as_str()
+        } else {
+            self.kinds
+                .get(&kind)
+                .expect("To have 'kind' defined in repository's kinds")
+                .as_str()
+        };
+
+        Event::Commit(String::from(section), Box::new(commit))
+    }
+
+    /// Classify the commit of the submodule, its commits are gathered in a
+    /// section named after its path
+    fn submodule(&mut self, path: &str, mut commit: Commit) -> Option<Event> {
+        match self.classifier.classify(&commit.message) {
+            Ok(Classification {
+                kind,
+                scope,
+                breaking,
+            }) => {
+                commit.kind = kind;
+                commit.scope = scope;
+                commit.breaking |= breaking;
+
+                Some(Event::Commit(path.to_owned(), Box::new(commit)))
+            }
+            Err(reason) => {
+                debug!("Skip submodule commit"; "event" => "commit_skipped", "repository" => &self.conf.name, "submodule" => path, "hash" => &commit.short_hash, "reason" => reason.to_string());
+                None
+            }
+        }
+    }
+}
+
+impl Iterator for History<'_> {
+    type Item = Result<Event, Box<dyn Error + Send + Sync>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(event) = self.pending.pop_front() {
+            return Some(Ok(event));
+        }
+
+        while let Some(read) = self.backend.read() {
+            match read {
+                Ok(Read::Revision(revision)) => return Some(Ok(self.classify(*revision))),
+                Ok(Read::Outside(Some(name))) => return Some(Ok(Event::Tag(name))),
+                Ok(Read::Outside(None)) => continue,
+                Err(err) => return Some(Err(err)),
+            }
+        }
+
+        self.backend.finish();
+        None
+    }
+}
+
+impl TryFrom<(&HashMap<String, String>, &conf::Repository)> for Repository {
+    type Error = Box<dyn Error + Send + Sync>;
+
+    fn try_from(tuple: (&HashMap<String, String>, &conf::Repository)) -> Result<Self, Self::Error> {
+        let (kinds, conf) = tuple;
+
+        Repository::parse(kinds, conf, &mut ())
+    }
+}
+
+impl Repository {
+    /// Parse the history of the repository, calling the given hooks on its
+    /// commits and tags
+    pub fn parse(
+        kinds: &HashMap<String, String>,
+        conf: &conf::Repository,
+        hooks: &mut dyn Hooks,
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let mut repository = Repository::from(conf.name.to_owned());
+        repository.group = conf.group.to_owned();
+        let repo;
+        let unranged;
+        let mut verifier = None;
+        let mut history = match conf.log {
+            Some(ref path) => History::from_log(log::read(path)?, kinds, conf)?,
+            None => {
+                repo = git::Repository::discover(&conf.path).map_err(|err| {
+                    format!(
+                        "could not retrieve git repository at '{:?}', {}",
+                        conf.path, err
+                    )
+                })?;
+
+                if conf.verify {
+                    verifier = Some((Verifier::from(&repo), &repo));
+                }
+
+                if is_shallow(&repo) && conf.unshallow {
+                    if let Err(err) = unshallow(&repo) {
+                        warn!("could not fetch the whole history"; "repository" => &conf.name, "error" => err.to_string());
+                    }
+                }
+
+                let shallow = is_shallow(&repo);
+                if shallow {
+                    warn!("Repository is a shallow clone, its history is incomplete"; "repository" => &conf.name);
+                    repository.notice = Some(String::from(SHALLOW));
+                }
+
+                match History::new(&repo, kinds, conf) {
+                    Ok(history) => history,
+                    // The start of the range may not have been fetched
+                    Err(err) if shallow && conf.range.is_some() => {
+                        warn!("could not walk the range of a shallow clone, walk the fetched history instead"; "repository" => &conf.name, "error" => err.to_string());
+                        unranged = conf::Repository {
+                            range: None,
+                            ..conf.to_owned()
+                        };
+
+                        History::new(&repo, kinds, &unranged)?
+                    }
+                    Err(err) => return Err(err),
+                }
+            }
+        };
+        let mut commits = BTreeMap::new();
+        for event in history.by_ref() {
+            match event? {
+                Event::Commit(mut section, mut commit) => {
+                    if hooks.on_commit_parsed(&repository.name, &mut section, &mut commit)
+                        == Verdict::Drop
+                    {
+                        debug!("Skip commit dropped by a hook"; "event" => "commit_skipped", "repository" => &repository.name, "hash" => &commit.short_hash, "reason" => "dropped by a hook");
+                        repository.skipped.push(Skipped {
+                            hash: commit.short_hash,
+                            message: commit.message,
+                            reason: Skip::Filtered(String::from("dropped by a hook")),
+                        });
+                        continue;
+                    }
+
+                    commits
+                        .entry(section)
+                        .or_insert_with(Vec::new)
+                        .push(*commit);
+                }
+                Event::Skipped(skipped) => {
+                    hooks.on_commit_skipped(&repository.name, &skipped);
+                    repository.skipped.push(skipped);
+                }
+                Event::Tag(name) => {
+                    let mut tag = Tag::from((name, std::mem::take(&mut commits)));
+                    if let Some((ref verifier, repo)) = verifier {
+                        tag.verification = Some(verifier.tag(repo, &tag.name));
+                    }
+
+                    hooks.on_tag_closed(&repository.name, &mut tag);
+                    debug!("Close tag"; "event" => "tag_closed", "repository" => &repository.name, "tag" => &tag.name, "entries" => tag.count());
+                    repository.tags.push(tag);
+                }
+            }
+        }
+
+        if !commits.is_empty() {
+            let mut tag = Tag::from((String::from(UNRELEASED), commits));
+            hooks.on_tag_closed(&repository.name, &mut tag);
+            debug!("Close tag"; "event" => "tag_closed", "repository" => &repository.name, "tag" => &tag.name, "entries" => tag.count());
+            repository.tags.push(tag);
+        }
+
+        if !history.violations().is_empty() {
+            return Err(Box::new(Violations(history.violations().to_vec())));
+        }
+
+        hooks.on_repository_walked(&repository.name);
+
+        if conf.first_contributions {
+            let firsts = history.firsts();
+            for commit in repository
+                .tags
+                .iter_mut()
+                .flat_map(|tag| tag.commits.values_mut().flatten())
+            {
+                commit.first_contribution = firsts.contains(&commit.hash);
+            }
+        }
+
+        if let Some(ref layout) = conf.link {
+            for tag in &mut repository.tags {
+                let name = match tag.name.as_str() {
+                    UNRELEASED => UNRELEASED_REF,
+                    name => name,
+                };
+
+                for commit in tag.commits.values_mut().flatten() {
+                    commit.format_link(layout, &conf.name, name)?;
+                }
+            }
+        }
+
+        if conf.deduplicate {
+            deduplicate(&mut repository.tags);
+        }
+
+        if conf.dependencies.collapse {
+            let patterns = patterns(&conf.dependencies.patterns)
+                .map_err(|err| format!("could not compile dependencies.patterns, {}", err))?;
+            let bots = patterns_of_bots(conf)?;
+
+            let is_dependency = |commit: &Commit| {
+                patterns.iter().any(|re| re.is_match(&commit.message))
+                    || (conf.dependencies.bots && bots.iter().any(|re| re.is_match(&commit.author)))
+            };
+
+            for tag in &mut repository.tags {
+                tag.collapse(
+                    &conf.dependencies.title,
+                    conf.dependencies.minimum,
+                    is_dependency,
+                );
+            }
+        }
+
+        repository.tags.reverse();
+
+        Ok(repository)
+    }
 }
 
-impl TryFrom<(&conf::Repository, &git::Commit<'_>)> for Commit {
-    type Error = Box<dyn Error + Send + Sync>;
+/// Keep only the first entry of the commits holding the same change, which
+/// are the cherry-picks of a commit across the releases or the commits sharing
+/// the same summary within a release, the hashes of the others are noted on the
+/// kept one
+pub fn deduplicate(tags: &mut [Tag]) {
+    let mut origins: HashMap<String, (usize, String, usize)> = HashMap::new();
+    let mut duplicates = vec![];
 
-    fn try_from(tuple: (&conf::Repository, &git::Commit<'_>)) -> Result<Self, Self::Error> {
-        let (conf, commit) = tuple;
-        let author = match commit.author().name() {
-            Some(author) => String::from(author),
-            None => match commit.committer().name() {
-                Some(committer) => String::from(committer),
-                None => return Err("No such author or commiter".into()),
-            },
-        };
+    // Tags are walked from the oldest to the newest one
+    for (idx, tag) in tags.iter().enumerate() {
+        // A summary could be reused by a later release for another change
+        let mut summaries: HashMap<String, (usize, String, usize)> = HashMap::new();
 
-        let message = match commit.summary() {
-            Some(summary) => String::from(summary),
-            None => match commit.message() {
-                Some(message) => String::from(message),
-                None => return Err("No such message or summary".into()),
-            },
-        };
+        for (section, commits) in &tag.commits {
+            for (position, commit) in commits.iter().enumerate() {
+                let origin = commit
+                    .origin
+                    .to_owned()
+                    .unwrap_or_else(|| commit.hash.to_owned());
 
-        let mut hash = commit.id().to_string();
-        let date = DateTime::<Utc>::from_utc(
-            NaiveDateTime::from_timestamp(commit.time().seconds(), 0),
-            Utc,
-        )
-        .date()
-        .format("%F")
-        .to_string();
+                let first = origins
+                    .get(&origin)
+                    .or_else(|| summaries.get(&commit.message));
 
-        let mut link = None;
-        if let Some(ref layout) = conf.link {
-            let mut vars = HashMap::new();
+                match first {
+                    Some(first) => duplicates.push((first.to_owned(), commit.to_owned())),
+                    None => {
+                        let location = (idx, section.to_owned(), position);
+                        origins.insert(origin, location.to_owned());
+                        summaries.insert(commit.message.to_owned(), location);
+                    }
+                }
+            }
+        }
+    }
 
-            vars.insert(String::from("hash"), hash.to_owned());
+    let removed: Vec<_> = duplicates
+        .iter()
+        .map(|(_, duplicate)| duplicate.hash.to_owned())
+        .collect();
 
-            link = Some(
-                strfmt(layout, &vars)
-                    .map_err(|err| format!("could not format commit link, {}", err))?,
-            );
+    for ((idx, section, position), duplicate) in duplicates {
+        if let Some(commit) = tags[idx]
+            .commits
+            .get_mut(&section)
+            .and_then(|commits| commits.get_mut(position))
+        {
+            commit.duplicates.push(duplicate.short_hash);
         }
+    }
 
-        hash.truncate(7);
+    for tag in tags.iter_mut() {
+        for commits in tag.commits.values_mut() {
+            commits.retain(|commit| !removed.contains(&commit.hash));
+        }
 
-        Ok(Self {
-            hash,
-            message,
-            author,
-            date,
-            link,
-        })
+        tag.commits.retain(|_, commits| !commits.is_empty());
     }
 }
 
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+pub struct Changelog {
+    pub repositories: Vec<Repository>,
+    #[serde(default)]
+    pub language: Language,
+    #[serde(default)]
+    pub dates: Dates,
+    #[serde(default)]
+    pub layout: Layout,
+    #[serde(default)]
+    pub overview: bool,
+    /// Name of the repository described by the badge, if selected
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub badge: Option<String>,
+}
+
+/// Latest release of a repository along with its number of entries by kind
 #[derive(Clone, Debug)]
-pub struct Tag {
-    pub name: String,
-    pub commits: HashMap<String, Vec<Commit>>,
+pub struct Row {
+    pub repository: String,
+    pub tag: String,
+    pub counts: Vec<usize>,
 }
 
-impl From<(String, HashMap<String, Vec<Commit>>)> for Tag {
-    fn from(tuple: (String, HashMap<String, Vec<Commit>>)) -> Self {
-        let (name, commits) = tuple;
+/// Summary of the latest release of each repository, the counts of each row
+/// follow the order of the kinds
+#[derive(Clone, Debug)]
+pub struct Overview {
+    pub kinds: Vec<String>,
+    pub rows: Vec<Row>,
+}
+
+impl From<&[Repository]> for Overview {
+    fn from(repositories: &[Repository]) -> Self {
+        let latests: Vec<_> = repositories
+            .iter()
+            .map(|repository| {
+                let tag = repository.tags.iter().find(|tag| tag.name != UNRELEASED);
+                (repository, tag)
+            })
+            .collect();
+
+        let mut kinds: Vec<String> = latests
+            .iter()
+            .filter_map(|(_, tag)| *tag)
+            .flat_map(|tag| tag.commits.keys().cloned())
+            .collect();
 
-        Self { name, commits }
+        kinds.sort();
+        kinds.dedup();
+
+        let rows = latests
+            .into_iter()
+            .map(|(repository, tag)| Row {
+                repository: repository.name.to_owned(),
+                tag: tag.map(|tag| tag.name.to_owned()).unwrap_or_default(),
+                counts: kinds
+                    .iter()
+                    .map(|kind| {
+                        tag.and_then(|tag| tag.commits.get(kind))
+                            .map(Vec::len)
+                            .unwrap_or_default()
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        Self { kinds, rows }
     }
 }
 
-#[derive(Clone, Debug)]
-pub struct Repository {
-    pub name: String,
-    pub tags: Vec<Tag>,
+/// Entries of a kind, grouped by repository along with the name of their tag
+pub type Group = (String, Vec<(String, Vec<(String, Commit)>)>);
+
+/// Returns the entries grouped by kind (sorted by title), then by repository
+/// (in the order of the configuration), the releases being the most recent first
+pub fn by_kind(repositories: &[Repository]) -> Vec<Group> {
+    let mut kinds: Vec<&String> = repositories
+        .iter()
+        .flat_map(|repository| &repository.tags)
+        .flat_map(|tag| tag.commits.keys())
+        .collect();
+
+    kinds.sort();
+    kinds.dedup();
+
+    kinds
+        .into_iter()
+        .map(|kind| {
+            let repositories = repositories
+                .iter()
+                .map(|repository| {
+                    let entries: Vec<_> = repository
+                        .tags
+                        .iter()
+                        .flat_map(|tag| {
+                            tag.commits
+                                .get(kind)
+                                .into_iter()
+                                .flatten()
+                                .map(move |commit| (tag.name.to_owned(), commit.to_owned()))
+                        })
+                        .collect();
+
+                    (repository.name.to_owned(), entries)
+                })
+                .filter(|(_, entries)| !entries.is_empty())
+                .collect();
+
+            (kind.to_owned(), repositories)
+        })
+        .collect()
 }
 
-impl From<String> for Repository {
-    fn from(name: String) -> Self {
-        Repository {
-            name,
-            tags: Default::default(),
+impl From<&Configuration> for Changelog {
+    fn from(conf: &Configuration) -> Self {
+        Self {
+            repositories: vec![],
+            language: conf.language,
+            dates: conf.dates.to_owned(),
+            layout: conf.layout,
+            overview: conf.overview,
+            badge: conf.badge.to_owned(),
         }
     }
 }
 
-impl TryFrom<(&HashMap<String, String>, &conf::Repository)> for Repository {
+impl Changelog {
+    /// Returns a changelog holding the given repositories, rendered the same
+    /// way as this one
+    pub fn with(&self, repositories: Vec<Repository>) -> Self {
+        Self {
+            repositories,
+            language: self.language,
+            dates: self.dates.to_owned(),
+            layout: self.layout,
+            overview: self.overview,
+            badge: self.badge.to_owned(),
+        }
+    }
+}
+
+impl TryFrom<Rc<Configuration>> for Changelog {
     type Error = Box<dyn Error + Send + Sync>;
 
-    fn try_from(tuple: (&HashMap<String, String>, &conf::Repository)) -> Result<Self, Self::Error> {
-        let (kinds, conf) = tuple;
-        let mut repository = Repository::from(conf.name.to_owned());
-        let repo = git::Repository::discover(&conf.path).map_err(|err| {
-            format!(
-                "could not retrieve git repository at '{:?}', {}",
-                conf.path, err
-            )
-        })?;
-
-        // We should build a map(commit-id -> tag) before starting walking over the git commit history.
-        //
-        // The full explanation is here:
-        // https://stackoverflow.com/questions/36528576/get-annotated-tags-from-revwalk-commit/36555358#36555358
-        let mut tags = HashMap::new();
-        for tag in repo
-            .tag_names(None)
-            .map_err(|err| format!("could not retrieve git tags, {}", err))?
-            .iter()
-        {
-            let object = repo
-                .revparse_single(tag.expect("tag to be written in utf-8 compliant format"))
-                .map_err(|err| format!("could not retrieve object for tag, {}", err))?;
-
-            let tag = match object.to_owned().into_tag() {
-                Ok(tag) => tag,
-                Err(_) => {
-                    let mut hash = object.id().to_string();
-                    hash.truncate(7);
-                    warn!("could not cast object into tag"; "hash" => hash);
-                    continue;
-                }
-            };
+    fn try_from(conf: Rc<Configuration>) -> Result<Self, Self::Error> {
+        Changelog::generate(conf, &mut ())
+    }
+}
+
+impl Changelog {
+    /// Generate the changelog of the configured repositories, calling the
+    /// given hooks along the way
+    pub fn generate(
+        conf: Rc<Configuration>,
+        hooks: &mut dyn Hooks,
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let mut changelog = Changelog::from(conf.as_ref());
+
+        // Plugins and scripts run before the hooks given by the caller
+        let mut plugins = Plugins::load(&conf.plugins, conf.plugin_fuel)?;
+        let mut scripts = Scripts::load(&conf.scripts, &conf.kinds)?;
+        let mut hooks = Chain(vec![&mut plugins, &mut scripts, hooks]);
 
-            tags.insert(tag.target_id().to_string(), tag);
+        dates::offset(&conf.dates.timezone, &Utc::now().into())?;
+        for format in conf.dates.formats.values() {
+            dates::check(format)?;
         }
 
-        let mut revwalk = repo
-            .revwalk()
-            .map_err(|err| format!("could create a walker on git history, {}", err))?;
+        // Emails are compared regardless of their case
+        let authors: HashMap<_, _> = conf
+            .authors
+            .iter()
+            .map(|(email, name)| (email.to_lowercase(), name.to_owned()))
+            .collect();
 
-        match &conf.range {
-            Some(range) => {
-                revwalk
-                    .push_range(range)
-                    .map_err(|err| format!("could not parse commit range, {}", err))?;
-            }
-            None => {
-                revwalk
-                    .push_head()
-                    .map_err(|err| format!("could not push HEAD commit, {}", err))?;
+        let rewrites = normalize::rewrites(&conf.rewrites)
+            .map_err(|err| format!("could not compile rewrites, {}", err))?;
+
+        let mut repositories = vec![];
+        for repository in &conf.repositories {
+            if repository.monorepo {
+                repositories.extend(packages(repository).map_err(|err| RepositoryError {
+                    repository: repository.name.to_owned(),
+                    error: format!("could not split it into packages, {}", err).into(),
+                })?);
+            } else {
+                repositories.push(repository.to_owned());
             }
         }
 
-        revwalk
-            .set_sorting(git::Sort::TIME | git::Sort::REVERSE)
-            .map_err(|err| format!("failed to sort git commit history, {}", err))?;
+        // The strict mode of the configuration applies unless the repository sets its own
+        for repository in &mut repositories {
+            repository.strict = repository.strict.or(Some(conf.strict));
+        }
 
-        let mut commits = HashMap::new();
-        for oid in revwalk {
-            let oid =
-                oid.map_err(|err| format!("could not retrieve object identifier, {}", err))?;
+        let started = Instant::now();
+        for repository in &repositories {
+            let processed = Instant::now();
+            let layout = repository.link.to_owned();
+            let periods = repository.periods;
+            let mut repository = match Repository::parse(&conf.kinds, repository, &mut hooks) {
+                Ok(parsed) => parsed,
+                Err(err) if err.is::<Unborn>() && repository.empty != EmptyPolicy::Fail => {
+                    warn!("Repository has no commits yet"; "repository" => &repository.name, "policy" => format!("{:?}", repository.empty).to_lowercase(), "error" => err.to_string());
+                    if repository.empty == EmptyPolicy::Render {
+                        let mut empty = Repository::from(repository.name.to_owned());
+                        empty.group = repository.group.to_owned();
+                        changelog.repositories.push(empty);
+                    }
 
-            let commit = repo
-                .find_commit(oid)
-                .map_err(|err| format!("could not retrieve commit '{}', {}", oid, err))?;
+                    continue;
+                }
+                Err(err) => {
+                    return Err(Box::new(RepositoryError {
+                        repository: repository.name.to_owned(),
+                        error: err,
+                    }))
+                }
+            };
 
-            let commit = Commit::try_from((conf, &commit))
-                .map_err(|err| format!("could not parse commit '{}', {}", oid, err))?;
+            for tag in &mut repository.tags {
+                let name = match tag.name.as_str() {
+                    UNRELEASED => UNRELEASED_REF,
+                    name => name,
+                };
 
-            let Commit { hash, message, .. } = commit.to_owned();
-            if message.starts_with("Merge pull request") || message.starts_with("Merge branch") {
-                info!("Skip merge commit"; "hash" => &hash);
-                continue;
-            }
+                for commit in tag.commits.values_mut().flatten() {
+                    let author = commit.author.to_owned();
+                    if let Some(name) = authors.get(&commit.email.to_lowercase()) {
+                        commit.author = name.to_owned();
+                    }
 
-            let re = Regex::new(PATTERN).expect("pattern to be a valid regular expression");
-            if !re.is_match(&message) {
-                error!("Could not parse the message"; "hash" => hash, "message" => message);
-                continue;
-            }
+                    commit.anonymize(conf.anonymize);
+                    commit.message = normalize::rewrite(&commit.message, &rewrites);
+                    commit.message = normalize::subject(&commit.message, &conf.normalize);
+                    commit.date = dates::time(commit, &conf.dates).format("%F").to_string();
 
-            let captures = re
-                .captures(&message)
-                .expect("captures to exists in PATTERN regex");
-            let kind = String::from(
-                captures
-                    .name("kind")
-                    .expect("To have 'kind' group in the PATTERN regex")
-                    .as_str(),
-            );
-
-            let scope = captures
-                .name("scope")
-                .map(|scope| String::from(scope.as_str()));
-
-            if !kinds.contains_key(&kind) {
-                warn!("Kind is not contained in provided kinds"; "hash" => &hash, "kind" => kind);
-                warn!("Skip commit"; "hash" => &hash);
-                continue;
+                    // Links may hold the author, they should use the displayed one
+                    if let (Some(layout), true) = (&layout, commit.author != author) {
+                        commit.format_link(layout, &repository.name, name)?;
+                    }
+                }
             }
 
-            if let Some(ref scope) = scope {
-                let sub_scopes = scope.as_str().split(',');
-                if let Some(ref scopes) = conf.scopes {
-                    for sub_scope in sub_scopes {
-                        if !scopes.contains(&String::from(sub_scope)) {
-                            warn!("Scope is not contained in provided scopes";  "hash" => &hash, "scope" => scope);
-                            continue;
-                        }
-                    }
+            // Repositories without tags are split into periods instead
+            if let Some(period) = periods {
+                if repository.tags.iter().all(|tag| tag.name == UNRELEASED) {
+                    repository.tags = std::mem::take(&mut repository.tags)
+                        .into_iter()
+                        .flat_map(|tag| tag.split(period, &conf.dates))
+                        .collect();
                 }
             }
 
-            (&mut commits)
-                .entry(String::from(
-                    kinds
-                        .get(&kind)
-                        .expect("To have 'kind' defined in repository's kinds")
-                        .as_str(),
-                ))
-                .or_insert_with(Vec::new)
-                .push(commit);
-
-            if let Some(tag) = tags.get(&oid.to_string()) {
-                repository.tags.push(Tag::from((
-                    String::from(tag.name().expect("tag name to be utf-8 compliant")),
-                    commits,
-                )));
+            if conf.other_changes.threshold > 0 {
+                for tag in &mut repository.tags {
+                    tag.merge_small(&conf.other_changes.title, conf.other_changes.threshold);
+                }
+            }
 
-                commits = HashMap::new();
+            for tag in &mut repository.tags {
+                tag.sort(conf.sort, &conf.dates);
             }
-        }
 
-        if !commits.is_empty() {
-            repository
-                .tags
-                .push(Tag::from((String::from("Technical preview"), commits)));
+            info!("Process repository"; "event" => "repository_processed", "repository" => &repository.name, "tags" => repository.tags.len(), "entries" => repository.tags.iter().map(Tag::count).sum::<usize>(), "skipped" => repository.skipped.len(), "duration_ms" => processed.elapsed().as_millis() as u64);
+            changelog.repositories.push(repository);
         }
 
-        repository.tags.reverse();
+        arrange(&mut changelog.repositories, &conf.order);
+        hooks.before_render(&mut changelog);
+        info!("Generate changelog"; "event" => "changelog_generated", "repositories" => changelog.repositories.len(), "duration_ms" => started.elapsed().as_millis() as u64);
 
-        Ok(repository)
+        Ok(changelog)
     }
 }
 
-#[derive(Default, Clone, Debug)]
-pub struct Changelog {
-    pub repositories: Vec<Repository>,
+/// Sort the repositories following the given order of their names, the others
+/// keep their order after them. Repositories of the same group are then moved
+/// next to the first one of the group.
+pub fn arrange(repositories: &mut [Repository], order: &[String]) {
+    repositories.sort_by_key(|repository| {
+        order
+            .iter()
+            .position(|name| name == &repository.name)
+            .unwrap_or(order.len())
+    });
+
+    let groups: Vec<_> = repositories
+        .iter()
+        .map(|repository| repository.group.to_owned())
+        .collect();
+
+    repositories.sort_by_key(|repository| {
+        groups
+            .iter()
+            .position(|group| group == &repository.group)
+            .unwrap_or_default()
+    });
 }
 
-impl TryFrom<Rc<Configuration>> for Changelog {
-    type Error = Box<dyn Error + Send + Sync>;
+/// Returns the group of the repository if it is the first one of its group
+/// among the rendered repositories
+fn heading(repositories: &[Repository], repository: &Repository) -> Option<String> {
+    let group = repository.group.as_deref()?;
+    let idx = repositories
+        .iter()
+        .position(|r| std::ptr::eq(r, repository))?;
 
-    fn try_from(conf: Rc<Configuration>) -> Result<Self, Self::Error> {
-        let mut changelog = Changelog::default();
+    match idx.checked_sub(1).and_then(|idx| repositories.get(idx)) {
+        Some(previous) if previous.group.as_deref() == Some(group) => None,
+        _ => Some(group.to_string()),
+    }
+}
 
-        for repository in &conf.repositories {
-            changelog
-                .repositories
-                .push(
-                    Repository::try_from((&conf.kinds, repository)).map_err(|err| {
-                        format!(
-                            "could not process repository '{}', {}",
-                            repository.name, err
-                        )
-                    })?,
-                );
-        }
+/// Returns the dates of the first and last commits of the tag formatted by
+/// the given function, if enabled
+fn period<F: Fn(&Commit) -> String>(tag: &Tag, dates: &Dates, format: F) -> String {
+    let (first, last) = match tag.period(dates) {
+        Some(period) if dates.ranges => period,
+        _ => return String::new(),
+    };
 
-        Ok(changelog)
+    let (first, last) = (format(first), format(last));
+    if first == last {
+        return format!(" ({})", first);
     }
+
+    format!(" ({} → {})", first, last)
+}
+
+/// Returns the date of the commit formatted using the format configured for
+/// the output or the language of the changelog
+fn date(commit: &Commit, dates: &Dates, language: Language, output: &str) -> String {
+    dates::format(commit, dates, output).unwrap_or_else(|| i18n::date(language, &commit.date))
 }
 
 #[derive(Template, Default, Clone, Debug)]
 #[template(path = "changelog.html")]
 pub struct HTMLChangelog {
     pub repositories: Vec<Repository>,
+    pub language: Language,
+    pub dates: Dates,
+    pub layout: Layout,
+    pub overview: bool,
 }
 
 impl From<Changelog> for HTMLChangelog {
     fn from(changelog: Changelog) -> Self {
         Self {
             repositories: changelog.repositories,
+            language: changelog.language,
+            dates: changelog.dates,
+            layout: changelog.layout,
+            overview: changelog.overview,
+        }
+    }
+}
+
+impl HTMLChangelog {
+    /// Returns the group of the repository if it is the first one of its group
+    fn heading(&self, repository: &Repository) -> Option<String> {
+        heading(&self.repositories, repository)
+    }
+
+    /// Returns the summary of the latest release of each repository, if enabled
+    fn overview(&self) -> Option<Overview> {
+        if !self.overview {
+            return None;
         }
+
+        Some(Overview::from(self.repositories.as_slice()))
+    }
+
+    /// Returns true if the entries are grouped by kind first
+    fn is_grouped_by_kind(&self) -> bool {
+        self.layout == Layout::Kind
+    }
+
+    /// Returns the entries grouped by kind, then by repository
+    fn by_kind(&self) -> Vec<Group> {
+        by_kind(&self.repositories)
+    }
+
+    /// Returns the dates of the first and last commits of the tag, if enabled
+    fn period(&self, tag: &Tag) -> String {
+        period(tag, &self.dates, |commit| self.date(commit))
+    }
+
+    /// Returns the text translated into the language of the changelog
+    fn t(&self, text: &str) -> String {
+        i18n::translate(self.language, text)
+    }
+
+    /// Returns the date formatted using the format configured for html or the
+    /// language of the changelog
+    fn date(&self, commit: &Commit) -> String {
+        date(commit, &self.dates, self.language, "html")
     }
 }
 
@@ -304,12 +2108,428 @@ impl From<Changelog> for HTMLChangelog {
 #[template(path = "changelog.md", escape = "none")]
 pub struct MarkdownChangelog {
     pub repositories: Vec<Repository>,
+    pub language: Language,
+    pub dates: Dates,
+    pub layout: Layout,
 }
 
 impl From<Changelog> for MarkdownChangelog {
     fn from(changelog: Changelog) -> Self {
         Self {
             repositories: changelog.repositories,
+            language: changelog.language,
+            dates: changelog.dates,
+            layout: changelog.layout,
+        }
+    }
+}
+
+impl MarkdownChangelog {
+    /// Returns the group of the repository if it is the first one of its group
+    fn heading(&self, repository: &Repository) -> Option<String> {
+        heading(&self.repositories, repository)
+    }
+
+    /// Returns true if the entries are grouped by kind first
+    fn is_grouped_by_kind(&self) -> bool {
+        self.layout == Layout::Kind
+    }
+
+    /// Returns the entries grouped by kind, then by repository
+    fn by_kind(&self) -> Vec<Group> {
+        by_kind(&self.repositories)
+    }
+
+    /// Returns the dates of the first and last commits of the tag, if enabled
+    fn period(&self, tag: &Tag) -> String {
+        period(tag, &self.dates, |commit| self.date(commit))
+    }
+
+    /// Returns the text translated into the language of the changelog
+    fn t(&self, text: &str) -> String {
+        i18n::translate(self.language, text)
+    }
+
+    /// Returns the date formatted using the format configured for markdown or
+    /// the language of the changelog
+    fn date(&self, commit: &Commit) -> String {
+        date(commit, &self.dates, self.language, "markdown")
+    }
+}
+
+/// Single repository of the markdown changelog, the changelog could then be
+/// rendered one repository at a time
+#[derive(Template, Clone, Debug)]
+#[template(path = "repository.md", escape = "none")]
+pub struct MarkdownRepository<'a> {
+    pub repository: &'a Repository,
+    /// Group heading to render above the repository, if any
+    pub group: Option<String>,
+    pub language: Language,
+    pub dates: &'a Dates,
+}
+
+impl MarkdownRepository<'_> {
+    /// Returns the group heading of the repository
+    fn heading(&self, _repository: &Repository) -> Option<String> {
+        self.group.to_owned()
+    }
+
+    /// Returns the dates of the first and last commits of the tag, if enabled
+    fn period(&self, tag: &Tag) -> String {
+        period(tag, self.dates, |commit| self.date(commit))
+    }
+
+    /// Returns the text translated into the language of the changelog
+    fn t(&self, text: &str) -> String {
+        i18n::translate(self.language, text)
+    }
+
+    /// Returns the date formatted using the format configured for markdown or
+    /// the language of the changelog
+    fn date(&self, commit: &Commit) -> String {
+        date(commit, self.dates, self.language, "markdown")
+    }
+}
+
+/// Returns a commit of the given kind authored on the given day, shared by
+/// the tests of the modules working on the parsed releases
+#[cfg(test)]
+pub fn commit(hash: &str, kind: &str, message: &str, date: &str) -> Commit {
+    let timestamp = format!("{}T12:00:00+00:00", date);
+
+    serde_json::from_value(serde_json::json!({
+        "hash": hash,
+        "short_hash": hash.chars().take(7).collect::<String>(),
+        "message": message,
+        "author": "Jane Doe",
+        "email": "jane@example.com",
+        "date": date,
+        "authored": timestamp,
+        "committed": timestamp,
+        "link": null,
+        "kind": kind,
+        "scope": null,
+        "breaking": false,
+    }))
+    .expect("commit to be valid")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds() -> HashMap<String, String> {
+        ["feat", "fix", "chore"]
+            .iter()
+            .map(|kind| (kind.to_string(), kind.to_string()))
+            .collect()
+    }
+
+    /// Returns the configuration of a repository with the given settings
+    fn repository(settings: serde_json::Value) -> conf::Repository {
+        let mut value = serde_json::json!({ "name": "api", "path": "." });
+        value
+            .as_object_mut()
+            .expect("repository to be an object")
+            .extend(settings.as_object().cloned().unwrap_or_default());
+
+        serde_json::from_value(value).expect("repository to be valid")
+    }
+
+    /// Returns the reason to exclude a commit of Jane Doe, if any
+    fn check(filter: &Filter<'_>, hash: &str, message: &str) -> Result<(), Skip> {
+        filter.check_parts(hash, message, "Jane Doe", "jane@example.com")
+    }
+
+    fn variant(name: &str, pattern: &str) -> conf::Variant {
+        conf::Variant {
+            name: name.to_owned(),
+            pattern: pattern.to_owned(),
         }
     }
+
+    #[test]
+    fn classify_uses_the_first_variant_matching_the_message() {
+        let kinds = kinds();
+        let grammar = [
+            variant("jira", r"^[A-Z]+-\d+ (?P<kind>\w+): "),
+            variant("bracket", r"\[(?P<kind>\w+)\]"),
+        ];
+
+        let classifier = Classifier::new(&kinds, None, &grammar).unwrap();
+
+        // Both variants and the conventional commits match, the first one wins
+        let classification = classifier.classify("API-12 fix: [feat] timeout").unwrap();
+        assert_eq!(classification.kind, "fix");
+
+        let classification = classifier.classify("[chore] feat: timeout").unwrap();
+        assert_eq!(classification.kind, "chore");
+
+        let classification = classifier.classify("feat(api)!: timeout").unwrap();
+        assert_eq!(classification.kind, "feat");
+        assert_eq!(classification.scope.as_deref(), Some("api"));
+        assert!(classification.breaking);
+    }
+
+    #[test]
+    fn classify_skips_merges_before_the_variants() {
+        let kinds = kinds();
+        let grammar = [variant("any", r"(?P<kind>\w+)")];
+
+        let classifier = Classifier::new(&kinds, None, &grammar).unwrap();
+
+        assert_eq!(classifier.classify("Merge branch 'fix'"), Err(Skip::Merge));
+    }
+
+    #[test]
+    fn classify_skips_a_match_without_kind() {
+        let kinds = kinds();
+        let grammar = [variant("optional", r"^release(: (?P<kind>\w+))?")];
+
+        let classifier = Classifier::new(&kinds, None, &grammar).unwrap();
+
+        assert_eq!(classifier.classify("release"), Err(Skip::Unparseable));
+        assert_eq!(classifier.classify("release: fix").unwrap().kind, "fix");
+    }
+
+    /// Returns a release holding the commits, all of the fix kind
+    fn release(name: &str, commits: Vec<Commit>) -> Tag {
+        Tag::from((
+            name.to_owned(),
+            BTreeMap::from([(String::from("fix"), commits)]),
+        ))
+    }
+
+    fn hashes(tag: &Tag) -> Vec<&str> {
+        tag.commits
+            .values()
+            .flatten()
+            .map(|commit| commit.hash.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn deduplicate_keeps_the_same_summary_in_two_releases() {
+        let mut tags = vec![
+            release(
+                "v1.0.0",
+                vec![commit("a000001", "fix", "fix: bump deps", "2024-01-01")],
+            ),
+            release(
+                "v1.1.0",
+                vec![commit("b000001", "fix", "fix: bump deps", "2024-02-01")],
+            ),
+        ];
+
+        deduplicate(&mut tags);
+
+        assert_eq!(hashes(&tags[0]), ["a000001"]);
+        assert_eq!(hashes(&tags[1]), ["b000001"]);
+        assert!(tags[0].commits["fix"][0].duplicates.is_empty());
+    }
+
+    #[test]
+    fn deduplicate_merges_the_same_summary_within_a_release() {
+        let mut tags = vec![release(
+            "v1.0.0",
+            vec![
+                commit("a000001", "fix", "fix: timeout", "2024-01-01"),
+                commit("a000002", "fix", "fix: timeout", "2024-01-02"),
+            ],
+        )];
+
+        deduplicate(&mut tags);
+
+        assert_eq!(hashes(&tags[0]), ["a000001"]);
+        assert_eq!(tags[0].commits["fix"][0].duplicates, ["a000002"]);
+    }
+
+    #[test]
+    fn deduplicate_merges_the_cherry_picks_across_releases() {
+        let mut pick = commit("b000001", "fix", "fix: timeout on backport", "2024-02-01");
+        pick.origin = Some(String::from("a000001"));
+
+        let mut tags = vec![
+            release(
+                "v1.0.0",
+                vec![commit("a000001", "fix", "fix: timeout", "2024-01-01")],
+            ),
+            release("v1.1.0", vec![pick]),
+        ];
+
+        deduplicate(&mut tags);
+
+        assert_eq!(hashes(&tags[0]), ["a000001"]);
+        assert!(tags[1].commits.is_empty());
+        assert_eq!(tags[0].commits["fix"][0].duplicates, ["b000001"]);
+    }
+
+    #[test]
+    fn changelog_round_trips_through_json_with_its_settings() {
+        let mut repository = Repository::from(String::from("api"));
+        repository.tags.push(release(
+            "v1.0.0",
+            vec![commit("a000001", "fix", "fix: timeout", "2024-01-01")],
+        ));
+
+        let changelog = Changelog {
+            repositories: vec![repository],
+            language: Language::Fr,
+            dates: Dates {
+                ranges: true,
+                formats: HashMap::from([(String::from("markdown"), String::from("%d/%m/%Y"))]),
+                ..Dates::default()
+            },
+            layout: Layout::Kind,
+            overview: true,
+            badge: Some(String::from("api")),
+        };
+
+        let json = serde_json::to_string(&changelog).unwrap();
+        let parsed: Changelog = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(parsed.language, Language::Fr);
+        assert!(parsed.dates.ranges);
+        assert_eq!(parsed.dates.formats["markdown"], "%d/%m/%Y");
+        assert_eq!(parsed.layout, Layout::Kind);
+        assert!(parsed.overview);
+        assert_eq!(parsed.badge.as_deref(), Some("api"));
+        assert_eq!(serde_json::to_string(&parsed).unwrap(), json);
+        assert_eq!(
+            MarkdownChangelog::from(parsed).to_string(),
+            MarkdownChangelog::from(changelog).to_string()
+        );
+    }
+
+    #[test]
+    fn changelog_without_settings_uses_the_defaults() {
+        let parsed: Changelog = serde_json::from_str(r#"{"repositories": []}"#).unwrap();
+
+        assert_eq!(parsed.language, Language::En);
+        assert_eq!(parsed.layout, Layout::Repository);
+        assert!(!parsed.overview);
+        assert!(parsed.badge.is_none());
+    }
+
+    #[test]
+    fn filter_skips_the_commits_starting_with_a_listed_hash() {
+        let conf = repository(serde_json::json!({ "skip_commits": ["abc1234", ""] }));
+        let filter = Filter::new(&conf).unwrap();
+
+        assert_eq!(
+            check(&filter, "abc1234def5678", "fix: reformat"),
+            Err(Skip::Filtered(String::from("commit is in the skip list")))
+        );
+        assert_eq!(check(&filter, "0abc1234def56", "fix: timeout"), Ok(()));
+    }
+
+    #[test]
+    fn filter_skips_the_messages_holding_a_marker() {
+        let conf = repository(serde_json::json!({}));
+        let filter = Filter::new(&conf).unwrap();
+
+        assert_eq!(
+            check(&filter, "a000001", "chore: bump\n\n[Skip Changelog]"),
+            Err(Skip::Filtered(String::from(
+                "message contains '[skip changelog]'"
+            )))
+        );
+        assert!(check(&filter, "a000001", "fix: typo [changelog skip]").is_err());
+        assert_eq!(check(&filter, "a000001", "fix: skip changelog"), Ok(()));
+
+        let conf = repository(serde_json::json!({ "skip_markers": ["[no-log]"] }));
+        let filter = Filter::new(&conf).unwrap();
+
+        assert!(check(&filter, "a000001", "fix: typo [no-log]").is_err());
+        assert_eq!(
+            check(&filter, "a000001", "fix: typo [skip changelog]"),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn filter_excludes_the_authors_before_including_them() {
+        let conf = repository(serde_json::json!({
+            "authors_include": ["@example\\.com$"],
+            "authors_exclude": ["^Jane"],
+        }));
+        let filter = Filter::new(&conf).unwrap();
+
+        // The author is both included by email and excluded by name
+        assert_eq!(
+            filter.check_parts("a000001", "fix: timeout", "Jane Doe", "jane@example.com"),
+            Err(Skip::Filtered(String::from("author is excluded")))
+        );
+        assert_eq!(
+            filter.check_parts("a000001", "fix: timeout", "John Doe", "john@example.com"),
+            Ok(())
+        );
+        assert_eq!(
+            filter.check_parts("a000001", "fix: timeout", "John Doe", "john@example.org"),
+            Err(Skip::Filtered(String::from("author is not included")))
+        );
+    }
+
+    #[test]
+    fn filter_checks_the_message_against_the_exclude_then_include_patterns() {
+        let conf = repository(serde_json::json!({
+            "include_pattern": "^(feat|fix)",
+            "exclude_pattern": "^chore\\(release\\)|^fix\\(ci\\)",
+        }));
+        let filter = Filter::new(&conf).unwrap();
+
+        assert_eq!(filter.check_message("feat: add the api"), Ok(()));
+        assert_eq!(
+            filter.check_message("fix(ci): retry"),
+            Err(Skip::Filtered(String::from(
+                "message matches exclude_pattern"
+            )))
+        );
+        assert_eq!(
+            filter.check_message("docs: typo"),
+            Err(Skip::Filtered(String::from(
+                "message does not match include_pattern"
+            )))
+        );
+    }
+
+    #[test]
+    fn collapse_gathers_the_dependency_updates_of_a_release() {
+        let is_dependency = |commit: &Commit| commit.message.contains("bump");
+        let mut tag = Tag::from((
+            String::from("v1.0.0"),
+            BTreeMap::from([
+                (
+                    String::from("fix"),
+                    vec![
+                        commit("a000001", "fix", "fix: bump serde", "2024-01-01"),
+                        commit("a000002", "fix", "fix: timeout", "2024-01-02"),
+                    ],
+                ),
+                (
+                    String::from("chore"),
+                    vec![commit(
+                        "a000003",
+                        "chore",
+                        "chore: bump regex",
+                        "2024-01-03",
+                    )],
+                ),
+            ]),
+        ));
+
+        // Below the minimum, the updates are left in their sections
+        let mut untouched = tag.clone();
+        untouched.collapse("Dependency updates", 3, is_dependency);
+        assert_eq!(untouched.collapsed, None);
+        assert_eq!(untouched.commits.len(), 2);
+
+        tag.collapse("Dependency updates", 2, is_dependency);
+        assert_eq!(tag.collapsed.as_deref(), Some("Dependency updates"));
+        assert_eq!(hashes(&tag), ["a000003", "a000001", "a000002"]);
+        assert_eq!(tag.commits["Dependency updates"].len(), 2);
+        assert_eq!(tag.commits["fix"].len(), 1);
+        assert!(!tag.commits.contains_key("chore"));
+    }
 }
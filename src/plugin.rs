@@ -0,0 +1,339 @@
+//! # Plugin module
+//!
+//! The plugin module runs the WebAssembly plugins declared in the
+//! configuration, so that teams could classify commits, rewrite entries or add
+//! sections without rebuilding the binary.
+//!
+//! Plugins do not import anything from the host, they export:
+//!
+//! - `memory`, the memory shared with the host
+//! - `changelog_api_version() -> i32`, returning [`API_VERSION`]
+//! - `alloc(len: i32) -> i32`, returning the address of `len` bytes which the
+//!   host writes the input to
+//!
+//! and any of the following functions, taking the address and the length of a
+//! JSON input:
+//!
+//! - `classify(ptr: i32, len: i32) -> i64`, receives
+//!   `{"repository": ..., "section": ..., "commit": ...}` and returns the
+//!   title of the section of the commit
+//! - `rewrite(ptr: i32, len: i32) -> i64`, receives the same input and returns
+//!   the commit to render, or -1 to drop it
+//! - `sections(ptr: i32, len: i32) -> i64`, receives
+//!   `{"repository": ..., "tag": ...}` once every commit of the tag has been
+//!   read and returns an object mapping the titles of new sections to their
+//!   commits
+//! - `lint(ptr: i32, len: i32) -> i64`, receives the commit checked by the
+//!   lint subcommand (see [`crate::rules::Candidate`]) and returns an array of
+//!   the reasons why it breaks the rules of the plugin, 0 accepts the commit
+//!
+//! The output is returned as its address shifted by 32 bits or-ed with its
+//! length, 0 keeps the entry unchanged.
+//!
+//! Each call is given the fuel configured by `plugin_fuel`, so that a plugin
+//! looping endlessly traps instead of blocking the generation.
+use std::{
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+use serde_json::json;
+use slog_scope::{debug, error};
+use wasmi::{core::TrapCode, Config, Engine, Linker, Memory, Module, Store, TypedFunc};
+
+use crate::{
+    hooks::{Hooks, Verdict},
+    parser::{Commit, Tag},
+};
+
+/// Version of the host API expected from the plugins
+pub const API_VERSION: i32 = 1;
+
+/// Output of a function exported by a plugin
+enum Output {
+    Unchanged,
+    Drop,
+    Value(Vec<u8>),
+}
+
+/// Function exported by a plugin, taking the address and length of its input
+type Export = TypedFunc<(i32, i32), i64>;
+
+/// Plugin loaded from a WebAssembly module
+pub struct Plugin {
+    path: PathBuf,
+    fuel: u64,
+    store: Store<()>,
+    memory: Memory,
+    alloc: TypedFunc<i32, i32>,
+    classify: Option<Export>,
+    rewrite: Option<Export>,
+    sections: Option<Export>,
+    lint: Option<Export>,
+}
+
+impl Plugin {
+    /// Load and instantiate the WebAssembly module at the given path, each
+    /// call is given the fuel
+    pub fn load(path: &Path, fuel: u64) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let wasm =
+            fs::read(path).map_err(|err| format!("could not read file '{:?}', {}", path, err))?;
+
+        let mut config = Config::default();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config);
+        let module = Module::new(&engine, &wasm)
+            .map_err(|err| format!("could not compile plugin '{:?}', {}", path, err))?;
+
+        let mut store = Store::new(&engine, ());
+        store
+            .set_fuel(fuel)
+            .map_err(|err| format!("could not fuel plugin '{:?}', {}", path, err))?;
+
+        let instance = Linker::<()>::new(&engine)
+            .instantiate(&mut store, &module)
+            .and_then(|instance| instance.start(&mut store))
+            .map_err(|err| format!("could not instantiate plugin '{:?}', {}", path, err))?;
+
+        let version = instance
+            .get_typed_func::<(), i32>(&store, "changelog_api_version")
+            .and_then(|func| func.call(&mut store, ()))
+            .map_err(|err| {
+                format!(
+                    "could not retrieve the api version of plugin '{:?}', {}",
+                    path, err
+                )
+            })?;
+
+        if version != API_VERSION {
+            return Err(format!(
+                "could not load plugin '{:?}', it targets version {} of the api instead of {}",
+                path, version, API_VERSION
+            )
+            .into());
+        }
+
+        let memory = instance.get_memory(&store, "memory").ok_or_else(|| {
+            format!(
+                "could not load plugin '{:?}', it does not export its memory",
+                path
+            )
+        })?;
+
+        let alloc = instance
+            .get_typed_func::<i32, i32>(&store, "alloc")
+            .map_err(|err| format!("could not load plugin '{:?}', {}", path, err))?;
+
+        let export = |name| {
+            instance
+                .get_typed_func::<(i32, i32), i64>(&store, name)
+                .ok()
+        };
+        let (classify, rewrite, sections, lint) = (
+            export("classify"),
+            export("rewrite"),
+            export("sections"),
+            export("lint"),
+        );
+
+        debug!("Load plugin"; "path" => path.display().to_string(), "classify" => classify.is_some(), "rewrite" => rewrite.is_some(), "sections" => sections.is_some(), "lint" => lint.is_some());
+        Ok(Self {
+            path: path.to_owned(),
+            fuel,
+            store,
+            memory,
+            alloc,
+            classify,
+            rewrite,
+            sections,
+            lint,
+        })
+    }
+
+    /// Returns true if the plugin exports the `lint` function
+    pub fn lints(&self) -> bool {
+        self.lint.is_some()
+    }
+
+    /// Returns the reasons why the commit breaks the rules of the plugin
+    pub fn lint<T: Serialize>(
+        &mut self,
+        candidate: &T,
+    ) -> Result<Vec<String>, Box<dyn Error + Send + Sync>> {
+        let func = self.lint.ok_or("the plugin does not export lint")?;
+
+        match self.call(func, candidate)? {
+            Output::Unchanged => Ok(vec![]),
+            Output::Drop => Err("could not read the reasons, -1 is not expected".into()),
+            Output::Value(output) => serde_json::from_slice(&output)
+                .map_err(|err| format!("could not parse the reasons, {}", err).into()),
+        }
+    }
+
+    /// Call the function with the given input serialized as json
+    fn call<T: Serialize>(
+        &mut self,
+        func: Export,
+        input: &T,
+    ) -> Result<Output, Box<dyn Error + Send + Sync>> {
+        let input = serde_json::to_vec(input)
+            .map_err(|err| format!("could not serialize the input, {}", err))?;
+
+        self.store
+            .set_fuel(self.fuel)
+            .map_err(|err| format!("could not fuel the plugin, {}", err))?;
+
+        let ptr = self
+            .alloc
+            .call(&mut self.store, input.len() as i32)
+            .map_err(|err| self.trap(err))?;
+        self.memory
+            .write(&mut self.store, ptr as u32 as usize, &input)
+            .map_err(|err| format!("could not write the input, {}", err))?;
+
+        let output = match func
+            .call(&mut self.store, (ptr, input.len() as i32))
+            .map_err(|err| self.trap(err))?
+        {
+            0 => return Ok(Output::Unchanged),
+            -1 => return Ok(Output::Drop),
+            output => output as u64,
+        };
+
+        // The range is checked before allocating, the length is up to 4 GiB
+        let (start, len) = ((output >> 32) as usize, (output & 0xffff_ffff) as usize);
+        let size = self.memory.data(&self.store).len();
+        if start.checked_add(len).map_or(true, |end| end > size) {
+            return Err(format!(
+                "could not read the output, {} bytes at {} are out of the {} bytes of memory",
+                len, start, size
+            )
+            .into());
+        }
+
+        let mut buffer = vec![0; len];
+        self.memory
+            .read(&self.store, start, &mut buffer)
+            .map_err(|err| format!("could not read the output, {}", err))?;
+
+        Ok(Output::Value(buffer))
+    }
+
+    /// Returns the reason why a call failed, naming the fuel if it ran out
+    fn trap(&self, err: wasmi::Error) -> String {
+        match err.as_trap_code() {
+            Some(TrapCode::OutOfFuel) => format!(
+                "plugin consumed all of its {} units of fuel, raise plugin_fuel if it is expected",
+                self.fuel
+            ),
+            _ => err.to_string(),
+        }
+    }
+
+    fn on_commit(
+        &mut self,
+        repository: &str,
+        section: &mut String,
+        commit: &mut Commit,
+    ) -> Result<Verdict, Box<dyn Error + Send + Sync>> {
+        if let Some(func) = self.classify {
+            let input = json!({ "repository": repository, "section": section, "commit": commit });
+            if let Output::Value(output) = self.call(func, &input)? {
+                *section = String::from_utf8(output)
+                    .map_err(|err| format!("could not read the section, {}", err))?;
+            }
+        }
+
+        if let Some(func) = self.rewrite {
+            let input = json!({ "repository": repository, "section": section, "commit": commit });
+            match self.call(func, &input)? {
+                Output::Unchanged => {}
+                Output::Drop => return Ok(Verdict::Drop),
+                Output::Value(output) => {
+                    *commit = serde_json::from_slice(&output)
+                        .map_err(|err| format!("could not parse the commit, {}", err))?;
+                }
+            }
+        }
+
+        Ok(Verdict::Keep)
+    }
+
+    fn on_tag(
+        &mut self,
+        repository: &str,
+        tag: &mut Tag,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let func = match self.sections {
+            Some(func) => func,
+            None => return Ok(()),
+        };
+
+        let input = json!({ "repository": repository, "tag": tag });
+        if let Output::Value(output) = self.call(func, &input)? {
+            let sections: Vec<(String, Vec<Commit>)> =
+                serde_json::from_slice::<serde_json::Map<_, _>>(&output)
+                    .and_then(|sections| {
+                        sections
+                            .into_iter()
+                            .map(|(title, commits)| Ok((title, serde_json::from_value(commits)?)))
+                            .collect()
+                    })
+                    .map_err(|err| format!("could not parse the sections, {}", err))?;
+
+            for (title, commits) in sections {
+                tag.commits.entry(title).or_default().extend(commits);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Plugins declared in the configuration, called in the order of their
+/// declaration. Failures of a plugin are logged and leave the entry unchanged.
+#[derive(Default)]
+pub struct Plugins(Vec<Plugin>);
+
+impl Plugins {
+    /// Load the plugins at the given paths, each call is given the fuel
+    pub fn load(paths: &[PathBuf], fuel: u64) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        Ok(Self(
+            paths
+                .iter()
+                .map(|path| Plugin::load(path, fuel))
+                .collect::<Result<_, _>>()?,
+        ))
+    }
+}
+
+impl Hooks for Plugins {
+    fn on_commit_parsed(
+        &mut self,
+        repository: &str,
+        section: &mut String,
+        commit: &mut Commit,
+    ) -> Verdict {
+        for plugin in &mut self.0 {
+            match plugin.on_commit(repository, section, commit) {
+                Ok(Verdict::Keep) => {}
+                Ok(Verdict::Drop) => return Verdict::Drop,
+                Err(err) => {
+                    error!("could not run plugin on commit"; "path" => plugin.path.display().to_string(), "hash" => &commit.short_hash, "error" => err.to_string());
+                }
+            }
+        }
+
+        Verdict::Keep
+    }
+
+    fn on_tag_closed(&mut self, repository: &str, tag: &mut Tag) {
+        for plugin in &mut self.0 {
+            if let Err(err) = plugin.on_tag(repository, tag) {
+                error!("could not run plugin on tag"; "path" => plugin.path.display().to_string(), "tag" => &tag.name, "error" => err.to_string());
+            }
+        }
+    }
+}
@@ -0,0 +1,120 @@
+//! # Progress module
+//!
+//! The progress module draws the phase of the generation (walk, enrich and
+//! render) on the standard error, so that large repositories do not look like
+//! hanging. Nothing is drawn when the standard error is not a terminal.
+use std::{
+    io::stderr,
+    time::{Duration, Instant},
+};
+
+use crossterm::{
+    execute,
+    style::Print,
+    terminal::{Clear, ClearType},
+    tty::IsTty,
+};
+
+use git_changelog::{
+    hooks::{Hooks, Verdict},
+    parser::{Changelog, Commit, Skipped},
+};
+
+/// Frames of the spinner
+const FRAMES: &[char] = &['|', '/', '-', '\\'];
+
+/// Minimum delay between two draws
+const INTERVAL: Duration = Duration::from_millis(100);
+
+/// Progress of the generation, driven by the hooks of the parsing pipeline
+pub struct Progress {
+    enabled: bool,
+    repository: String,
+    phase: &'static str,
+    commits: usize,
+    frame: usize,
+    drawn: Option<Instant>,
+}
+
+impl Progress {
+    /// Returns the progress, which is drawn only if enabled and if the
+    /// standard error is a terminal
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled: enabled && stderr().is_tty(),
+            repository: String::new(),
+            phase: "",
+            commits: 0,
+            frame: 0,
+            drawn: None,
+        }
+    }
+
+    /// Start the given phase of the repository
+    pub fn phase(&mut self, repository: &str, phase: &'static str) {
+        self.repository = repository.to_owned();
+        self.phase = phase;
+        self.commits = 0;
+        self.draw(true);
+    }
+
+    /// Erase the progress, before printing anything else
+    pub fn finish(&mut self) {
+        if self.enabled {
+            let _ = execute!(stderr(), Print('\r'), Clear(ClearType::CurrentLine));
+        }
+    }
+
+    fn walk(&mut self, repository: &str) {
+        let started = self.phase != "walk" || self.repository != repository;
+        if started {
+            self.repository = repository.to_owned();
+            self.phase = "walk";
+            self.commits = 0;
+        }
+
+        self.commits += 1;
+        self.draw(started);
+    }
+
+    fn draw(&mut self, force: bool) {
+        if !self.enabled || (!force && self.drawn.is_some_and(|at| at.elapsed() < INTERVAL)) {
+            return;
+        }
+
+        self.frame = (self.frame + 1) % FRAMES.len();
+        let line = match (self.phase, self.repository.as_str()) {
+            ("walk", repository) => format!("{}: walk, {} commits", repository, self.commits),
+            (phase, "") => phase.to_owned(),
+            (phase, repository) => format!("{}: {}", repository, phase),
+        };
+
+        // The progress is a courtesy, failing to draw it should not fail the run
+        let _ = execute!(
+            stderr(),
+            Print('\r'),
+            Clear(ClearType::CurrentLine),
+            Print(format!("{} {}", FRAMES[self.frame], line))
+        );
+        self.drawn = Some(Instant::now());
+    }
+}
+
+impl Hooks for Progress {
+    fn on_commit_parsed(&mut self, repository: &str, _: &mut String, _: &mut Commit) -> Verdict {
+        self.walk(repository);
+        Verdict::Keep
+    }
+
+    fn on_commit_skipped(&mut self, repository: &str, _: &Skipped) {
+        self.walk(repository);
+    }
+
+    fn on_repository_walked(&mut self, repository: &str) {
+        self.phase(repository, "enrich");
+    }
+
+    fn before_render(&mut self, _: &mut Changelog) {
+        self.phase("", "render");
+    }
+}
@@ -0,0 +1,642 @@
+//! # Renderer module
+//!
+//! The renderer module provides additional output formats of the changelog
+//! and the registry of the formats, which embedders could extend with their
+//! own renderers
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fmt::{self, Display, Formatter},
+    io::Write,
+};
+
+use serde::Serialize;
+
+use askama::Template;
+use chrono::{DateTime, Utc};
+
+use crate::{
+    conf::Layout,
+    i18n,
+    parser::{
+        Changelog, HTMLChangelog, MarkdownChangelog, MarkdownRepository, Repository, UNRELEASED,
+    },
+};
+
+/// Output format of the changelog
+pub trait Renderer {
+    /// Name of the format, as given on the command line
+    fn name(&self) -> &str;
+
+    /// Extension of the rendered file
+    fn extension(&self) -> &str;
+
+    fn render(&self, changelog: &Changelog) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>>;
+}
+
+/// Format rendered by the crate
+struct Builtin {
+    name: &'static str,
+    extension: &'static str,
+    render: fn(Changelog) -> Result<String, Box<dyn Error + Send + Sync>>,
+}
+
+impl Renderer for Builtin {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn extension(&self) -> &str {
+        self.extension
+    }
+
+    fn render(&self, changelog: &Changelog) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+        Ok((self.render)(changelog.to_owned())?.into_bytes())
+    }
+}
+
+/// Renderers available by their name, the default registry holds the formats
+/// of the crate
+pub struct Registry {
+    renderers: Vec<Box<dyn Renderer>>,
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        let builtins = [
+            Builtin {
+                name: "html",
+                extension: "html",
+                render: |changelog| Ok(HTMLChangelog::from(changelog).to_string()),
+            },
+            Builtin {
+                name: "markdown",
+                extension: "md",
+                render: |changelog| Ok(MarkdownChangelog::from(changelog).to_string()),
+            },
+            Builtin {
+                name: "badge",
+                extension: "json",
+                render: |changelog| Ok(BadgeChangelog::try_from(changelog)?.to_string()),
+            },
+            Builtin {
+                name: "ical",
+                extension: "ics",
+                render: |changelog| Ok(ICalChangelog::from(changelog).to_string()),
+            },
+            Builtin {
+                name: "bulk",
+                extension: "ndjson",
+                render: |changelog| Ok(BulkChangelog::from(changelog).to_string()),
+            },
+            Builtin {
+                name: "term",
+                extension: "txt",
+                render: |changelog| Ok(TermChangelog::from(changelog).to_string()),
+            },
+            Builtin {
+                name: "analytics",
+                extension: "json",
+                render: |changelog| Ok(AnalyticsChangelog::from(changelog).to_string()),
+            },
+        ];
+
+        Self {
+            renderers: builtins
+                .into_iter()
+                .map(|builtin| Box::new(builtin) as Box<dyn Renderer>)
+                .collect(),
+        }
+    }
+}
+
+impl Registry {
+    /// Add the renderer, replacing the one having the same name if any
+    pub fn register(&mut self, renderer: Box<dyn Renderer>) {
+        self.renderers
+            .retain(|registered| registered.name() != renderer.name());
+        self.renderers.push(renderer);
+    }
+
+    /// Returns the renderer having the given name, or the only one having the
+    /// given extension (e.g. md), if any
+    pub fn get(&self, name: &str) -> Option<&dyn Renderer> {
+        if let Some(renderer) = self
+            .renderers
+            .iter()
+            .find(|renderer| renderer.name() == name)
+        {
+            return Some(renderer.as_ref());
+        }
+
+        let mut renderers = self
+            .renderers
+            .iter()
+            .filter(|renderer| renderer.extension() == name);
+
+        match (renderers.next(), renderers.next()) {
+            (Some(renderer), None) => Some(renderer.as_ref()),
+            _ => None,
+        }
+    }
+
+    /// Returns the names of the registered renderers
+    pub fn names(&self) -> Vec<&str> {
+        self.renderers
+            .iter()
+            .map(|renderer| renderer.name())
+            .collect()
+    }
+
+    /// Render the changelog using the given format, returns the file
+    /// extension of the format along with the content
+    pub fn render(
+        &self,
+        format: &str,
+        changelog: &Changelog,
+    ) -> Result<(String, Vec<u8>), Box<dyn Error + Send + Sync>> {
+        let renderer = self.get(format).ok_or_else(|| {
+            format!(
+                "could not use the given value for formatting, the format '{}' is not yet implemented",
+                format
+            )
+        })?;
+
+        Ok((
+            renderer.extension().to_string(),
+            renderer.render(changelog)?,
+        ))
+    }
+}
+
+/// Render the changelog using the given format of the default registry,
+/// returns the file extension of the format along with the content
+pub fn render(
+    format: &str,
+    changelog: Changelog,
+) -> Result<(String, String), Box<dyn Error + Send + Sync>> {
+    let (extension, content) = Registry::default().render(format, &changelog)?;
+    let content = String::from_utf8(content)
+        .map_err(|err| format!("could not render the changelog as text, {}", err))?;
+
+    Ok((extension, content))
+}
+
+/// Render the markdown changelog into the writer one repository at a time,
+/// so that the whole output is never held in memory
+pub fn stream<W: Write>(
+    changelog: Changelog,
+    writer: &mut W,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    // Entries grouped by kind mix all repositories, they are rendered at once
+    if changelog.layout == Layout::Kind {
+        return write!(writer, "{}", MarkdownChangelog::from(changelog))
+            .map_err(|err| format!("could not write content, {}", err).into());
+    }
+
+    writeln!(
+        writer,
+        "# {}",
+        i18n::translate(changelog.language, "Changelog")
+    )
+    .map_err(|err| format!("could not write content, {}", err))?;
+
+    let mut previous = None;
+    for repository in &changelog.repositories {
+        let group = repository
+            .group
+            .to_owned()
+            .filter(|group| previous != Some(group));
+        previous = repository.group.as_ref();
+
+        let rendered = MarkdownRepository {
+            repository,
+            group,
+            language: changelog.language,
+            dates: &changelog.dates,
+        }
+        .render()
+        .map_err(|err| format!("could not render repository '{}', {}", repository.name, err))?;
+
+        write!(writer, "\n{}", rendered)
+            .map_err(|err| format!("could not write content, {}", err))?;
+    }
+
+    Ok(())
+}
+
+/// Badge description understood by the shields.io endpoint
+///
+/// See https://shields.io/endpoint
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Badge {
+    pub schema_version: u8,
+    pub label: String,
+    pub message: String,
+    pub color: String,
+}
+
+/// Repository described by the badge, the one selected by the configuration
+/// or the only one of the changelog
+#[derive(Default, Clone, Debug)]
+pub struct BadgeChangelog {
+    pub repository: Option<Repository>,
+}
+
+impl TryFrom<Changelog> for BadgeChangelog {
+    type Error = Box<dyn Error + Send + Sync>;
+
+    fn try_from(changelog: Changelog) -> Result<Self, Self::Error> {
+        let mut repositories = changelog.repositories.into_iter();
+        let repository = match changelog.badge {
+            Some(name) => Some(
+                repositories
+                    .find(|repository| repository.name == name)
+                    .ok_or_else(|| {
+                        format!("could not render the badge, repository '{}' is not in the changelog", name)
+                    })?,
+            ),
+            None => match (repositories.next(), repositories.len()) {
+                (repository, 0) => repository,
+                (_, others) => {
+                    return Err(format!(
+                        "could not render the badge, the changelog holds {} repositories, select one using the badge entry of the configuration",
+                        others + 1
+                    )
+                    .into())
+                }
+            },
+        };
+
+        Ok(Self { repository })
+    }
+}
+
+impl From<&BadgeChangelog> for Badge {
+    fn from(changelog: &BadgeChangelog) -> Self {
+        let latest = changelog
+            .repository
+            .as_ref()
+            .and_then(|repository| repository.tags.iter().find(|tag| tag.name != UNRELEASED));
+
+        let (message, color) = match latest {
+            Some(tag) => {
+                let entries: usize = tag.commits.values().map(Vec::len).sum();
+                (format!("{} ({} entries)", tag.name, entries), "blue")
+            }
+            None => (String::from("unreleased"), "lightgrey"),
+        };
+
+        Self {
+            schema_version: 1,
+            label: String::from("changelog"),
+            message,
+            color: String::from(color),
+        }
+    }
+}
+
+impl Display for BadgeChangelog {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let badge = serde_json::to_string_pretty(&Badge::from(self)).map_err(|_| fmt::Error)?;
+
+        writeln!(f, "{}", badge)
+    }
+}
+
+#[derive(Default, Clone, Debug)]
+pub struct ICalChangelog {
+    pub repositories: Vec<Repository>,
+    /// Time at which the calendar is generated
+    pub stamp: DateTime<Utc>,
+}
+
+impl From<Changelog> for ICalChangelog {
+    fn from(changelog: Changelog) -> Self {
+        Self {
+            repositories: changelog.repositories,
+            stamp: Utc::now(),
+        }
+    }
+}
+
+impl ICalChangelog {
+    /// Escape a text value as described in RFC 5545, section 3.3.11
+    fn escape(value: &str) -> String {
+        value
+            .replace('\\', "\\\\")
+            .replace(';', "\\;")
+            .replace(',', "\\,")
+            .replace('\n', "\\n")
+    }
+
+    /// Write a content line folded at 75 octets as described in RFC 5545,
+    /// section 3.1
+    fn line(f: &mut Formatter<'_>, line: &str) -> fmt::Result {
+        let mut width = 0;
+        for c in line.chars() {
+            if width + c.len_utf8() > 75 {
+                write!(f, "\r\n ")?;
+                width = 1;
+            }
+
+            write!(f, "{}", c)?;
+            width += c.len_utf8();
+        }
+
+        write!(f, "\r\n")
+    }
+}
+
+impl Display for ICalChangelog {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Self::line(f, "BEGIN:VCALENDAR")?;
+        Self::line(f, "VERSION:2.0")?;
+        Self::line(
+            f,
+            &format!(
+                "PRODID:-//{}//{}//EN",
+                env!("CARGO_PKG_NAME"),
+                env!("CARGO_PKG_VERSION")
+            ),
+        )?;
+
+        for repository in &self.repositories {
+            // The pending changes are not released yet, so they have no date
+            for tag in repository.tags.iter().filter(|tag| tag.name != UNRELEASED) {
+                let date = match tag.date() {
+                    Some(date) => date.replace('-', ""),
+                    None => continue,
+                };
+
+                let mut description = String::new();
+                for (kind, commits) in &tag.commits {
+                    description += &format!("{}\n", kind);
+                    for commit in commits {
+                        description += &match commit.author.as_str() {
+                            "" => format!("- {} ({})\n", commit.message, commit.short_hash),
+                            author => format!(
+                                "- {} ({}, {})\n",
+                                commit.message, commit.short_hash, author
+                            ),
+                        };
+                    }
+                }
+
+                Self::line(f, "BEGIN:VEVENT")?;
+                Self::line(
+                    f,
+                    &format!(
+                        "UID:{}@{}",
+                        format!("{}-{}", repository.name, tag.name).replace(' ', "-"),
+                        env!("CARGO_PKG_NAME")
+                    ),
+                )?;
+                Self::line(
+                    f,
+                    &format!("DTSTAMP:{}", self.stamp.format("%Y%m%dT%H%M%SZ")),
+                )?;
+                Self::line(f, &format!("DTSTART;VALUE=DATE:{}", date))?;
+                Self::line(
+                    f,
+                    &format!(
+                        "SUMMARY:{}",
+                        Self::escape(&format!("{} {}", repository.name, tag.name))
+                    ),
+                )?;
+                Self::line(
+                    f,
+                    &format!("DESCRIPTION:{}", Self::escape(description.trim_end())),
+                )?;
+                Self::line(f, "END:VEVENT")?;
+            }
+        }
+
+        Self::line(f, "END:VCALENDAR")
+    }
+}
+
+/// Document indexed for each commit by the bulk export
+#[derive(Serialize, Clone, Debug)]
+pub struct Document<'a> {
+    pub repository: &'a str,
+    pub tag: &'a str,
+    pub section: &'a str,
+    pub kind: &'a str,
+    pub scope: Option<&'a str>,
+    pub hash: &'a str,
+    pub message: &'a str,
+    pub author: &'a str,
+    pub date: &'a str,
+    pub link: Option<&'a str>,
+}
+
+/// Newline delimited json following the elasticsearch and opensearch bulk
+/// api, the index is the one given in the request path.
+///
+/// See https://www.elastic.co/guide/en/elasticsearch/reference/current/docs-bulk.html
+#[derive(Default, Clone, Debug)]
+pub struct BulkChangelog {
+    pub repositories: Vec<Repository>,
+}
+
+impl From<Changelog> for BulkChangelog {
+    fn from(changelog: Changelog) -> Self {
+        Self {
+            repositories: changelog.repositories,
+        }
+    }
+}
+
+impl Display for BulkChangelog {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for repository in &self.repositories {
+            for tag in &repository.tags {
+                for (section, commits) in &tag.commits {
+                    for commit in commits {
+                        let action = serde_json::json!({
+                            "index": { "_id": format!("{}-{}", repository.name, commit.hash) }
+                        });
+
+                        let document = Document {
+                            repository: &repository.name,
+                            tag: &tag.name,
+                            section,
+                            kind: &commit.kind,
+                            scope: commit.scope.as_deref(),
+                            hash: &commit.hash,
+                            message: &commit.message,
+                            author: &commit.author,
+                            date: &commit.date,
+                            link: commit.link.as_deref(),
+                        };
+
+                        writeln!(f, "{}", action)?;
+                        writeln!(
+                            f,
+                            "{}",
+                            serde_json::to_string(&document).map_err(|_| fmt::Error)?
+                        )?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Number of entries of each kind in a release
+#[derive(Serialize, Clone, Debug)]
+pub struct Distribution<'a> {
+    pub tag: &'a str,
+    pub date: Option<&'a str>,
+    pub entries: usize,
+    pub breaking: usize,
+    pub kinds: BTreeMap<&'a str, usize>,
+}
+
+/// Distributions of the releases of a repository, from the oldest to the
+/// most recent one
+#[derive(Serialize, Clone, Debug)]
+pub struct Analytics<'a> {
+    pub repository: &'a str,
+    pub releases: Vec<Distribution<'a>>,
+}
+
+/// Json summarizing the number of entries of each kind per release, in
+/// order to chart the trends of a project (e.g. fixes versus features)
+#[derive(Default, Clone, Debug)]
+pub struct AnalyticsChangelog {
+    pub repositories: Vec<Repository>,
+}
+
+impl From<Changelog> for AnalyticsChangelog {
+    fn from(changelog: Changelog) -> Self {
+        Self {
+            repositories: changelog.repositories,
+        }
+    }
+}
+
+impl Display for AnalyticsChangelog {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let analytics: Vec<_> = self
+            .repositories
+            .iter()
+            .map(|repository| Analytics {
+                repository: &repository.name,
+                releases: repository
+                    .tags
+                    .iter()
+                    .rev()
+                    .map(|tag| {
+                        let mut kinds = BTreeMap::new();
+                        let mut breaking = 0;
+                        for commit in tag.commits.values().flatten() {
+                            *kinds.entry(commit.kind.as_str()).or_insert(0) += 1;
+                            if commit.breaking {
+                                breaking += 1;
+                            }
+                        }
+
+                        Distribution {
+                            tag: &tag.name,
+                            date: tag.date(),
+                            entries: kinds.values().sum(),
+                            breaking,
+                            kinds,
+                        }
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        let content = serde_json::to_string_pretty(&analytics).map_err(|_| fmt::Error)?;
+
+        writeln!(f, "{}", content)
+    }
+}
+
+const BOLD: &str = "\x1b[1m";
+const DIM: &str = "\x1b[2m";
+const UNDERLINE: &str = "\x1b[4m";
+const GREEN: &str = "\x1b[32m";
+const YELLOW: &str = "\x1b[33m";
+const CYAN: &str = "\x1b[36m";
+const RESET: &str = "\x1b[0m";
+
+/// Make the text a hyperlink to the url (OSC 8), terminals not supporting it
+/// display the text only
+fn hyperlink(text: &str, url: Option<&str>) -> String {
+    match url {
+        Some(url) => format!("\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\", url, text),
+        None => text.to_owned(),
+    }
+}
+
+/// Changelog rendered with ansi escape codes, to be displayed in a terminal
+#[derive(Default, Clone, Debug)]
+pub struct TermChangelog {
+    pub repositories: Vec<Repository>,
+}
+
+impl From<Changelog> for TermChangelog {
+    fn from(changelog: Changelog) -> Self {
+        Self {
+            repositories: changelog.repositories,
+        }
+    }
+}
+
+impl Display for TermChangelog {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for repository in &self.repositories {
+            writeln!(f, "{}{}{}{}", BOLD, UNDERLINE, repository.name, RESET)?;
+            if let Some(ref notice) = repository.notice {
+                writeln!(f, "{}{}{}", YELLOW, notice, RESET)?;
+            }
+
+            for tag in &repository.tags {
+                writeln!(f)?;
+                write!(f, "{}{}{}{}", BOLD, YELLOW, tag.name, RESET)?;
+                match tag.verification {
+                    Some(verification) => writeln!(f, " {}", verification.marker())?,
+                    None => writeln!(f)?,
+                }
+
+                for (kind, commits) in &tag.commits {
+                    writeln!(f)?;
+                    writeln!(f, "  {}{}{}{}", BOLD, CYAN, kind, RESET)?;
+
+                    for commit in commits {
+                        write!(
+                            f,
+                            "    {}{}{} {} {}{}{} {}{}{}",
+                            DIM,
+                            hyperlink(&commit.short_hash, commit.link.as_deref()),
+                            RESET,
+                            commit.message,
+                            GREEN,
+                            commit.author,
+                            RESET,
+                            DIM,
+                            commit.date,
+                            RESET
+                        )?;
+
+                        match commit.verification {
+                            Some(verification) => writeln!(f, " {}", verification.marker())?,
+                            None => writeln!(f)?,
+                        }
+                    }
+                }
+            }
+
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
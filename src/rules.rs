@@ -0,0 +1,188 @@
+//! # Rules module
+//!
+//! The rules module checks the commits against the lint rules supplied by the
+//! scripts and the plugins declared in the `lint` table of the configuration,
+//! so that organizations could enforce their own rules (e.g. a ticket required
+//! by the fixes) without forking the linter.
+//!
+//! Scripts are given the `repository`, `hash`, `kind`, `scope`, `subject`,
+//! `message`, `author` and `email` constants of the [`Candidate`]. They
+//! evaluate to the reason why the commit breaks the rule, to an array of
+//! reasons, or to `false`. Anything else accepts the commit.
+//!
+//! ```rhai
+//! if kind == "fix" && !message.contains("JIRA-") {
+//!     return "fixes should reference a JIRA ticket";
+//! }
+//! ```
+//!
+//! Plugins export the `lint` function described in the plugin module.
+use std::{
+    error::Error,
+    path::{Path, PathBuf},
+};
+
+use rhai::{Array, Dynamic, Engine, Scope, AST};
+use serde::Serialize;
+use slog_scope::error;
+
+use crate::{
+    conf::{self, Severity},
+    plugin::Plugin,
+    script,
+};
+
+/// Commit checked against the rules, the hash, the author and the email are
+/// empty when a message file is checked
+#[derive(Serialize, Default, Clone, Debug)]
+pub struct Candidate {
+    pub repository: String,
+    pub hash: String,
+    /// Kind of the commit, empty if it does not follow the convention
+    pub kind: String,
+    pub scope: Option<String>,
+    pub subject: String,
+    /// Full message of the commit
+    pub message: String,
+    pub author: String,
+    pub email: String,
+}
+
+/// Rule broken by a commit
+#[derive(Clone, Debug)]
+pub struct Broken {
+    /// Identifier of the rule (e.g. subject_length), the name of the file of a
+    /// script or a plugin
+    pub rule: String,
+    pub severity: Severity,
+    pub reason: String,
+}
+
+/// Supplier of a rule
+enum Source {
+    Script(AST),
+    Plugin(Box<Plugin>),
+}
+
+/// Rules declared in the configuration, checked in the order of their
+/// declaration, scripts first. A rule which fails or traps is broken with the
+/// error severity whatever its declared severity, so that a faulty rule never
+/// accepts the commit silently.
+pub struct Rules {
+    engine: Engine,
+    rules: Vec<(PathBuf, Severity, Source)>,
+}
+
+impl Rules {
+    /// Compile the scripts and load the plugins of the lint rules, each call
+    /// of a plugin is given the fuel
+    pub fn load(lint: &conf::Lint, fuel: u64) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let engine = script::engine();
+        let mut rules = vec![];
+
+        for custom in &lint.scripts {
+            let ast = engine
+                .compile_file(custom.path.to_owned())
+                .map_err(|err| format!("could not compile script '{:?}', {}", custom.path, err))?;
+
+            rules.push((custom.path.to_owned(), custom.severity, Source::Script(ast)));
+        }
+
+        for custom in &lint.plugins {
+            let plugin = Plugin::load(&custom.path, fuel)?;
+            if !plugin.lints() {
+                return Err(format!(
+                    "could not load plugin '{:?}', it does not export lint",
+                    custom.path
+                )
+                .into());
+            }
+
+            let source = Source::Plugin(Box::new(plugin));
+            rules.push((custom.path.to_owned(), custom.severity, source));
+        }
+
+        Ok(Self { engine, rules })
+    }
+
+    /// Returns the rules broken by the commit
+    pub fn check(&mut self, candidate: &Candidate) -> Vec<Broken> {
+        let mut broken = vec![];
+
+        for (path, severity, source) in &mut self.rules {
+            let rule = name(path);
+            let reasons = match source {
+                Source::Script(ast) => script(&self.engine, ast, &rule, candidate),
+                Source::Plugin(plugin) => plugin.lint(candidate),
+            };
+
+            match reasons {
+                Ok(reasons) => broken.extend(reasons.into_iter().map(|reason| Broken {
+                    rule: rule.to_owned(),
+                    severity: *severity,
+                    reason,
+                })),
+                Err(err) => {
+                    error!("could not check rule on commit"; "path" => path.display().to_string(), "hash" => &candidate.hash, "error" => err.to_string());
+                    broken.push(Broken {
+                        rule: rule.to_owned(),
+                        severity: Severity::Error,
+                        reason: format!("could not check the rule, {}", err),
+                    });
+                }
+            }
+        }
+
+        broken
+    }
+}
+
+/// Returns the name of the rule supplied by the file at the given path
+fn name(path: &Path) -> String {
+    path.file_stem()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string()
+}
+
+/// Run the script on the commit, returns the reasons it evaluates to
+fn script(
+    engine: &Engine,
+    ast: &AST,
+    rule: &str,
+    candidate: &Candidate,
+) -> Result<Vec<String>, Box<dyn Error + Send + Sync>> {
+    let mut scope = Scope::new();
+    scope.push_constant("repository", candidate.repository.to_owned());
+    scope.push_constant("hash", candidate.hash.to_owned());
+    scope.push_constant("kind", candidate.kind.to_owned());
+    scope.push_constant(
+        "scope",
+        candidate
+            .scope
+            .to_owned()
+            .map(Dynamic::from)
+            .unwrap_or(Dynamic::UNIT),
+    );
+    scope.push_constant("subject", candidate.subject.to_owned());
+    scope.push_constant("message", candidate.message.to_owned());
+    scope.push_constant("author", candidate.author.to_owned());
+    scope.push_constant("email", candidate.email.to_owned());
+
+    let result: Dynamic = engine
+        .eval_ast_with_scope(&mut scope, ast)
+        .map_err(|err| script::failure(&err))?;
+
+    if let Ok(false) = result.as_bool() {
+        return Ok(vec![format!("breaks the rule '{}'", rule)]);
+    }
+
+    if result.is_string() {
+        return Ok(vec![result.to_string()]);
+    }
+
+    match result.try_cast::<Array>() {
+        Some(reasons) => Ok(reasons.iter().map(Dynamic::to_string).collect()),
+        None => Ok(vec![]),
+    }
+}
@@ -0,0 +1,188 @@
+//! # Script module
+//!
+//! The script module runs the Rhai scripts declared in the configuration on
+//! each parsed commit, a lighter alternative to the plugins for ad-hoc rules.
+//!
+//! Scripts are given the `repository` and `hash` constants and the `section`,
+//! `kind`, `scope`, `message`, `author`, `email` and `breaking` variables,
+//! which are read back once the script has run. Changing the kind moves the
+//! commit to the section of the new kind, unless the section has been changed
+//! too. The commit is dropped if the script evaluates to `false`.
+//!
+//! ```rhai
+//! if message.contains("[internal]") {
+//!     return false;
+//! }
+//!
+//! if kind == "perf" {
+//!     kind = "feat";
+//! }
+//! ```
+use std::{collections::HashMap, error::Error, path::PathBuf};
+
+use rhai::{Dynamic, Engine, EvalAltResult, Scope, AST};
+use slog_scope::error;
+
+use crate::{
+    hooks::{Hooks, Verdict},
+    parser::Commit,
+};
+
+/// Maximum number of operations run by a script on a commit
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+/// Maximum depth of the function calls of a script
+const MAX_CALL_LEVELS: usize = 64;
+
+/// Maximum length of the strings built by a script, in bytes
+const MAX_STRING_SIZE: usize = 1024 * 1024;
+
+/// Returns an engine whose limits stop the scripts looping, recursing or
+/// allocating endlessly
+pub fn engine() -> Engine {
+    let mut engine = Engine::new();
+    engine
+        .set_max_operations(MAX_OPERATIONS)
+        .set_max_call_levels(MAX_CALL_LEVELS)
+        .set_max_string_size(MAX_STRING_SIZE);
+
+    engine
+}
+
+/// Returns the reason why a script failed, naming the limit it exceeded if any
+pub fn failure(err: &EvalAltResult) -> String {
+    match err.unwrap_inner() {
+        EvalAltResult::ErrorTooManyOperations(pos) => format!(
+            "script exceeded the limit of {} operations ({})",
+            MAX_OPERATIONS, pos
+        ),
+        EvalAltResult::ErrorStackOverflow(pos) => format!(
+            "script exceeded the limit of {} nested calls ({})",
+            MAX_CALL_LEVELS, pos
+        ),
+        EvalAltResult::ErrorDataTooLarge(_, pos) => format!(
+            "script exceeded the limit of {} bytes per string ({})",
+            MAX_STRING_SIZE, pos
+        ),
+        _ => err.to_string(),
+    }
+}
+
+/// Scripts declared in the configuration, run in the order of their
+/// declaration. Failures of a script are logged and leave the commit unchanged.
+pub struct Scripts {
+    engine: Engine,
+    scripts: Vec<(PathBuf, AST)>,
+    kinds: HashMap<String, String>,
+}
+
+impl Scripts {
+    /// Compile the scripts at the given paths, the kinds are used to retrieve
+    /// the section of a commit whose kind has been changed
+    pub fn load(
+        paths: &[PathBuf],
+        kinds: &HashMap<String, String>,
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let engine = engine();
+        let scripts = paths
+            .iter()
+            .map(|path| {
+                engine
+                    .compile_file(path.to_owned())
+                    .map(|ast| (path.to_owned(), ast))
+                    .map_err(|err| format!("could not compile script '{:?}', {}", path, err))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            engine,
+            scripts,
+            kinds: kinds.to_owned(),
+        })
+    }
+
+    fn run(
+        &self,
+        ast: &AST,
+        repository: &str,
+        section: &mut String,
+        commit: &mut Commit,
+    ) -> Result<Verdict, Box<dyn Error + Send + Sync>> {
+        let mut scope = Scope::new();
+        scope.push_constant("repository", repository.to_string());
+        scope.push_constant("hash", commit.hash.to_owned());
+        scope.push("section", section.to_owned());
+        scope.push("kind", commit.kind.to_owned());
+        scope.push(
+            "scope",
+            commit
+                .scope
+                .to_owned()
+                .map(Dynamic::from)
+                .unwrap_or(Dynamic::UNIT),
+        );
+        scope.push("message", commit.message.to_owned());
+        scope.push("author", commit.author.to_owned());
+        scope.push("email", commit.email.to_owned());
+        scope.push("breaking", commit.breaking);
+
+        let result: Dynamic = self
+            .engine
+            .eval_ast_with_scope(&mut scope, ast)
+            .map_err(|err| failure(&err))?;
+
+        if let Ok(false) = result.as_bool() {
+            return Ok(Verdict::Drop);
+        }
+
+        let string = |name: &str| {
+            scope
+                .get_value::<String>(name)
+                .ok_or_else(|| format!("could not read variable '{}', expected a string", name))
+        };
+
+        let (kind, moved) = (string("kind")?, string("section")?);
+        let (message, author, email) = (string("message")?, string("author")?, string("email")?);
+        let breaking = scope
+            .get_value::<bool>("breaking")
+            .ok_or("could not read variable 'breaking', expected a boolean")?;
+
+        if moved != *section {
+            *section = moved;
+        } else if kind != commit.kind {
+            if let Some(title) = self.kinds.get(&kind) {
+                *section = title.to_owned();
+            }
+        }
+
+        commit.kind = kind;
+        commit.scope = scope.get_value::<String>("scope");
+        commit.message = message;
+        commit.author = author;
+        commit.email = email;
+        commit.breaking = breaking;
+
+        Ok(Verdict::Keep)
+    }
+}
+
+impl Hooks for Scripts {
+    fn on_commit_parsed(
+        &mut self,
+        repository: &str,
+        section: &mut String,
+        commit: &mut Commit,
+    ) -> Verdict {
+        for (path, ast) in &self.scripts {
+            match self.run(ast, repository, section, commit) {
+                Ok(Verdict::Keep) => {}
+                Ok(Verdict::Drop) => return Verdict::Drop,
+                Err(err) => {
+                    error!("could not run script on commit"; "path" => path.display().to_string(), "hash" => &commit.short_hash, "error" => err.to_string());
+                }
+            }
+        }
+
+        Verdict::Keep
+    }
+}
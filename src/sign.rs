@@ -0,0 +1,135 @@
+//! # Sign module
+//!
+//! The sign module provides checksums and detached signatures of the
+//! generated files
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+    fs::{self, File},
+    io::Write,
+    path::{Path, PathBuf},
+    process::Command,
+    str::FromStr,
+};
+
+use sha2::{Digest, Sha256};
+use slog_scope::info;
+
+/// Tool used to produce a detached signature
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Signer {
+    Gpg,
+    Minisign,
+}
+
+impl Signer {
+    pub const VARIANTS: &'static [&'static str] = &["gpg", "minisign"];
+
+    /// Extension appended to the signed file
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Gpg => "asc",
+            Self::Minisign => "minisig",
+        }
+    }
+}
+
+impl FromStr for Signer {
+    type Err = Box<dyn Error + Send + Sync>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "gpg" => Ok(Self::Gpg),
+            "minisign" => Ok(Self::Minisign),
+            signer => Err(format!("the signer '{}' is not yet implemented", signer).into()),
+        }
+    }
+}
+
+impl Display for Signer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Gpg => write!(f, "gpg"),
+            Self::Minisign => write!(f, "minisign"),
+        }
+    }
+}
+
+/// Append the given extension to the path, keeping the existing one
+pub fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(".");
+    path.push(suffix);
+    PathBuf::from(path)
+}
+
+/// Write a `<file>.sha256` file next to the given one, using the format
+/// understood by `sha256sum --check`
+pub fn checksum(path: &Path) -> Result<PathBuf, Box<dyn Error + Send + Sync>> {
+    let content =
+        fs::read(path).map_err(|err| format!("could not read file '{:?}', {}", path, err))?;
+
+    let digest = Sha256::digest(&content);
+    let name = path
+        .file_name()
+        .ok_or_else(|| format!("could not retrieve file name of '{:?}'", path))?
+        .to_string_lossy();
+
+    let destination = with_suffix(path, "sha256");
+    let mut file = File::create(&destination)
+        .map_err(|err| format!("could not create file '{:?}', {}", destination, err))?;
+
+    file.write_all(format!("{:x}  {}\n", digest, name).as_bytes())
+        .map_err(|err| format!("could not write checksum, {}", err))?;
+    file.sync_all()
+        .map_err(|err| format!("could not sync checksum on disk, {}", err))?;
+
+    info!("Write checksum"; "path" => destination.to_string_lossy().to_string());
+    Ok(destination)
+}
+
+/// Produce a detached signature of the given file using the signer, the key
+/// is the one configured by default for the tool unless one is provided
+pub fn sign(
+    path: &Path,
+    signer: Signer,
+    key: Option<&str>,
+) -> Result<PathBuf, Box<dyn Error + Send + Sync>> {
+    let (destination, mut command) = match signer {
+        Signer::Gpg => {
+            let destination = with_suffix(path, signer.extension());
+            let mut command = Command::new("gpg");
+
+            command.args(["--batch", "--yes", "--armor", "--detach-sign"]);
+            if let Some(key) = key {
+                command.args(["--local-user", key]);
+            }
+
+            command.arg("--output").arg(&destination).arg(path);
+            (destination, command)
+        }
+        Signer::Minisign => {
+            let destination = with_suffix(path, signer.extension());
+            let mut command = Command::new("minisign");
+
+            command.arg("-S");
+            if let Some(key) = key {
+                command.args(["-s", key]);
+            }
+
+            command.arg("-x").arg(&destination).arg("-m").arg(path);
+            (destination, command)
+        }
+    };
+
+    let status = command
+        .status()
+        .map_err(|err| format!("could not execute '{}', {}", signer, err))?;
+
+    if !status.success() {
+        return Err(format!("could not sign file '{:?}', '{}' {}", path, signer, status).into());
+    }
+
+    info!("Write signature"; "path" => destination.to_string_lossy().to_string(), "signer" => signer.to_string());
+    Ok(destination)
+}
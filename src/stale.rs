@@ -0,0 +1,59 @@
+//! # Stale module
+//!
+//! The stale module compares the generated changelog with the one written on
+//! disk, in order to keep the committed changelog up to date.
+use std::{error::Error, fs, path::Path};
+
+use similar::{ChangeTag, TextDiff};
+
+use crate::parser;
+
+/// Maximum number of lines of the diff displayed
+const MAX_LINES: usize = 40;
+
+/// Compare the content with the file at the given path and returns an error
+/// describing the differences if they are not the same
+pub fn check(path: &Path, content: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let current = fs::read_to_string(path)
+        .map_err(|err| format!("could not read file '{:?}', {}", path, err))?;
+
+    // The changelog may be checked out with CRLF line endings (core.autocrlf)
+    let current = parser::newlines(&current);
+
+    if current == content {
+        println!("Changelog {:?} is up to date", path);
+        return Ok(());
+    }
+
+    let diff = TextDiff::from_lines(current.as_str(), content);
+    let (mut insertions, mut deletions) = (0, 0);
+    for change in diff.iter_all_changes() {
+        match change.tag() {
+            ChangeTag::Insert => insertions += 1,
+            ChangeTag::Delete => deletions += 1,
+            ChangeTag::Equal => {}
+        }
+    }
+
+    let name = path.to_string_lossy();
+    let unified = diff
+        .unified_diff()
+        .context_radius(2)
+        .header(&name, "generated")
+        .to_string();
+
+    for line in unified.lines().take(MAX_LINES) {
+        eprintln!("{}", line);
+    }
+
+    let lines = unified.lines().count();
+    if lines > MAX_LINES {
+        eprintln!("... {} more lines", lines - MAX_LINES);
+    }
+
+    Err(format!(
+        "changelog {:?} is stale, {} line(s) to add and {} line(s) to remove",
+        path, insertions, deletions
+    )
+    .into())
+}
@@ -0,0 +1,146 @@
+//! # Summary module
+//!
+//! The summary module prints what has been done once the changelog has been
+//! generated on the standard error, so that it does not mix with a changelog
+//! written on the standard output, colored when it is a terminal.
+use std::{io::stderr, path::PathBuf};
+
+use crossterm::{
+    style::{style, Stylize},
+    tty::IsTty,
+};
+
+use git_changelog::parser::{Changelog, Skip};
+
+/// File which would be written, along with its size when known
+pub type Planned = (PathBuf, Option<usize>);
+
+/// Counters of the history of a repository
+#[derive(Default, Clone, Debug)]
+struct Counters {
+    name: String,
+    walked: usize,
+    entries: usize,
+    tags: usize,
+    merges: usize,
+    unparseable: usize,
+    unknown: usize,
+    filtered: usize,
+}
+
+/// Summary of the generation, printed once the outputs are written
+#[derive(Default, Clone, Debug)]
+pub struct Summary {
+    repositories: Vec<Counters>,
+}
+
+impl From<&Changelog> for Summary {
+    fn from(changelog: &Changelog) -> Self {
+        let repositories = changelog
+            .repositories
+            .iter()
+            .map(|repository| {
+                let mut counters = Counters {
+                    name: repository.name.to_owned(),
+                    entries: repository.tags.iter().map(|tag| tag.count()).sum(),
+                    tags: repository.tags.len(),
+                    ..Default::default()
+                };
+
+                for skipped in &repository.skipped {
+                    match skipped.reason {
+                        Skip::Merge => counters.merges += 1,
+                        Skip::Unparseable => counters.unparseable += 1,
+                        Skip::UnknownKind(_) => counters.unknown += 1,
+                        Skip::Filtered(_) => counters.filtered += 1,
+                    }
+                }
+
+                counters.walked = counters.entries + repository.skipped.len();
+                counters
+            })
+            .collect();
+
+        Self { repositories }
+    }
+}
+
+impl Summary {
+    /// Returns the number of commits skipped because they do not follow the
+    /// convention
+    pub fn warnings(&self) -> usize {
+        self.repositories
+            .iter()
+            .map(|repository| repository.unparseable + repository.unknown)
+            .sum()
+    }
+
+    /// Print the summary of each repository followed by the written files
+    pub fn print(&self, outputs: &[PathBuf]) {
+        let colored = stderr().is_tty();
+        self.repositories(colored);
+
+        let wrote = if colored {
+            style("Wrote").green().to_string()
+        } else {
+            String::from("Wrote")
+        };
+
+        for output in outputs {
+            eprintln!("{} {}", wrote, output.display());
+        }
+    }
+
+    /// Print the summary of each repository followed by the files which would
+    /// be written, along with their size when known
+    pub fn plan(&self, outputs: &[Planned]) {
+        let colored = stderr().is_tty();
+        self.repositories(colored);
+
+        let would = if colored {
+            style("Would write").yellow().to_string()
+        } else {
+            String::from("Would write")
+        };
+
+        for (output, size) in outputs {
+            match size {
+                Some(size) => eprintln!("{} {} ({} bytes)", would, output.display(), size),
+                None => eprintln!("{} {}", would, output.display()),
+            }
+        }
+    }
+
+    fn repositories(&self, colored: bool) {
+        // Counters worth a look are highlighted, the zero ones are dimmed
+        let count = |value: usize, warning: bool| match (colored, value, warning) {
+            (false, _, _) => value.to_string(),
+            (true, 0, _) => style(value).dim().to_string(),
+            (true, _, true) => style(value).yellow().to_string(),
+            (true, _, false) => value.to_string(),
+        };
+
+        for repository in &self.repositories {
+            let name = if colored {
+                style(&repository.name).bold().to_string()
+            } else {
+                repository.name.to_owned()
+            };
+
+            eprintln!("{}", name);
+            eprintln!(
+                "  {} commits walked, {} entries in {} tags",
+                count(repository.walked, false),
+                count(repository.entries, false),
+                count(repository.tags, false)
+            );
+            eprintln!(
+                "  skipped: {} merge, {} unparseable, {} unknown kind, {} filtered",
+                count(repository.merges, false),
+                count(repository.unparseable, true),
+                count(repository.unknown, true),
+                count(repository.filtered, false)
+            );
+        }
+    }
+}
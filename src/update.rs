@@ -0,0 +1,302 @@
+//! # Update module
+//!
+//! The update module prepends the new releases to an existing markdown
+//! changelog instead of regenerating the whole file, the sections already
+//! written are kept untouched.
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    fs,
+    io::ErrorKind,
+    path::Path,
+};
+
+use crate::{
+    conf::Layout,
+    i18n, keepachangelog,
+    parser::{Changelog, MarkdownChangelog, UNRELEASED},
+    verify::Verification,
+};
+
+/// Returns the name of a release heading, without the dates of its first
+/// and last commits nor the verification of its tag if any (e.g.
+/// `v1.3.0 (2024-02-01 → 2024-03-15) (✅ verified)`)
+fn release(heading: &str) -> &str {
+    let mut heading = heading.trim();
+
+    // The verification follows the dates, its label may be translated
+    let markers = [
+        Verification::Verified,
+        Verification::Unverified,
+        Verification::Unsigned,
+    ];
+    for marker in markers.iter().map(Verification::marker) {
+        if let Some(idx) = heading.rfind(&format!(" ({} ", marker)) {
+            if heading.ends_with(')') {
+                heading = heading[..idx].trim_end();
+                break;
+            }
+        }
+    }
+
+    match heading.rfind(" (") {
+        Some(idx) if heading.ends_with(')') => &heading[..idx],
+        _ => heading,
+    }
+}
+
+/// Returns the versions already written in the changelog for each repository
+pub fn versions(content: &str) -> HashMap<String, HashSet<String>> {
+    let mut versions: HashMap<String, HashSet<String>> = HashMap::new();
+    let mut repository = None;
+
+    for line in content.lines() {
+        if let Some(name) = line.strip_prefix("## ") {
+            repository = Some(name.trim().to_string());
+            versions.entry(name.trim().to_string()).or_default();
+        } else if let (Some(name), Some(ref repository)) = (line.strip_prefix("### "), &repository)
+        {
+            versions
+                .entry(repository.to_owned())
+                .or_default()
+                .insert(release(name).to_string());
+        }
+    }
+
+    versions
+}
+
+/// Returns the content without the unreleased sections, which are
+/// regenerated on each update, the label is the translated one
+fn strip_unreleased(content: &str, unreleased: &str) -> String {
+    let mut stripped = String::new();
+    let mut skipping = false;
+
+    for line in content.split_inclusive('\n') {
+        if line.starts_with("### ") || line.starts_with("## ") {
+            skipping = line
+                .strip_prefix("### ")
+                .is_some_and(|name| release(name) == unreleased);
+        }
+
+        if !skipping {
+            stripped.push_str(line);
+        }
+    }
+
+    stripped
+}
+
+/// Returns the body of each repository of the rendered changelog, which is
+/// everything written below its heading
+fn bodies(rendered: &str) -> Vec<(String, String)> {
+    let mut bodies: Vec<(String, String)> = vec![];
+
+    let mut inside = false;
+    for line in rendered.split_inclusive('\n') {
+        if let Some(name) = line.strip_prefix("## ") {
+            bodies.push((name.trim().to_string(), String::new()));
+            inside = true;
+        } else if line.starts_with("# ") {
+            // Headings of groups end the body of the previous repository
+            inside = false;
+        } else if let (true, Some((_, body))) = (inside, bodies.last_mut()) {
+            body.push_str(line);
+        }
+    }
+
+    bodies
+}
+
+/// Prepend the releases of the changelog which are not yet written in the
+/// existing content, just below the heading of their repository
+pub fn update(existing: &str, mut changelog: Changelog) -> String {
+    let versions = versions(existing);
+    for repository in &mut changelog.repositories {
+        if let Some(written) = versions.get(&repository.name) {
+            repository
+                .tags
+                .retain(|tag| tag.name == UNRELEASED || !written.contains(&tag.name));
+        }
+    }
+
+    let unreleased = i18n::translate(changelog.language, UNRELEASED);
+    let rendered = MarkdownChangelog::from(changelog).to_string();
+    let mut bodies = bodies(&rendered);
+    let existing = strip_unreleased(existing, &unreleased);
+
+    let mut content = String::new();
+    let mut lines = existing.split_inclusive('\n').peekable();
+    while let Some(line) = lines.next() {
+        content.push_str(line);
+
+        let name = match line.strip_prefix("## ") {
+            Some(name) => name.trim(),
+            None => continue,
+        };
+
+        let idx = match bodies.iter().position(|(n, _)| n == name) {
+            Some(idx) => idx,
+            None => continue,
+        };
+
+        let (_, body) = bodies.remove(idx);
+        let body = body.trim_matches('\n');
+        if body.is_empty() {
+            continue;
+        }
+
+        // Blank lines below the heading are replaced by the new sections
+        while lines.peek().is_some_and(|line| line.trim().is_empty()) {
+            lines.next();
+        }
+
+        content.push('\n');
+        content.push_str(body);
+        content.push_str("\n\n\n");
+    }
+
+    // Repositories which are not yet in the changelog are appended
+    for (name, body) in bodies {
+        if !content.ends_with('\n') {
+            content.push('\n');
+        }
+
+        content.push_str(&format!("\n## {}\n\n{}\n", name, body.trim_matches('\n')));
+    }
+
+    content
+}
+
+/// Read the changelog at the given path and prepend the new releases, the
+/// whole changelog is rendered if the file does not exist yet. Changelogs
+/// following the Keep a Changelog format are merged instead.
+pub fn render(path: &Path, changelog: Changelog) -> Result<String, Box<dyn Error + Send + Sync>> {
+    // Releases could not be prepended once their entries are spread across kinds
+    if changelog.layout == Layout::Kind {
+        return Err(
+            "could not update a changelog grouped by kind, use the repository layout".into(),
+        );
+    }
+
+    match fs::read_to_string(path) {
+        Ok(existing) if keepachangelog::detect(&existing) => {
+            Ok(keepachangelog::update(&existing, changelog))
+        }
+        Ok(existing) => Ok(update(&existing, changelog)),
+        Err(err) if err.kind() == ErrorKind::NotFound => {
+            Ok(MarkdownChangelog::from(changelog).to_string())
+        }
+        Err(err) => Err(format!("could not read file '{:?}', {}", path, err).into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::parser::{commit, Repository, Tag};
+
+    /// Returns a changelog of the repositories, each tag holding one fix
+    fn changelog(repositories: &[(&str, &[&str])]) -> Changelog {
+        let repositories = repositories
+            .iter()
+            .map(|(name, tags)| {
+                let mut repository = Repository::from(name.to_string());
+                for (idx, tag) in tags.iter().enumerate() {
+                    let hash = format!("{:07}{}", idx + 1, name);
+                    let message = format!("fix: change of {}", tag);
+                    let commits = BTreeMap::from([(
+                        String::from("Bug Fixes"),
+                        vec![commit(&hash, "fix", &message, "2024-01-01")],
+                    )]);
+                    repository.tags.push(Tag::from((tag.to_string(), commits)));
+                }
+
+                repository
+            })
+            .collect();
+
+        Changelog {
+            repositories,
+            ..Changelog::default()
+        }
+    }
+
+    #[test]
+    fn release_strips_the_dates_and_the_verification() {
+        assert_eq!(release("v1.0.0"), "v1.0.0");
+        assert_eq!(release(" v1.0.0 (2024-01-01) "), "v1.0.0");
+        assert_eq!(release("v1.3.0 (2024-02-01 → 2024-03-15)"), "v1.3.0");
+
+        let heading = format!(
+            "v1.3.0 (2024-02-01 → 2024-03-15) ({} verified)",
+            Verification::Verified.marker()
+        );
+        assert_eq!(release(&heading), "v1.3.0");
+
+        let heading = format!("v1.3.0 ({} non signé)", Verification::Unsigned.marker());
+        assert_eq!(release(&heading), "v1.3.0");
+    }
+
+    #[test]
+    fn versions_are_listed_by_repository() {
+        let content = "# Changelog\n\n## api\n\n### v1.1.0 (2024-02-01)\n\n#### Features\n\n### v1.0.0\n\n## web\n";
+
+        let versions = versions(content);
+
+        assert_eq!(
+            versions.get("api"),
+            Some(&HashSet::from([
+                String::from("v1.1.0"),
+                String::from("v1.0.0")
+            ]))
+        );
+        assert_eq!(versions.get("web"), Some(&HashSet::new()));
+    }
+
+    #[test]
+    fn update_prepends_the_new_releases_only() {
+        let existing = "# Changelog\n\n## api\n\n### v1.0.0\n\nHand written notes\n";
+        let changelog = changelog(&[("api", &[UNRELEASED, "v1.1.0", "v1.0.0"])]);
+
+        let content = update(existing, changelog);
+
+        let unreleased = content
+            .find(&format!("### {}", UNRELEASED))
+            .expect("unreleased to be written");
+        let fresh = content
+            .find("### v1.1.0")
+            .expect("new release to be written");
+        let written = content.find("### v1.0.0").expect("release to be kept");
+        assert!(unreleased < fresh && fresh < written);
+        assert_eq!(content.matches("### v1.0.0").count(), 1);
+        assert!(content.contains("fix: change of v1.1.0"));
+        assert!(!content.contains("fix: change of v1.0.0"));
+        assert!(content.ends_with("### v1.0.0\n\nHand written notes\n"));
+    }
+
+    #[test]
+    fn update_regenerates_the_unreleased_section() {
+        let changelog = changelog(&[("api", &[UNRELEASED, "v1.0.0"])]);
+        let existing = update("# Changelog\n\n## api\n", changelog.to_owned());
+
+        let content = update(&existing, changelog);
+
+        assert_eq!(content, existing);
+        assert_eq!(content.matches(&format!("### {}", UNRELEASED)).count(), 1);
+    }
+
+    #[test]
+    fn update_appends_the_new_repositories() {
+        let existing = "# Changelog\n\n## api\n\n### v1.0.0\n";
+        let changelog = changelog(&[("api", &["v1.0.0"]), ("web", &["v0.1.0"])]);
+
+        let content = update(existing, changelog);
+
+        assert!(content.starts_with(existing));
+        let web = content.find("## web").expect("repository to be appended");
+        assert!(content[web..].contains("### v0.1.0"));
+    }
+}
@@ -0,0 +1,214 @@
+//! # Verify module
+//!
+//! The verify module checks the signatures of the commits and the annotated
+//! tags, using gpg or ssh-keygen depending on the kind of the signature, so
+//! that the changelog tells which changes come from trusted keys.
+use std::{
+    env,
+    error::Error,
+    fs,
+    io::Write,
+    path::PathBuf,
+    process::{self, Command, Stdio},
+};
+
+use git2 as git;
+use serde::{Deserialize, Serialize};
+use slog_scope::warn;
+
+/// Header of the signatures made using ssh-keygen
+const SSH: &[u8] = b"-----BEGIN SSH SIGNATURE-----";
+
+/// Header of the signatures made using gpg
+const PGP: &[u8] = b"-----BEGIN PGP SIGNATURE-----";
+
+/// Outcome of the verification of a commit or a tag
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Verification {
+    /// The signature is good and made by a trusted key
+    Verified,
+    /// The signature is bad or made by an unknown key
+    Unverified,
+    Unsigned,
+}
+
+impl Verification {
+    /// Returns the label of the outcome, to be translated
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Verified => "verified",
+            Self::Unverified => "unverified",
+            Self::Unsigned => "unsigned",
+        }
+    }
+
+    /// Returns the marker displayed along with the label
+    pub fn marker(&self) -> &'static str {
+        match self {
+            Self::Verified => "✅",
+            Self::Unverified => "❌",
+            Self::Unsigned => "⚠️",
+        }
+    }
+}
+
+/// Verifier of the signatures of a repository, ssh signatures are checked
+/// against the allowed signers configured for git
+pub struct Verifier {
+    allowed: Option<PathBuf>,
+}
+
+impl From<&git::Repository> for Verifier {
+    fn from(repo: &git::Repository) -> Self {
+        let allowed = repo
+            .config()
+            .and_then(|config| config.get_path("gpg.ssh.allowedSignersFile"))
+            .ok();
+
+        Self { allowed }
+    }
+}
+
+impl Verifier {
+    /// Verify the signature of the commit
+    pub fn commit(&self, repo: &git::Repository, commit: &git::Commit<'_>) -> Verification {
+        let (signature, data) = match repo.extract_signature(&commit.id(), None) {
+            Ok(extracted) => extracted,
+            Err(err) if err.code() == git::ErrorCode::NotFound => return Verification::Unsigned,
+            Err(err) => {
+                warn!("could not extract signature"; "hash" => commit.id().to_string(), "error" => err.to_string());
+                return Verification::Unverified;
+            }
+        };
+
+        let email = String::from_utf8_lossy(commit.committer().email_bytes()).to_string();
+        self.check(&commit.id(), &signature, &data, &email)
+    }
+
+    /// Verify the signature of the tag with the given name, lightweight tags
+    /// could not be signed
+    pub fn tag(&self, repo: &git::Repository, name: &str) -> Verification {
+        let tag = match repo
+            .find_reference(&format!("refs/tags/{}", name))
+            .and_then(|reference| reference.peel_to_tag())
+        {
+            Ok(tag) => tag,
+            Err(_) => return Verification::Unsigned,
+        };
+
+        let raw = match repo
+            .odb()
+            .and_then(|odb| odb.read(tag.id()).map(|object| object.data().to_vec()))
+        {
+            Ok(raw) => raw,
+            Err(err) => {
+                warn!("could not read tag"; "tag" => name, "error" => err.to_string());
+                return Verification::Unverified;
+            }
+        };
+
+        // The signature of a tag is appended to its message
+        let idx = match [SSH, PGP]
+            .iter()
+            .filter_map(|header| find(&raw, header))
+            .max()
+        {
+            Some(idx) => idx,
+            None => return Verification::Unsigned,
+        };
+
+        let email = tag
+            .tagger()
+            .map(|tagger| String::from_utf8_lossy(tagger.email_bytes()).to_string())
+            .unwrap_or_default();
+
+        self.check(&tag.id(), &raw[idx..], &raw[..idx], &email)
+    }
+
+    /// Check the signature of the data, signed by the given principal
+    fn check(
+        &self,
+        oid: &git::Oid,
+        signature: &[u8],
+        data: &[u8],
+        principal: &str,
+    ) -> Verification {
+        match self.run(oid, signature, data, principal) {
+            Ok(true) => Verification::Verified,
+            Ok(false) => Verification::Unverified,
+            Err(err) => {
+                warn!("could not verify signature"; "hash" => oid.to_string(), "error" => err.to_string());
+                Verification::Unverified
+            }
+        }
+    }
+
+    /// Run the tool matching the kind of the signature, returns whether the
+    /// signature is good
+    fn run(
+        &self,
+        oid: &git::Oid,
+        signature: &[u8],
+        data: &[u8],
+        principal: &str,
+    ) -> Result<bool, Box<dyn Error + Send + Sync>> {
+        let ssh = signature.starts_with(SSH);
+        let mut command = if ssh {
+            let allowed = self
+                .allowed
+                .as_ref()
+                .ok_or("gpg.ssh.allowedSignersFile is not set in the git configuration")?;
+
+            let mut command = Command::new("ssh-keygen");
+            command
+                .args(["-Y", "verify", "-n", "git", "-f"])
+                .arg(allowed)
+                .args(["-I", principal]);
+            command
+        } else {
+            let mut command = Command::new("gpg");
+            command.args(["--batch", "--status-fd", "1", "--verify"]);
+            command
+        };
+
+        // Both tools read the signature from a file and the data from stdin
+        let path = env::temp_dir().join(format!("git-changelog-{}-{}.sig", process::id(), oid));
+        fs::write(&path, signature)
+            .map_err(|err| format!("could not write signature to '{:?}', {}", path, err))?;
+
+        if ssh {
+            command.arg("-s").arg(&path);
+        } else {
+            command.arg(&path).arg("-");
+        }
+
+        let tool = if ssh { "ssh-keygen" } else { "gpg" };
+        let output = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .and_then(|mut child| {
+                if let Some(mut stdin) = child.stdin.take() {
+                    stdin.write_all(data)?;
+                }
+
+                child.wait_with_output()
+            });
+
+        let _ = fs::remove_file(&path);
+        let output = output.map_err(|err| format!("could not execute '{}', {}", tool, err))?;
+
+        // As done by git, gpg should report the signature as good
+        Ok(output.status.success()
+            && (ssh || String::from_utf8_lossy(&output.stdout).contains("[GNUPG:] GOODSIG")))
+    }
+}
+
+/// Returns the position of the last occurrence of the needle
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .rposition(|window| window == needle)
+}
@@ -0,0 +1,149 @@
+//! # Watch module
+//!
+//! The watch module polls the references and the HEAD of the configured
+//! repositories, so that the changelog is generated again once a commit or a
+//! tag is added while drafting a release. The configuration file and the
+//! files it includes are polled as well, so that it could be reloaded.
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use git2 as git;
+use slog_scope::{info, warn};
+
+use git_changelog::conf::Configuration;
+
+/// Delay between two polls of the repositories
+const INTERVAL: Duration = Duration::from_secs(1);
+
+/// Modification times of the configuration file and of the files it includes
+pub type Stamps = BTreeMap<PathBuf, Option<SystemTime>>;
+
+/// Change noticed by the watcher
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Change {
+    Repositories,
+    Configuration,
+}
+
+/// Watcher of the references of the configured repositories, and of the
+/// configuration file if any
+pub struct Watcher {
+    paths: Vec<PathBuf>,
+    state: BTreeMap<String, String>,
+    config: Option<PathBuf>,
+    stamps: Stamps,
+}
+
+impl Watcher {
+    /// Watch the repositories of the configuration, along with the given
+    /// configuration file
+    pub fn new(conf: &Configuration, config: Option<PathBuf>) -> Self {
+        let stamps = config.as_deref().map(stamps).unwrap_or_default();
+        let mut watcher = Self {
+            paths: vec![],
+            state: BTreeMap::new(),
+            config,
+            stamps,
+        };
+
+        watcher.follow(conf);
+        watcher
+    }
+
+    /// Watch the repositories of the given configuration, once reloaded
+    pub fn follow(&mut self, conf: &Configuration) {
+        self.paths = conf
+            .repositories
+            .iter()
+            .map(|repository| repository.path.to_owned())
+            .collect();
+
+        self.state = state(&self.paths);
+    }
+
+    /// Block until a reference or the HEAD of a repository changes, or the
+    /// configuration changes
+    pub fn wait(&mut self) -> Change {
+        loop {
+            thread::sleep(INTERVAL);
+
+            if let Some(ref config) = self.config {
+                let current = stamps(config);
+                if current != self.stamps {
+                    info!("Configuration changed, reload it");
+                    self.stamps = current;
+                    return Change::Configuration;
+                }
+            }
+
+            let current = state(&self.paths);
+            if current != self.state {
+                info!("Repositories changed, generate the changelog");
+                self.state = current;
+                return Change::Repositories;
+            }
+        }
+    }
+}
+
+/// Returns the modification times of the configuration file and of the files
+/// it includes, only the file itself is known while it could not be loaded
+pub fn stamps(config: &Path) -> Stamps {
+    let files = Configuration::files(config).unwrap_or_else(|_| vec![config.to_owned()]);
+
+    files
+        .into_iter()
+        .map(|path| {
+            let modified = fs::metadata(&path)
+                .and_then(|metadata| metadata.modified())
+                .ok();
+            (path, modified)
+        })
+        .collect()
+}
+
+/// Returns the target of each reference of the repositories, along with
+/// their HEAD
+fn state(paths: &[PathBuf]) -> BTreeMap<String, String> {
+    let mut state = BTreeMap::new();
+    for path in paths {
+        let repo = match git::Repository::discover(path) {
+            Ok(repo) => repo,
+            Err(err) => {
+                warn!("could not watch git repository at '{:?}', {}", path, err);
+                continue;
+            }
+        };
+
+        // HEAD changes on checkouts and commits to a detached HEAD
+        if let Ok(head) = repo.head() {
+            let target = head.target().map(|oid| oid.to_string()).unwrap_or_default();
+            state.insert(
+                format!("{}:HEAD", path.display()),
+                format!("{} {}", head.name().unwrap_or_default(), target),
+            );
+        }
+
+        let references = match repo.references() {
+            Ok(references) => references,
+            Err(err) => {
+                warn!("could not retrieve references of '{:?}', {}", path, err);
+                continue;
+            }
+        };
+
+        for reference in references.flatten() {
+            let target = reference.target().map(|oid| oid.to_string());
+            if let (Some(name), Some(target)) = (reference.name(), target) {
+                state.insert(format!("{}:{}", path.display(), name), target);
+            }
+        }
+    }
+
+    state
+}